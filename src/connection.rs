@@ -0,0 +1,152 @@
+use crate::error::{Result, SQLRiteError};
+use crate::sql::cache::{StatementCache, STATEMENT_CACHE_CAPACITY};
+use crate::sql::db::database::Database;
+use crate::sql::db::value::Value;
+use crate::sql::prepared::PreparedStatement;
+use crate::sql::Output;
+
+/// Path opening a database that only lives in memory, as in SQLite
+pub const MEMORY_PATH: &str = ":memory:";
+
+/// A connection to a database, to run statements on it without going through the REPL.
+/// Nothing is printed, rows are returned as `Rows`.
+#[derive(Debug)]
+pub struct Connection {
+    db: Database,
+    /// Statements prepared before, see `StatementCache`
+    cache: StatementCache,
+}
+
+impl Connection {
+    /// Opens a connection to a new database. Databases are not persisted yet,
+    /// so `path` must be `:memory:`.
+    ///
+    pub fn open(path: &str) -> Result<Connection> {
+        if path != MEMORY_PATH {
+            return Err(SQLRiteError::NotImplemented(format!(
+                "cannot open {}, only {} databases are supported yet",
+                path, MEMORY_PATH
+            )));
+        }
+        Ok(Connection {
+            db: Database::new(String::from("main")),
+            cache: StatementCache::new(STATEMENT_CACHE_CAPACITY),
+        })
+    }
+
+    /// Executes a single statement, `params` bound to its parameters by index.
+    /// Returns the number of rows it inserted, updated or deleted.
+    ///
+    pub fn execute(&mut self, sql: &str, params: &[Value]) -> Result<usize> {
+        self.prepare(sql)?.execute(params)
+    }
+
+    /// Runs a single statement, `params` bound to its parameters by index, and returns
+    /// its rows. Statements returning no rows return no columns either.
+    ///
+    pub fn query(&mut self, sql: &str, params: &[Value]) -> Result<Rows> {
+        self.prepare(sql)?.query(params)
+    }
+
+    /// Prepares a single statement, to be run any number of times with other values
+    /// bound to its parameters. Statements prepared before are not parsed again, nor planned
+    /// again while the tables they read stay the same.
+    ///
+    pub fn prepare(&mut self, sql: &str) -> Result<Statement<'_>> {
+        let statement = self.cache.prepare(sql, &self.db)?;
+        Ok(Statement {
+            connection: self,
+            statement,
+        })
+    }
+}
+
+/// A statement prepared by `Connection::prepare`. Values bound to its parameters are
+/// kept from one run to the next, NULL until bound.
+#[derive(Debug)]
+pub struct Statement<'conn> {
+    connection: &'conn mut Connection,
+    statement: PreparedStatement,
+}
+
+impl Statement<'_> {
+    /// Returns the number of parameters, the largest index of a parameter
+    ///
+    pub fn parameter_count(&self) -> usize {
+        self.statement.parameter_count()
+    }
+
+    /// Returns the index, starting at 1, of the parameter with a name, prefix included
+    ///
+    pub fn parameter_index(&self, name: &str) -> Option<usize> {
+        (1..=self.parameter_count()).find(|&i| self.statement.parameter_name(i) == Some(name))
+    }
+
+    /// Binds a value to the parameter at an index starting at 1
+    ///
+    pub fn bind(&mut self, index: usize, value: impl Into<Value>) -> Result<()> {
+        self.statement.bind(index, value.into())
+    }
+
+    /// Binds a value to the parameter with a name, prefix included, such as `:id`
+    ///
+    pub fn bind_named(&mut self, name: &str, value: impl Into<Value>) -> Result<()> {
+        match self.parameter_index(name) {
+            Some(index) => self.bind(index, value),
+            None => Err(SQLRiteError::General(format!(
+                "no such parameter: {}",
+                name
+            ))),
+        }
+    }
+
+    /// Executes the statement, `params` bound to its first parameters by index.
+    /// Returns the number of rows it inserted, updated or deleted.
+    ///
+    pub fn execute(&mut self, params: &[Value]) -> Result<usize> {
+        Ok(self.run(params)?.changes)
+    }
+
+    /// Runs the statement, `params` bound to its first parameters by index, and returns its rows
+    ///
+    pub fn query(&mut self, params: &[Value]) -> Result<Rows> {
+        let (columns, rows) = match self.run(params)?.rows {
+            Some(result) => (result.columns, result.rows),
+            None => (vec![], vec![]),
+        };
+        Ok(Rows {
+            columns,
+            rows: rows.into_iter(),
+        })
+    }
+
+    fn run(&mut self, params: &[Value]) -> Result<Output> {
+        for (i, value) in params.iter().enumerate() {
+            self.statement.bind(i + 1, value.clone())?;
+        }
+        self.statement.execute(&mut self.connection.db)
+    }
+}
+
+/// The rows returned by a statement, each one a value per column
+#[derive(Debug)]
+pub struct Rows {
+    columns: Vec<String>,
+    rows: std::vec::IntoIter<Vec<Value>>,
+}
+
+impl Rows {
+    /// Returns the names of the columns
+    ///
+    pub fn columns(&self) -> &[String] {
+        &self.columns
+    }
+}
+
+impl Iterator for Rows {
+    type Item = Vec<Value>;
+
+    fn next(&mut self) -> Option<Vec<Value>> {
+        self.rows.next()
+    }
+}
@@ -22,6 +22,21 @@ pub enum SQLRiteError {
     UnknownCommand(String),
     #[error("SQL error: {0:?}")]
     SqlError(#[from] ParserError),
+    #[error("Trigger error: {1}")]
+    Raise(RaiseAction, String),
+}
+
+/// What RAISE() does to the statement that fired the trigger calling it
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum RaiseAction {
+    /// Skips the row the trigger fired for, without failing the statement
+    Ignore,
+    /// Fails the statement and rolls back the open transaction
+    Rollback,
+    /// Fails the statement, undoing every change it made
+    Abort,
+    /// Fails the statement, keeping the changes it made before
+    Fail,
 }
 
 /// Returns SQLRiteError::General error from String
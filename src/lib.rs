@@ -0,0 +1,31 @@
+//! rustdb is a light version of SQLite, usable from its REPL or embedded as a library
+//! through a `Connection`.
+//!
+//! # Examples
+//!
+//! ```
+//! use rustdb::{Connection, Value};
+//!
+//! let mut conn = Connection::open(":memory:").unwrap();
+//! conn.execute("CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT);", &[])
+//!     .unwrap();
+//! let changes = conn
+//!     .execute("INSERT INTO users (id, name) VALUES (?, ?);", &[1.into(), "Jack".into()])
+//!     .unwrap();
+//! assert_eq!(changes, 1);
+//!
+//! let mut rows = conn.query("SELECT name FROM users WHERE id = ?;", &[1.into()]).unwrap();
+//! assert_eq!(rows.columns(), ["name"]);
+//! assert_eq!(rows.next(), Some(vec![Value::Text("Jack".to_string())]));
+//! assert_eq!(rows.next(), None);
+//! ```
+pub mod connection;
+pub mod error;
+pub mod sql;
+
+#[macro_use]
+extern crate prettytable;
+
+pub use connection::{Connection, Rows, Statement};
+pub use error::{Result, SQLRiteError};
+pub use sql::db::value::Value;
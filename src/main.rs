@@ -1,20 +1,19 @@
 // Initalize modules so we can use functions, types, constants in scope
-mod error;
 mod meta_command;
 mod repl;
-mod sql;
+
+// The engine itself lives in the library crate
+use rustdb::{error, sql};
 
 use meta_command::handle_meta_command;
 use repl::{get_command_type, get_config, CommandType, REPLHelper};
+use sql::batch::{execute_batch, OnError, StatementResult};
+use sql::cache::{StatementCache, STATEMENT_CACHE_CAPACITY};
 use sql::db::database::Database;
-use sql::process_command;
 
 use rustyline::error::ReadlineError;
 use rustyline::Editor;
 
-#[macro_use]
-extern crate prettytable;
-
 use clap::{crate_name, crate_version};
 
 fn main() -> rustyline::Result<()> {
@@ -50,6 +49,10 @@ fn main() -> rustyline::Result<()> {
 
     // Creates new database with name and empty hashmap of tables
     let mut db = Database::new("mydb".to_string());
+    // Scripts keep running after a failing statement unless `.bail on` was entered
+    let mut on_error = OnError::Continue;
+    // Statements run again are taken from the cache instead of being parsed again
+    let mut cache = StatementCache::new(STATEMENT_CACHE_CAPACITY);
 
     loop {
         let p = format!("rustdb> ");
@@ -65,17 +68,36 @@ fn main() -> rustyline::Result<()> {
                 // Parsing user's input and returning and enum of repl::CommandType
                 match get_command_type(&command.trim().to_owned()) {
                     CommandType::SQLCommand(_cmd) => {
-                        // process_command takes care of tokenizing, parsing and executing
-                        // the SQL Statement and returning a Result<String, SQLRiteError>
-                        let _ = match process_command(&command, &mut db) {
-                            Ok(response) => println!("{}", response),
+                        // execute_batch splits the input into statements and runs each one
+                        // with process_command, which takes care of tokenizing, parsing and
+                        // executing it and returning a Result<String, SQLRiteError>
+                        // Errors of a script name the statement that failed
+                        let mut report =
+                            |i: usize, count: usize, stmt: &StatementResult| match &stmt.result {
+                                Ok(response) => println!("{}", response),
+                                Err(err) if count == 1 => eprintln!("An error occured: {}", err),
+                                Err(err) => eprintln!(
+                                    "An error occured in statement {} ({}): {}",
+                                    i + 1,
+                                    stmt.statement,
+                                    err
+                                ),
+                            };
+                        match execute_batch(&command, &mut db, &mut cache, on_error, &mut report) {
+                            Ok(batch) if batch.results.len() + batch.skipped > 1 => println!(
+                                "{} statements succeeded, {} failed, {} skipped.",
+                                batch.results.len() - batch.failed(),
+                                batch.failed(),
+                                batch.skipped
+                            ),
+                            Ok(_) => (),
                             Err(err) => eprintln!("An error occured: {}", err),
-                        };
+                        }
                     }
                     CommandType::MetaCommand(cmd) => {
                         // handle_meta_command parses and executes the MetaCommand
                         // and returns a Result<String, SQLRiteError>
-                        let _ = match handle_meta_command(cmd, &mut repl) {
+                        let _ = match handle_meta_command(cmd, &mut repl, &mut db, &mut on_error) {
                             Ok(response) => println!("{}", response),
                             Err(err) => eprintln!("An error occured: {}", err),
                         };
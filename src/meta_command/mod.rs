@@ -1,7 +1,15 @@
 use crate::error::{Result, SQLRiteError};
 
 use crate::repl::REPLHelper;
+use crate::sql::batch::OnError;
+use crate::sql::db::database::Database;
+use crate::sql::db::value::Value;
+use crate::sql::executor::expr::{evaluate, NoRow};
+use crate::sql::prepared::literal;
 use rustyline::Editor;
+use sqlparser::dialect::SQLiteDialect;
+use sqlparser::parser::{Parser, ParserError};
+use sqlparser::tokenizer::{Token, Tokenizer};
 use std::fmt;
 
 #[derive(Debug, PartialEq)]
@@ -9,6 +17,8 @@ pub enum MetaCommand {
     Exit,
     Help,
     Open(String),
+    Bail(String),
+    Parameter(String),
     Unknown,
 }
 
@@ -19,6 +29,8 @@ impl fmt::Display for MetaCommand {
             MetaCommand::Exit => f.write_str(".exit"),
             MetaCommand::Help => f.write_str(".help"),
             MetaCommand::Open(_) => f.write_str(".open"),
+            MetaCommand::Bail(_) => f.write_str(".bail"),
+            MetaCommand::Parameter(_) => f.write_str(".parameter"),
             MetaCommand::Unknown => f.write_str("Unknown command"),
         }
     }
@@ -32,22 +44,33 @@ impl MetaCommand {
             ".exit" => MetaCommand::Exit,
             ".help" => MetaCommand::Help,
             ".open" => MetaCommand::Open(command),
+            ".bail" => MetaCommand::Bail(command),
+            ".parameter" | ".param" => MetaCommand::Parameter(command),
             _ => MetaCommand::Unknown,
         }
     }
 }
 
-pub fn handle_meta_command(command: MetaCommand, repl: &mut Editor<REPLHelper>) -> Result<String> {
+/// Runs a meta command. `on_error` is the setting of `.bail`, telling if a script
+/// stops at the first statement that fails.
+pub fn handle_meta_command(
+    command: MetaCommand,
+    repl: &mut Editor<REPLHelper>,
+    db: &mut Database,
+    on_error: &mut OnError,
+) -> Result<String> {
     match command {
         MetaCommand::Exit => {
             repl.append_history("history").unwrap();
             std::process::exit(0)
         }
         MetaCommand::Help => Ok(format!(
-            "{}{}{}{}{}{}{}{}",
+            "{}{}{}{}{}{}{}{}{}{}",
             "Special commands:\n",
+            ".bail on|off     - Stop after hitting an error. Default OFF\n",
             ".help            - Display this message\n",
             ".open <FILENAME> - Close existing database and reopen FILENAME\n",
+            ".parameter CMD   - Manage values bound to statement parameters: clear, list,\n                   set NAME VALUE, unset NAME\n",
             ".save <FILENAME> - Write in-memory database into FILENAME\n",
             ".read <FILENAME> - Read input from FILENAME\n",
             ".tables          - List names of tables\n",
@@ -55,8 +78,78 @@ pub fn handle_meta_command(command: MetaCommand, repl: &mut Editor<REPLHelper>)
             ".exit            - Quits this application"
         )),
         MetaCommand::Open(args) => Ok(format!("To be implemented: {}", args)),
+        MetaCommand::Bail(args) => {
+            let args: Vec<&str> = args.split_whitespace().collect();
+            *on_error = match args.as_slice() {
+                [_, "on"] => OnError::Stop,
+                [_, "off"] => OnError::Continue,
+                _ => {
+                    return Err(SQLRiteError::UnknownCommand(String::from(
+                        "Usage: .bail on|off",
+                    )))
+                }
+            };
+            Ok(format!("Bail {}.", args[1]))
+        }
+        MetaCommand::Parameter(args) => {
+            let usage = || {
+                SQLRiteError::UnknownCommand(String::from(
+                    "Usage: .parameter clear|list|set NAME VALUE|unset NAME",
+                ))
+            };
+            let mut words = args.splitn(4, char::is_whitespace).skip(1);
+            match (words.next(), words.next(), words.next()) {
+                (Some("clear"), None, None) => {
+                    db.parameters.clear();
+                    Ok(String::from("Parameters cleared."))
+                }
+                (Some("list"), None, None) => Ok(db
+                    .parameters
+                    .iter()
+                    .map(|(name, value)| format!("{} {}", name, literal(value)))
+                    .collect::<Vec<String>>()
+                    .join("\n")),
+                (Some("set"), Some(name), Some(value)) if is_parameter_name(name) => {
+                    let value = parse_value(value)?;
+                    db.parameters.insert(name.to_string(), value);
+                    Ok(format!("Parameter {} set.", name))
+                }
+                (Some("unset"), Some(name), None) => match db.parameters.remove(name) {
+                    Some(_) => Ok(format!("Parameter {} unset.", name)),
+                    None => Err(SQLRiteError::General(format!("no such parameter: {}", name))),
+                },
+                _ => Err(usage()),
+            }
+        }
         MetaCommand::Unknown => Err(SQLRiteError::UnknownCommand(format!(
             "Unknown command or invalid arguments. Enter '.help'"
         ))),
     }
 }
+
+/// Returns true if a name is that of a parameter, `?NNN`, `:name`, `@name` or `$name`
+fn is_parameter_name(name: &str) -> bool {
+    match name.split_at(name.chars().next().map_or(0, char::len_utf8)) {
+        ("?", number) => number.parse::<usize>().is_ok_and(|index| index > 0),
+        (":" | "@" | "$", rest) => !rest.is_empty(),
+        _ => false,
+    }
+}
+
+/// Parses the value of `.parameter set`, a constant SQL expression
+fn parse_value(text: &str) -> Result<Value> {
+    let dialect = SQLiteDialect {};
+    let tokens = Tokenizer::new(&dialect, text)
+        .tokenize()
+        .map_err(ParserError::from)?;
+    let mut parser = Parser::new(tokens, &dialect);
+    let expr = parser.parse_expr()?;
+    let token = parser.peek_token();
+    if token != Token::EOF {
+        return Err(SQLRiteError::SqlError(ParserError::ParserError(format!(
+            "Expected end of value, found: {}",
+            token
+        ))));
+    }
+    evaluate(&expr, &NoRow)
+}
@@ -1,4 +1,5 @@
 use crate::meta_command::*;
+use crate::sql::batch::is_complete;
 use crate::sql::*;
 
 use std::borrow::Cow::{self, Borrowed, Owned};
@@ -72,6 +73,9 @@ impl Validator for REPLHelper {
             Valid(None)
         } else if !input.ends_with(';') {
             Incomplete
+        } else if !is_complete(input) {
+            // The statements of a trigger body end with semicolons too
+            Incomplete
         } else {
             Valid(None)
         };
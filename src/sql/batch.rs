@@ -0,0 +1,171 @@
+use sqlparser::dialect::SQLiteDialect;
+use sqlparser::parser::ParserError;
+use sqlparser::tokenizer::{Token, Tokenizer};
+
+use crate::error::Result;
+use crate::sql::cache::StatementCache;
+use crate::sql::db::database::Database;
+use crate::sql::parser::trigger::{is_keyword, tokens_to_string};
+use crate::sql::process_command;
+
+/// What a batch does once one of its statements fails, set with `.bail on|off`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OnError {
+    /// The statements after the failing one are skipped
+    Stop,
+    /// The statements after the failing one still run, the default
+    Continue,
+}
+
+/// The outcome of a single statement of a batch
+#[derive(Debug)]
+pub struct StatementResult {
+    /// Text of the statement
+    pub statement: String,
+    /// Message of the statement, or the error it failed with
+    pub result: Result<String>,
+}
+
+/// The outcome of a whole batch
+#[derive(Debug)]
+pub struct BatchResult {
+    /// Outcome of every statement that ran, in order
+    pub results: Vec<StatementResult>,
+    /// Number of statements skipped after a failure, with `OnError::Stop`
+    pub skipped: usize,
+}
+
+impl BatchResult {
+    /// Returns the number of statements that failed
+    pub fn failed(&self) -> usize {
+        self.results
+            .iter()
+            .filter(|stmt| stmt.result.is_err())
+            .count()
+    }
+}
+
+/// Runs the statements of a script one after the other, each one as `process_command` does.
+/// `report` is called with the position of every statement, the number of statements in the
+/// script and the outcome of the statement as soon as it ran, so its message comes right after
+/// the rows it printed.
+pub fn execute_batch(
+    input: &str,
+    db: &mut Database,
+    cache: &mut StatementCache,
+    on_error: OnError,
+    report: &mut dyn FnMut(usize, usize, &StatementResult),
+) -> Result<BatchResult> {
+    let statements = split_statements(input)?;
+    let mut results: Vec<StatementResult> = vec![];
+    let mut skipped = 0;
+    for (i, statement) in statements.iter().enumerate() {
+        if on_error == OnError::Stop && results.iter().any(|stmt| stmt.result.is_err()) {
+            skipped = statements.len() - i;
+            break;
+        }
+        let result = StatementResult {
+            statement: statement.to_string(),
+            result: process_command(statement, db, cache),
+        };
+        report(i, statements.len(), &result);
+        results.push(result);
+    }
+    Ok(BatchResult { results, skipped })
+}
+
+/// Splits a script into its statements, each one keeping its closing semicolon. Semicolons
+/// within the body of a CREATE TRIGGER statement do not end it, only the one after its END does.
+/// Text after the last semicolon makes a last statement, unless it is only whitespace or comments.
+pub fn split_statements(input: &str) -> Result<Vec<String>> {
+    let (statements, _) = scan_statements(input)?;
+    Ok(statements
+        .iter()
+        .map(|statement| tokens_to_string(statement).trim().to_string())
+        .collect())
+}
+
+/// Returns true if every statement of a script is closed by a semicolon,
+/// and the body of a CREATE TRIGGER statement by END. A script that does not
+/// tokenize is complete once it ends with a semicolon, for its error to be reported.
+pub fn is_complete(input: &str) -> bool {
+    match scan_statements(input) {
+        Ok((_, closed)) => closed,
+        Err(_) => input.trim_end().ends_with(';'),
+    }
+}
+
+/// Splits a script into the tokens of its statements, see `split_statements`. Returns them
+/// along with a value representing if the last statement is closed.
+fn scan_statements(input: &str) -> Result<(Vec<Vec<Token>>, bool)> {
+    let dialect = SQLiteDialect {};
+    let tokens = Tokenizer::new(&dialect, input)
+        .tokenize()
+        .map_err(ParserError::from)?;
+
+    let mut statements: Vec<Vec<Token>> = vec![];
+    let mut current: Vec<Token> = vec![];
+    // The first words of the statement, telling if it is a CREATE TRIGGER statement
+    let mut words: Vec<Token> = vec![];
+    let mut in_body = false;
+    let mut case_depth = 0;
+    let mut body_ended = false;
+    for token in tokens {
+        let is_word = matches!(token, Token::Word(_));
+        if is_word && words.len() < 3 {
+            words.push(token.clone());
+        }
+        let is_trigger = match words.as_slice() {
+            [create, trigger, ..]
+                if is_keyword(create, "CREATE") && is_keyword(trigger, "TRIGGER") =>
+            {
+                true
+            }
+            [create, temp, trigger] => {
+                is_keyword(create, "CREATE")
+                    && (is_keyword(temp, "TEMP") || is_keyword(temp, "TEMPORARY"))
+                    && is_keyword(trigger, "TRIGGER")
+            }
+            _ => false,
+        };
+        if is_trigger && is_word {
+            // END also closes the CASE expressions of the body
+            if is_keyword(&token, "BEGIN") && !in_body {
+                in_body = true;
+            } else if in_body && is_keyword(&token, "CASE") {
+                case_depth += 1;
+            } else if in_body && is_keyword(&token, "END") {
+                match case_depth {
+                    0 => body_ended = true,
+                    _ => case_depth -= 1,
+                }
+            }
+        }
+
+        let closes = token == Token::SemiColon && (!is_trigger || body_ended);
+        current.push(token);
+        if !closes {
+            continue;
+        }
+        // Empty statements, such as the second one of `;;`, are left out
+        if current
+            .iter()
+            .any(|token| !matches!(token, Token::Whitespace(_) | Token::SemiColon))
+        {
+            statements.push(current);
+        }
+        current = vec![];
+        words.clear();
+        in_body = false;
+        case_depth = 0;
+        body_ended = false;
+    }
+
+    let closed = current
+        .iter()
+        .all(|token| matches!(token, Token::Whitespace(_)));
+    if !closed {
+        statements.push(current);
+    }
+    Ok((statements, closed))
+}
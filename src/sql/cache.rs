@@ -0,0 +1,96 @@
+use std::collections::HashMap;
+
+use crate::error::Result;
+use crate::sql::db::database::Database;
+use crate::sql::prepared::PreparedStatement;
+
+/// Number of statements the statement cache of the REPL keeps
+pub const STATEMENT_CACHE_CAPACITY: usize = 64;
+
+/// Prepared statements kept by SQL text, for a statement run again to be neither parsed nor
+/// planned again. Only SELECT, INSERT, UPDATE and DELETE statements are kept. Once the cache
+/// is full the least recently used statement makes room, and a statement is prepared again
+/// once the schema of a table or view it refers to changed, see `PreparedStatement::is_current`.
+#[derive(Debug)]
+pub struct StatementCache {
+    /// Maximum number of statements kept
+    capacity: usize,
+    /// Statements kept, by SQL text
+    entries: HashMap<String, CachedStatement>,
+    /// Incremented on every lookup, telling how recently every statement was used
+    clock: u64,
+}
+
+/// A statement kept by the cache
+#[derive(Debug)]
+struct CachedStatement {
+    statement: PreparedStatement,
+    /// Value of the clock when the statement was last used
+    last_used: u64,
+}
+
+impl StatementCache {
+    pub fn new(capacity: usize) -> StatementCache {
+        StatementCache {
+            capacity,
+            entries: HashMap::new(),
+            clock: 0,
+        }
+    }
+
+    /// Returns the statement prepared from a SQL text, taken from the cache when the same
+    /// text was prepared before and the tables and views it refers to did not change since.
+    /// No value is bound to the parameters of the statement.
+    ///
+    pub fn prepare(&mut self, query: &str, db: &Database) -> Result<PreparedStatement> {
+        self.clock += 1;
+        if let Some(entry) = self.entries.get_mut(query) {
+            if entry.statement.is_current(db) {
+                entry.last_used = self.clock;
+                return Ok(entry.statement.clone());
+            }
+            self.entries.remove(query);
+        }
+
+        let statement = PreparedStatement::prepare(query, db)?;
+        if !statement.accepts_parameters() || self.capacity == 0 {
+            return Ok(statement);
+        }
+        if self.entries.len() >= self.capacity {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(query, _)| query.to_string());
+            if let Some(oldest) = oldest {
+                self.entries.remove(&oldest);
+            }
+        }
+        self.entries.insert(
+            query.to_string(),
+            CachedStatement {
+                statement: statement.clone(),
+                last_used: self.clock,
+            },
+        );
+        Ok(statement)
+    }
+
+    /// Returns true if the cache keeps a statement prepared from a SQL text
+    ///
+    pub fn contains(&self, query: &str) -> bool {
+        self.entries.contains_key(query)
+    }
+
+    /// Returns the number of statements kept
+    ///
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if no statement is kept
+    ///
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
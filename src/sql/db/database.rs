@@ -1,7 +1,14 @@
-use crate::error::{Result, SQLRiteError};
-use crate::sql::db::table::Table;
+use crate::error::{RaiseAction, Result, SQLRiteError};
+use crate::sql::db::foreign_key;
+use crate::sql::db::stats::TableStats;
+use crate::sql::db::table::{NewRow, Table, AUTOINDEX_PREFIX};
+use crate::sql::db::trigger::Trigger;
+use crate::sql::db::value::Value;
+use crate::sql::db::view::View;
+use crate::sql::parser::trigger::TriggerTiming;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use sqlparser::ast::Expr;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 
 /// The database is represented by this structure.assert_eq!
 #[derive(Serialize, Deserialize, PartialEq, Debug)]
@@ -10,6 +17,77 @@ pub struct Database {
     pub db_name: String,
     /// HashMap of tables in this database
     pub tables: HashMap<String, Table>,
+    /// HashMap of views in this database, keyed by view name
+    pub views: HashMap<String, View>,
+    /// HashMap of triggers in this database, keyed by trigger name
+    pub triggers: HashMap<String, Trigger>,
+    /// Names of the triggers being run, which do not fire again until they end
+    pub running_triggers: Vec<String>,
+    /// Value representing if FOREIGN KEY constraints are enforced, set with `PRAGMA foreign_keys`
+    pub foreign_keys: bool,
+    /// Tables and views as they were when the open transaction began, restored by ROLLBACK.
+    /// `None` outside of a transaction, when every statement commits on its own.
+    pub transaction: Option<Snapshot>,
+    /// Tables and views changed by the running statement as they were before it, restored
+    /// if it fails. `None` when the running statement does not need undoing.
+    pub undo_log: Option<UndoLog>,
+    /// Values bound by name to the parameters of the statements run by `process_command`,
+    /// set with `.parameter set`
+    pub parameters: BTreeMap<String, Value>,
+    /// Version of the schema of every table and view, changed by the statements altering it
+    /// and by ANALYZE, for the statements planned against the previous one to be planned again
+    pub schema_versions: HashMap<String, u64>,
+    /// Last version given to the schema of a table or view
+    pub last_schema_version: u64,
+}
+
+/// The tables, views and triggers of a database at some point in time, restored to undo
+/// the changes made since, including those to the rows of materialized views
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct Snapshot {
+    pub tables: HashMap<String, Table>,
+    pub views: HashMap<String, View>,
+    pub triggers: HashMap<String, Trigger>,
+    pub schema_versions: HashMap<String, u64>,
+}
+
+/// Copies of the tables and views a statement changed, each one taken right before the
+/// first change the statement made to it, restored to undo the statement. Unlike a
+/// `Snapshot`, the tables and views the statement does not change are not copied.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Default)]
+pub struct UndoLog {
+    pub tables: HashMap<String, Table>,
+    pub views: HashMap<String, View>,
+}
+
+/// Ends the undo log of a statement for either tables or views, restoring the copies if the
+/// statement failed. The copies are handed to the undo log of the statement that ran this one,
+/// if any, for the tables or views it had not changed yet: those were, before this statement,
+/// as they were before the outer one.
+fn close_undo_log<T: Clone>(
+    copies: HashMap<String, T>,
+    objects: &mut HashMap<String, T>,
+    mut outer_copies: Option<&mut HashMap<String, T>>,
+    failed: bool,
+) {
+    for (name, copy) in copies {
+        let outer_copies = outer_copies
+            .as_deref_mut()
+            .filter(|outer_copies| !outer_copies.contains_key(&name));
+        match (outer_copies, failed) {
+            (Some(outer_copies), true) => {
+                outer_copies.insert(name.to_string(), copy.clone());
+                objects.insert(name, copy);
+            }
+            (Some(outer_copies), false) => {
+                outer_copies.insert(name, copy);
+            }
+            (None, true) => {
+                objects.insert(name, copy);
+            }
+            (None, false) => (),
+        }
+    }
 }
 
 impl Database {
@@ -18,12 +96,21 @@ impl Database {
     /// # Examples
     ///
     /// ```
-    /// let mut db = sql::db::database::Database::new("my_db".to_string());
+    /// let mut db = rustdb::sql::db::database::Database::new("my_db".to_string());
     /// ```
     pub fn new(db_name: String) -> Self {
         Database {
             db_name,
             tables: HashMap::new(),
+            views: HashMap::new(),
+            triggers: HashMap::new(),
+            running_triggers: vec![],
+            foreign_keys: false,
+            transaction: None,
+            undo_log: None,
+            parameters: BTreeMap::new(),
+            schema_versions: HashMap::new(),
+            last_schema_version: 0,
         }
     }
 
@@ -45,13 +132,637 @@ impl Database {
     }
 
     /// Returns an mutable reference of `sql::db::table::Table` if the database contains a
-    /// table with the specified key as a table name.
+    /// table with the specified key as a table name. The table is copied to the undo log
+    /// of the running statement, if any, before its first change.
     ///
     pub fn get_table_mut(&mut self, table_name: String) -> Result<&mut Table> {
+        if let Some(undo_log) = &mut self.undo_log {
+            if let Some(table) = self.tables.get(&table_name) {
+                if !undo_log.tables.contains_key(&table_name) {
+                    undo_log
+                        .tables
+                        .insert(table_name.to_string(), table.clone());
+                }
+            }
+        }
         if let Some(table) = self.tables.get_mut(&table_name) {
             Ok(table)
         } else {
             Err(SQLRiteError::General(String::from("Table not found.")))
         }
     }
+
+    /// Removes a table from the database together with all its rows and indexes,
+    /// returning the removed `sql::db::table::Table`.
+    ///
+    pub fn drop_table(&mut self, table_name: String) -> Result<Table> {
+        match self.tables.remove(&table_name) {
+            Some(table) => {
+                // Materialized views reading the table keep their rows until the next REFRESH
+                for view in self.views.values_mut() {
+                    if view.dependencies().any(|name| *name == table_name) {
+                        view.groups = None;
+                    }
+                }
+                self.triggers
+                    .retain(|_, trigger| trigger.table_name != table_name);
+                self.schema_changed(&table_name);
+                Ok(table)
+            }
+            None => Err(SQLRiteError::General(format!(
+                "no such table: {}",
+                table_name
+            ))),
+        }
+    }
+
+    /// Returns true if any table in the database has an index with the specified name.
+    ///
+    pub fn contains_index(&self, index_name: String) -> bool {
+        self.tables
+            .values()
+            .any(|table| table.indexes.contains_key(&index_name))
+    }
+
+    /// Removes the index with the specified name from whichever table owns it.
+    /// Indexes created automatically for PRIMARY KEY and UNIQUE columns can not be dropped.
+    ///
+    pub fn drop_index(&mut self, index_name: String) -> Result<()> {
+        for table in self.tables.values_mut() {
+            if table.indexes.contains_key(&index_name) {
+                let table_name = table.tb_name.to_string();
+                table.drop_index(index_name)?;
+                self.schema_changed(&table_name);
+                return Ok(());
+            }
+        }
+        Err(SQLRiteError::General(format!(
+            "no such index: {}",
+            index_name
+        )))
+    }
+
+    /// Renames a table, re-registering it under the new name in `tables`.
+    /// Indexes created automatically for the table and FOREIGN KEY constraints
+    /// referring to it follow the new name.
+    ///
+    pub fn rename_table(&mut self, table_name: String, new_name: String) -> Result<()> {
+        if self.contains_table(new_name.to_string()) || self.contains_view(&new_name) {
+            return Err(SQLRiteError::General(format!(
+                "there is already another table or index with this name: {}",
+                new_name
+            )));
+        }
+        let mut table = self
+            .tables
+            .remove(&table_name)
+            .ok_or_else(|| SQLRiteError::General(format!("no such table: {}", table_name)))?;
+        let old_prefix = format!("{}{}_", AUTOINDEX_PREFIX, table_name);
+        table.indexes = table
+            .indexes
+            .into_iter()
+            .map(|(index_name, mut index)| {
+                if let Some(n) = index_name.strip_prefix(&old_prefix) {
+                    index.name = format!("{}{}_{}", AUTOINDEX_PREFIX, new_name, n);
+                }
+                (index.name.to_string(), index)
+            })
+            .collect();
+        table.tb_name = new_name.to_string();
+        self.tables.insert(new_name.to_string(), table);
+        // FOREIGN KEY constraints follow the table to its new name
+        for table in self.tables.values_mut() {
+            for foreign_key in table.foreign_keys.iter_mut() {
+                if foreign_key.parent_table == table_name {
+                    foreign_key.parent_table = new_name.to_string();
+                }
+            }
+        }
+        // So do the views reading from it and its triggers
+        for view in self.views.values_mut() {
+            view.query.rename_table(&table_name, &new_name);
+        }
+        for trigger in self.triggers.values_mut() {
+            if trigger.table_name == table_name {
+                trigger.table_name = new_name.to_string();
+            }
+        }
+        self.schema_changed(&table_name);
+        self.schema_changed(&new_name);
+        Ok(())
+    }
+
+    /// Creates an index on a table. Index names share a namespace with table names
+    /// and must be unique across the whole database.
+    ///
+    pub fn create_index(
+        &mut self,
+        index_name: String,
+        table_name: String,
+        expressions: Vec<Expr>,
+        predicate: Option<Expr>,
+        is_unique: bool,
+    ) -> Result<()> {
+        if self.contains_index(index_name.to_string())
+            || self.contains_table(index_name.to_string())
+        {
+            return Err(SQLRiteError::General(format!(
+                "there is already a table or index named {}",
+                index_name
+            )));
+        }
+        if self.contains_view(&table_name) {
+            return Err(SQLRiteError::General(String::from(
+                "views may not be indexed",
+            )));
+        }
+        let table = self
+            .get_table_mut(table_name.to_string())
+            .map_err(|_| SQLRiteError::General(format!("no such table: {}", table_name)))?;
+        table.create_index(index_name, expressions, predicate, is_unique)?;
+        self.schema_changed(&table_name);
+        Ok(())
+    }
+
+    /// Returns true if the database contains a view with the specified name.
+    ///
+    pub fn contains_view(&self, view_name: &str) -> bool {
+        self.views.contains_key(view_name)
+    }
+
+    /// Creates a view, checking that its query is valid. The rows of a materialized view
+    /// are computed right away. View names share a namespace with table and index names.
+    /// With `or_replace` an existing view of the same name is replaced.
+    ///
+    pub fn create_view(&mut self, mut view: View, or_replace: bool) -> Result<()> {
+        if self.contains_table(view.name.to_string()) {
+            return Err(SQLRiteError::General(format!(
+                "table {} already exists",
+                view.name
+            )));
+        }
+        if self.contains_index(view.name.to_string()) {
+            return Err(SQLRiteError::General(format!(
+                "there is already an index named {}",
+                view.name
+            )));
+        }
+        if self.contains_view(&view.name) && !or_replace {
+            return Err(SQLRiteError::General(format!(
+                "view {} already exists",
+                view.name
+            )));
+        }
+        if self.reads_from(&view, &view.name) {
+            return Err(SQLRiteError::General(format!(
+                "view {} is circularly defined",
+                view.name
+            )));
+        }
+        match view.materialized {
+            true => view.refresh(self)?,
+            false => {
+                view.execute(self)?;
+            }
+        }
+        self.schema_changed(&view.name);
+        self.views.insert(view.name.to_string(), view);
+        Ok(())
+    }
+
+    /// Recomputes the rows of a materialized view
+    ///
+    pub fn refresh_view(&mut self, view_name: &str) -> Result<()> {
+        let mut view = match self.views.get(view_name) {
+            Some(view) if view.materialized => view.clone(),
+            Some(_) => {
+                return Err(SQLRiteError::General(format!(
+                    "{} is not a materialized view",
+                    view_name
+                )))
+            }
+            None => {
+                return Err(SQLRiteError::General(format!(
+                    "no such view: {}",
+                    view_name
+                )))
+            }
+        };
+        view.refresh(self)?;
+        self.views.insert(view_name.to_string(), view);
+        // The column types of a materialized view are inferred from its rows
+        self.schema_changed(view_name);
+        Ok(())
+    }
+
+    /// Updates the materialized views maintained incrementally from a table for one of its
+    /// rows being added (`delta` of 1) or removed (`delta` of -1)
+    ///
+    pub fn maintain_views(&mut self, table_name: &str, rowid: i64, delta: i64) -> Result<()> {
+        let names: Vec<String> = self
+            .views
+            .values()
+            .filter(|view| view.groups.is_some() && view.query.from[0].name == table_name)
+            .map(|view| view.name.to_string())
+            .collect();
+        for name in names {
+            let mut view = self.views.remove(&name).unwrap();
+            if let Some(undo_log) = &mut self.undo_log {
+                if !undo_log.views.contains_key(&name) {
+                    undo_log.views.insert(name.to_string(), view.clone());
+                }
+            }
+            let result = view.maintain(self, rowid, delta);
+            self.views.insert(name, view);
+            result?;
+        }
+        Ok(())
+    }
+
+    /// Removes a view from the database together with its triggers,
+    /// returning the removed `sql::db::view::View`.
+    ///
+    pub fn drop_view(&mut self, view_name: &str) -> Result<View> {
+        match self.views.remove(view_name) {
+            Some(view) => {
+                self.triggers
+                    .retain(|_, trigger| trigger.table_name != view_name);
+                self.schema_changed(view_name);
+                Ok(view)
+            }
+            None => Err(SQLRiteError::General(format!(
+                "no such view: {}",
+                view_name
+            ))),
+        }
+    }
+
+    /// Creates a trigger. BEFORE and AFTER triggers are attached to tables and INSTEAD OF
+    /// triggers to views. With `if_not_exists`, creating a trigger that already exists is a no-op.
+    ///
+    pub fn create_trigger(&mut self, trigger: Trigger, if_not_exists: bool) -> Result<()> {
+        if self.triggers.contains_key(&trigger.name) {
+            return match if_not_exists {
+                true => Ok(()),
+                false => Err(SQLRiteError::General(format!(
+                    "trigger {} already exists",
+                    trigger.name
+                ))),
+            };
+        }
+        let is_view = self.contains_view(&trigger.table_name);
+        if !is_view && !self.contains_table(trigger.table_name.to_string()) {
+            return Err(SQLRiteError::General(format!(
+                "no such table: {}",
+                trigger.table_name
+            )));
+        }
+        match (trigger.timing, is_view) {
+            (TriggerTiming::InsteadOf, false) => {
+                return Err(SQLRiteError::General(format!(
+                    "cannot create INSTEAD OF trigger on table: {}",
+                    trigger.table_name
+                )))
+            }
+            (TriggerTiming::Before | TriggerTiming::After, true) => {
+                return Err(SQLRiteError::General(format!(
+                    "cannot create {} trigger on view: {}",
+                    match trigger.timing {
+                        TriggerTiming::Before => "BEFORE",
+                        _ => "AFTER",
+                    },
+                    trigger.table_name
+                )))
+            }
+            _ => (),
+        }
+        self.triggers.insert(trigger.name.to_string(), trigger);
+        Ok(())
+    }
+
+    /// Removes a trigger from the database, returning the removed `sql::db::trigger::Trigger`.
+    ///
+    pub fn drop_trigger(&mut self, trigger_name: &str) -> Result<Trigger> {
+        match self.triggers.remove(trigger_name) {
+            Some(trigger) => Ok(trigger),
+            None => Err(SQLRiteError::General(format!(
+                "no such trigger: {}",
+                trigger_name
+            ))),
+        }
+    }
+
+    /// Returns true if a view reads from the table or view with the specified name,
+    /// directly or through other views.
+    ///
+    pub fn reads_from(&self, view: &View, name: &str) -> bool {
+        view.dependencies().any(|dependency| {
+            dependency == name
+                || self
+                    .views
+                    .get(dependency)
+                    .is_some_and(|view| self.reads_from(view, name))
+        })
+    }
+
+    /// Checks that the views reading from a table are still valid, e.g. after its columns
+    /// were changed.
+    ///
+    pub fn check_views(&self, table_name: &str) -> Result<()> {
+        let mut views: Vec<&View> = self
+            .views
+            .values()
+            .filter(|view| self.reads_from(view, table_name))
+            .collect();
+        views.sort_by(|a, b| a.name.cmp(&b.name));
+        for view in views {
+            if let Err(err) = view.execute(self) {
+                return Err(match err {
+                    SQLRiteError::General(message) => {
+                        SQLRiteError::General(format!("error in view {}: {}", view.name, message))
+                    }
+                    err => err,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Collects the statistics the query planner uses for the given table, or for
+    /// every table of the database when no table is given.
+    ///
+    pub fn analyze(&mut self, table_name: Option<String>) -> Result<()> {
+        match table_name {
+            Some(table_name) => {
+                let table = self
+                    .get_table_mut(table_name.to_string())
+                    .map_err(|_| SQLRiteError::General(format!("no such table: {}", table_name)))?;
+                table.stats = Some(TableStats::collect(table));
+                // Statements are planned against the statistics as well
+                self.schema_changed(&table_name);
+            }
+            None => {
+                for table in self.tables.values_mut() {
+                    table.stats = Some(TableStats::collect(table));
+                }
+                let table_names: Vec<String> = self.tables.keys().cloned().collect();
+                for table_name in table_names {
+                    self.schema_changed(&table_name);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Starts a transaction. Until COMMIT or ROLLBACK, deferred FOREIGN KEY constraints are
+    /// not checked and every change can be undone.
+    ///
+    pub fn begin(&mut self) -> Result<()> {
+        if self.transaction.is_some() {
+            return Err(SQLRiteError::General(String::from(
+                "cannot start a transaction within a transaction",
+            )));
+        }
+        self.transaction = Some(self.snapshot());
+        Ok(())
+    }
+
+    /// Commits the open transaction. If a deferred FOREIGN KEY constraint is broken
+    /// the COMMIT fails and the transaction stays open, like in SQLite.
+    ///
+    pub fn commit(&mut self) -> Result<()> {
+        if self.transaction.is_none() {
+            return Err(SQLRiteError::General(String::from(
+                "cannot commit - no transaction is active",
+            )));
+        }
+        if self.foreign_keys {
+            foreign_key::check_deferred(self)?;
+        }
+        self.transaction = None;
+        Ok(())
+    }
+
+    /// Undoes every change made since the open transaction began
+    ///
+    pub fn rollback(&mut self) -> Result<()> {
+        match self.transaction.take() {
+            Some(snapshot) => {
+                self.restore(snapshot);
+                Ok(())
+            }
+            None => Err(SQLRiteError::General(String::from(
+                "cannot rollback - no transaction is active",
+            ))),
+        }
+    }
+
+    /// Returns a copy of the tables, views and triggers of the database
+    ///
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            tables: self.tables.clone(),
+            views: self.views.clone(),
+            triggers: self.triggers.clone(),
+            schema_versions: self.schema_versions.clone(),
+        }
+    }
+
+    /// Brings the tables, views and triggers of the database back to a snapshot
+    ///
+    pub fn restore(&mut self, snapshot: Snapshot) {
+        self.tables = snapshot.tables;
+        self.views = snapshot.views;
+        self.triggers = snapshot.triggers;
+        self.schema_versions = snapshot.schema_versions;
+    }
+
+    /// Runs the writes of a single statement. When FOREIGN KEY constraints are enforced,
+    /// a statement breaking one is undone as a whole, including the actions it cascaded
+    /// to other tables, and deferred constraints are checked once it ends outside of a
+    /// transaction. With `atomic` set, a failing statement is always undone as a whole,
+    /// like the ABORT conflict resolution of SQLite. So is a statement that may fire triggers,
+    /// along with the changes the triggers made, unless it failed with `RAISE(FAIL, ...)`.
+    /// Only the tables and views the statement changes are copied to undo it, see `UndoLog`.
+    ///
+    pub fn run_statement<T>(
+        &mut self,
+        atomic: bool,
+        statement: impl FnOnce(&mut Database) -> Result<T>,
+    ) -> Result<T> {
+        let enforced = self.foreign_keys
+            && self
+                .tables
+                .values()
+                .any(|table| !table.foreign_keys.is_empty());
+        if !enforced && !atomic && self.triggers.is_empty() {
+            return statement(self);
+        }
+
+        // A statement run by a trigger keeps its own log, so that it can be undone alone
+        let outer_log = self.undo_log.replace(UndoLog::default());
+        let result = statement(self).and_then(|value| {
+            if enforced && self.transaction.is_none() {
+                foreign_key::check_deferred(self)?;
+            }
+            Ok(value)
+        });
+        let undo_log = std::mem::replace(&mut self.undo_log, outer_log).unwrap_or_default();
+        let failed = !matches!(
+            &result,
+            Err(SQLRiteError::Raise(RaiseAction::Fail, _)) | Ok(_)
+        );
+        let (outer_tables, outer_views) = match &mut self.undo_log {
+            Some(outer_log) => (Some(&mut outer_log.tables), Some(&mut outer_log.views)),
+            None => (None, None),
+        };
+        close_undo_log(undo_log.tables, &mut self.tables, outer_tables, failed);
+        close_undo_log(undo_log.views, &mut self.views, outer_views, failed);
+        result
+    }
+
+    /// Inserts a row into a table, checking the FOREIGN KEY constraints of the new row.
+    /// Returns the ROWID of the new row.
+    ///
+    pub fn insert_row(
+        &mut self,
+        table_name: &str,
+        cols: &[String],
+        values: &[Value],
+    ) -> Result<i64> {
+        let row = self
+            .get_table(table_name.to_string())?
+            .prepare_row(cols, values)?;
+        self.store_row(table_name, &row)
+    }
+
+    /// Stores a row built by `Table::prepare_row`, checking the FOREIGN KEY constraints
+    /// of the new row. Returns the ROWID of the new row.
+    ///
+    pub fn store_row(&mut self, table_name: &str, row: &NewRow) -> Result<i64> {
+        let rowid = self.get_table_mut(table_name.to_string())?.store_row(row)?;
+        self.maintain_views(table_name, rowid, 1)?;
+        if self.foreign_keys {
+            foreign_key::check_child_row(self, table_name, rowid, None)?;
+        }
+        Ok(rowid)
+    }
+
+    /// Applies the updates of an UPDATE statement to a table, checking the FOREIGN KEY
+    /// constraints of the updated rows and applying the ON UPDATE actions of the rows
+    /// referring to them. Returns the ROWIDs of the updated rows, which change along with
+    /// their INTEGER PRIMARY KEY.
+    ///
+    pub fn update_rows(
+        &mut self,
+        table_name: &str,
+        updates: Vec<(i64, Vec<(String, Value)>)>,
+    ) -> Result<Vec<i64>> {
+        match self.foreign_keys {
+            true => foreign_key::update_rows(self, table_name, updates),
+            false => self.write_updates(table_name, updates),
+        }
+    }
+
+    /// Applies updates to the rows of a table without checking FOREIGN KEY constraints,
+    /// keeping the materialized views maintained from the table up to date.
+    /// Returns the ROWIDs of the updated rows.
+    ///
+    pub fn write_updates(
+        &mut self,
+        table_name: &str,
+        updates: Vec<(i64, Vec<(String, Value)>)>,
+    ) -> Result<Vec<i64>> {
+        for (rowid, _) in &updates {
+            self.maintain_views(table_name, *rowid, -1)?;
+        }
+        let old_rowids: Vec<i64> = updates.iter().map(|(rowid, _)| *rowid).collect();
+        // A failed update leaves every row as it was, so the rows are added back either way
+        let result = self
+            .get_table_mut(table_name.to_string())?
+            .update_rows(updates);
+        let rowids = match &result {
+            Ok(rowids) => rowids,
+            Err(_) => &old_rowids,
+        };
+        for rowid in rowids {
+            self.maintain_views(table_name, *rowid, 1)?;
+        }
+        result
+    }
+
+    /// Deletes a row from a table, applying the ON DELETE actions of the rows referring to it
+    ///
+    pub fn delete_row(&mut self, table_name: &str, rowid: i64) -> Result<()> {
+        match self.foreign_keys {
+            true => foreign_key::delete_row(self, table_name, rowid, &mut BTreeSet::new()),
+            false => self.remove_row(table_name, rowid),
+        }
+    }
+
+    /// Deletes a row from a table without applying ON DELETE actions, keeping the
+    /// materialized views maintained from the table up to date
+    ///
+    pub fn remove_row(&mut self, table_name: &str, rowid: i64) -> Result<()> {
+        self.maintain_views(table_name, rowid, -1)?;
+        self.get_table_mut(table_name.to_string())?
+            .delete_row(rowid)
+    }
+
+    /// Returns true if a table other than `table_name` has a FOREIGN KEY referring to it
+    ///
+    pub fn is_referenced(&self, table_name: &str) -> bool {
+        self.tables.iter().any(|(name, table)| {
+            name != table_name
+                && table
+                    .foreign_keys
+                    .iter()
+                    .any(|foreign_key| foreign_key.parent_table == table_name)
+        })
+    }
+
+    /// Renames a column of a table, along with the FOREIGN KEY constraints of other
+    /// tables referring to it.
+    ///
+    pub fn rename_column(
+        &mut self,
+        table_name: &str,
+        old_name: String,
+        new_name: String,
+    ) -> Result<()> {
+        self.get_table_mut(table_name.to_string())?
+            .rename_column(old_name.to_string(), new_name.to_string())?;
+        for table in self.tables.values_mut() {
+            for foreign_key in table.foreign_keys.iter_mut() {
+                if foreign_key.parent_table == table_name {
+                    for col in foreign_key.parent_columns.iter_mut() {
+                        if *col == old_name {
+                            *col = new_name.to_string();
+                        }
+                    }
+                }
+            }
+        }
+        self.schema_changed(table_name);
+        Ok(())
+    }
+
+    /// Gives a new version to the schema of a table or view, for the statements planned
+    /// against the previous one to be planned again, see `PreparedStatement`
+    ///
+    pub fn schema_changed(&mut self, name: &str) {
+        self.last_schema_version += 1;
+        self.schema_versions
+            .insert(name.to_string(), self.last_schema_version);
+    }
+
+    /// Returns the version of the schema of a table or view, `None` when there is none
+    /// of that name
+    ///
+    pub fn schema_version(&self, name: &str) -> Option<u64> {
+        match self.contains_table(name.to_string()) || self.contains_view(name) {
+            true => Some(self.schema_versions.get(name).copied().unwrap_or(0)),
+            false => None,
+        }
+    }
 }
@@ -0,0 +1,388 @@
+use crate::error::{Result, SQLRiteError};
+use crate::sql::db::database::Database;
+use crate::sql::db::index::Index;
+use crate::sql::db::table::Table;
+use crate::sql::db::value::Value;
+use crate::sql::executor::expr::{evaluate, NoRow};
+use crate::sql::parser::create::ParsedForeignKey;
+use serde::{Deserialize, Serialize};
+use sqlparser::ast::{Expr, ReferentialAction};
+use std::collections::BTreeSet;
+
+/// A FOREIGN KEY constraint of a table, the child, referring to the rows of another
+/// table, the parent. Like in SQLite, a key holding a NULL refers to no row and
+/// always satisfies the constraint.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct ForeignKey {
+    /// Name given to the constraint with `CONSTRAINT <name>`, if any
+    pub name: Option<String>,
+    /// Columns of the child table holding the key
+    pub columns: Vec<String>,
+    /// Name of the parent table
+    pub parent_table: String,
+    /// Columns of the parent table the key refers to, empty for its PRIMARY KEY
+    pub parent_columns: Vec<String>,
+    /// Action taken on the child rows when their parent row is deleted
+    pub on_delete: ReferentialAction,
+    /// Action taken on the child rows when the key of their parent row changes
+    pub on_update: ReferentialAction,
+    /// Value representing if the constraint is only checked at COMMIT
+    pub deferred: bool,
+}
+
+impl ForeignKey {
+    pub fn new(foreign_key: &ParsedForeignKey) -> Self {
+        ForeignKey {
+            name: foreign_key.name.clone(),
+            columns: foreign_key.columns.to_vec(),
+            parent_table: foreign_key.parent_table.to_string(),
+            parent_columns: foreign_key.parent_columns.to_vec(),
+            on_delete: foreign_key.on_delete.clone(),
+            on_update: foreign_key.on_update.clone(),
+            deferred: foreign_key.deferred,
+        }
+    }
+
+    /// Returns the key a child row holds, `None` if any part of it is NULL
+    fn child_key(&self, child: &Table, rowid: i64) -> Option<Vec<Value>> {
+        let key: Vec<Value> = self
+            .columns
+            .iter()
+            .map(|col| child.get_value(col, rowid))
+            .collect();
+        match key.iter().any(|val| val.is_null()) {
+            true => None,
+            false => Some(key),
+        }
+    }
+
+    /// Returns the columns of the parent table the key refers to
+    fn resolve_parent_columns(&self, child_name: &str, parent: &Table) -> Result<Vec<String>> {
+        match (self.parent_columns.is_empty(), &parent.primary_key) {
+            (true, None) => Err(self.mismatch(child_name)),
+            // A key referring to the PRIMARY KEY must have as many columns as it
+            (true, Some(primary_key)) if primary_key.columns.len() != self.columns.len() => {
+                Err(self.mismatch(child_name))
+            }
+            (true, Some(primary_key)) => Ok(primary_key.columns.to_vec()),
+            (false, _) => Ok(self.parent_columns.to_vec()),
+        }
+    }
+
+    /// Returns the key a parent row is referred to by, `None` if any part of it is NULL
+    fn parent_key(
+        &self,
+        child_name: &str,
+        parent: &Table,
+        rowid: i64,
+    ) -> Result<Option<Vec<Value>>> {
+        let key: Vec<Value> = self
+            .resolve_parent_columns(child_name, parent)?
+            .iter()
+            .map(|col| parent.get_value(col, rowid))
+            .collect();
+        match key.iter().any(|val| val.is_null()) {
+            true => Ok(None),
+            false => Ok(Some(key)),
+        }
+    }
+
+    /// Returns true if the parent table has a row holding the key. The parent columns
+    /// must be covered by a UNIQUE index, which is used for the lookup.
+    fn parent_exists(&self, db: &Database, child_name: &str, key: &[Value]) -> Result<bool> {
+        let parent = match db.tables.get(&self.parent_table) {
+            Some(parent) => parent,
+            None => return Ok(false),
+        };
+        let parent_columns = self.resolve_parent_columns(child_name, parent)?;
+        let index = self.parent_index(child_name, parent, &parent_columns)?;
+        // The key parts follow the order of the index, which may differ from the
+        // order the parent columns are listed in
+        let index_key: Vec<Value> = index
+            .columns
+            .iter()
+            .map(|col| {
+                let i = parent_columns.iter().position(|c| c == col).unwrap();
+                key[i].clone()
+            })
+            .collect();
+        Ok(index.entries.contains_key(&index_key))
+    }
+
+    /// Finds the UNIQUE index of the parent table over exactly the parent columns
+    fn parent_index<'a>(
+        &self,
+        child_name: &str,
+        parent: &'a Table,
+        parent_columns: &[String],
+    ) -> Result<&'a Index> {
+        let wanted: BTreeSet<&String> = parent_columns.iter().collect();
+        parent
+            .indexes
+            .values()
+            .find(|index| {
+                index.is_unique
+                    && index.predicate.is_none()
+                    && index
+                        .expressions
+                        .iter()
+                        .all(|expr| matches!(expr, Expr::Identifier(_)))
+                    && index.columns.len() == wanted.len()
+                    && index.columns.iter().collect::<BTreeSet<&String>>() == wanted
+            })
+            .ok_or_else(|| self.mismatch(child_name))
+    }
+
+    fn mismatch(&self, child_name: &str) -> SQLRiteError {
+        SQLRiteError::General(format!(
+            "foreign key mismatch - \"{}\" referencing \"{}\"",
+            child_name, self.parent_table
+        ))
+    }
+}
+
+fn violation() -> SQLRiteError {
+    SQLRiteError::General(String::from("FOREIGN KEY constraint failed"))
+}
+
+/// Returns the foreign keys of every table referring to `parent_name`,
+/// as (child table name, foreign key) pairs
+fn referencing_keys(db: &Database, parent_name: &str) -> Vec<(String, ForeignKey)> {
+    let mut keys: Vec<(String, ForeignKey)> = vec![];
+    for (child_name, child) in db.tables.iter() {
+        for foreign_key in &child.foreign_keys {
+            if foreign_key.parent_table == parent_name {
+                keys.push((child_name.to_string(), foreign_key.clone()));
+            }
+        }
+    }
+    keys.sort_by(|a, b| a.0.cmp(&b.0));
+    keys
+}
+
+/// Returns the ROWIDs of the rows of the child table holding `key`
+fn find_children(
+    db: &Database,
+    child_name: &str,
+    foreign_key: &ForeignKey,
+    key: &[Value],
+) -> Vec<i64> {
+    let child = &db.tables[child_name];
+    child
+        .rowids()
+        .into_iter()
+        .filter(|rowid| foreign_key.child_key(child, *rowid).as_deref() == Some(key))
+        .collect()
+}
+
+/// Returns true if any of `columns` is part of the key
+fn is_changed(foreign_key_columns: &[String], columns: &[String]) -> bool {
+    foreign_key_columns.iter().any(|col| columns.contains(col))
+}
+
+/// Checks that the parent rows referred to by a child row exist, for the foreign keys
+/// that are not deferred. With `columns` set, only the keys using any of them are checked.
+pub fn check_child_row(
+    db: &Database,
+    child_name: &str,
+    rowid: i64,
+    columns: Option<&[String]>,
+) -> Result<()> {
+    let child = &db.tables[child_name];
+    for foreign_key in child.foreign_keys.iter().filter(|fk| !fk.deferred) {
+        if let Some(columns) = columns {
+            if !is_changed(&foreign_key.columns, columns) {
+                continue;
+            }
+        }
+        if let Some(key) = foreign_key.child_key(child, rowid) {
+            if !foreign_key.parent_exists(db, child_name, &key)? {
+                return Err(violation());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Checks every row of every table against the deferred foreign keys,
+/// as done at COMMIT or at the end of a statement run outside of a transaction
+pub fn check_deferred(db: &Database) -> Result<()> {
+    for (child_name, child) in db.tables.iter() {
+        for foreign_key in child.foreign_keys.iter().filter(|fk| fk.deferred) {
+            for rowid in child.rowids() {
+                if let Some(key) = foreign_key.child_key(child, rowid) {
+                    if !foreign_key.parent_exists(db, child_name, &key)? {
+                        return Err(violation());
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The values the key columns of a child row are set to by a SET NULL or SET DEFAULT action
+fn reset_values(
+    db: &Database,
+    child_name: &str,
+    foreign_key: &ForeignKey,
+    action: &ReferentialAction,
+) -> Result<Vec<(String, Value)>> {
+    let child = &db.tables[child_name];
+    let mut values: Vec<(String, Value)> = vec![];
+    for col_name in &foreign_key.columns {
+        let val = match action {
+            ReferentialAction::SetDefault => {
+                match child
+                    .columns
+                    .iter()
+                    .find(|col| col.column_name == *col_name)
+                    .and_then(|col| col.default.as_ref())
+                {
+                    Some(default) => evaluate(default, &NoRow)?,
+                    None => Value::Null,
+                }
+            }
+            _ => Value::Null,
+        };
+        values.push((col_name.to_string(), val));
+    }
+    Ok(values)
+}
+
+/// Applies the ON DELETE actions of the foreign keys referring to a parent row about to
+/// be deleted. `deleting` holds the rows whose deletion is in progress, so cascades
+/// through rows referring to each other come to an end.
+pub fn on_parent_delete(
+    db: &mut Database,
+    parent_name: &str,
+    rowid: i64,
+    deleting: &mut BTreeSet<(String, i64)>,
+) -> Result<()> {
+    for (child_name, foreign_key) in referencing_keys(db, parent_name) {
+        let key = match foreign_key.parent_key(&child_name, &db.tables[parent_name], rowid) {
+            Ok(Some(key)) => key,
+            Ok(None) => continue,
+            // Like SQLite, a mismatched key only fails the statements writing to the child
+            Err(_) => continue,
+        };
+        let children: Vec<i64> = find_children(db, &child_name, &foreign_key, &key)
+            .into_iter()
+            .filter(|child_rowid| !deleting.contains(&(child_name.to_string(), *child_rowid)))
+            .collect();
+        if children.is_empty() {
+            continue;
+        }
+        match &foreign_key.on_delete {
+            ReferentialAction::Cascade => {
+                for child_rowid in children {
+                    delete_row(db, &child_name, child_rowid, deleting)?;
+                }
+            }
+            action @ (ReferentialAction::SetNull | ReferentialAction::SetDefault) => {
+                let values = reset_values(db, &child_name, &foreign_key, action)?;
+                let updates = children
+                    .into_iter()
+                    .map(|child_rowid| (child_rowid, values.to_vec()))
+                    .collect();
+                update_rows(db, &child_name, updates)?;
+            }
+            ReferentialAction::NoAction if foreign_key.deferred => (),
+            ReferentialAction::Restrict | ReferentialAction::NoAction => {
+                return Err(violation());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Deletes a row, first applying the ON DELETE actions of the foreign keys referring to it
+pub fn delete_row(
+    db: &mut Database,
+    table_name: &str,
+    rowid: i64,
+    deleting: &mut BTreeSet<(String, i64)>,
+) -> Result<()> {
+    if !db.get_table(table_name.to_string())?.contains_rowid(rowid) {
+        return Ok(());
+    }
+    deleting.insert((table_name.to_string(), rowid));
+    on_parent_delete(db, table_name, rowid, deleting)?;
+    db.remove_row(table_name, rowid)
+}
+
+/// Updates rows of a table, then checks the keys they now hold and applies the ON UPDATE
+/// actions of the foreign keys referring to the keys they held before.
+/// Returns the ROWIDs of the updated rows.
+pub fn update_rows(
+    db: &mut Database,
+    table_name: &str,
+    updates: Vec<(i64, Vec<(String, Value)>)>,
+) -> Result<Vec<i64>> {
+    let referencing = referencing_keys(db, table_name);
+    // Keys the updated rows are referred to by, before the update
+    let mut old_keys: Vec<Vec<Option<Vec<Value>>>> = vec![];
+    {
+        let table = db.get_table(table_name.to_string())?;
+        for (rowid, _) in &updates {
+            let mut keys: Vec<Option<Vec<Value>>> = vec![];
+            for (child_name, foreign_key) in &referencing {
+                keys.push(
+                    foreign_key
+                        .parent_key(child_name, table, *rowid)
+                        .unwrap_or(None),
+                );
+            }
+            old_keys.push(keys);
+        }
+    }
+
+    let rowids = db.write_updates(table_name, updates.to_vec())?;
+
+    for (((_, assignments), old_keys), rowid) in updates.iter().zip(old_keys).zip(&rowids) {
+        let columns: Vec<String> = assignments.iter().map(|(col, _)| col.to_string()).collect();
+        check_child_row(db, table_name, *rowid, Some(&columns))?;
+
+        for ((child_name, foreign_key), old_key) in referencing.iter().zip(old_keys) {
+            let old_key = match old_key {
+                Some(old_key) => old_key,
+                None => continue,
+            };
+            let new_key = foreign_key.parent_key(child_name, &db.tables[table_name], *rowid)?;
+            if new_key.as_ref() == Some(&old_key) {
+                continue;
+            }
+            let children: Vec<i64> = find_children(db, child_name, foreign_key, &old_key)
+                .into_iter()
+                .filter(|child_rowid| !(child_name == table_name && child_rowid == rowid))
+                .collect();
+            if children.is_empty() {
+                continue;
+            }
+            let values = match &foreign_key.on_update {
+                ReferentialAction::Cascade => {
+                    let new_key = new_key.unwrap_or_else(|| vec![Value::Null; old_key.len()]);
+                    foreign_key
+                        .columns
+                        .iter()
+                        .map(|col| col.to_string())
+                        .zip(new_key)
+                        .collect()
+                }
+                action @ (ReferentialAction::SetNull | ReferentialAction::SetDefault) => {
+                    reset_values(db, child_name, foreign_key, action)?
+                }
+                ReferentialAction::NoAction if foreign_key.deferred => continue,
+                ReferentialAction::Restrict | ReferentialAction::NoAction => {
+                    return Err(violation());
+                }
+            };
+            let child_updates = children
+                .into_iter()
+                .map(|child_rowid| (child_rowid, values.to_vec()))
+                .collect();
+            update_rows(db, child_name, child_updates)?;
+        }
+    }
+    Ok(rowids)
+}
@@ -0,0 +1,255 @@
+use crate::error::{Result, SQLRiteError};
+use crate::sql::db::database::Database;
+use crate::sql::db::value::Value;
+use crate::sql::executor::expr::{
+    contains_aggregate, evaluate, is_aggregate_call, is_truthy, to_f64, to_numeric,
+};
+use crate::sql::executor::planner::Scope;
+use crate::sql::executor::select::{prepare_select, JoinedRow};
+use crate::sql::parser::select::SelectQuery;
+use serde::{Deserialize, Serialize};
+use sqlparser::ast::{Expr, FunctionArg, FunctionArgExpr};
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+
+/// Running state of an aggregate function over the values its argument takes in the rows
+/// of a group, updated as rows join and leave the group instead of being recomputed
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Default)]
+pub struct AggregateState {
+    /// Number of values that are not NULL
+    count: i64,
+    /// Sum of the INTEGER values
+    integer_sum: i128,
+    /// Number of values that are not INTEGERs, which make sum() a REAL
+    non_integers: i64,
+    /// Sum of every value as a REAL
+    real_sum: f64,
+    /// Number of times every value occurs, only kept for min() and max()
+    values: BTreeMap<Value, i64>,
+}
+
+impl AggregateState {
+    /// Adds a value to the state, or removes it when `delta` is -1
+    fn apply(&mut self, value: &Value, delta: i64, keep_values: bool) {
+        if value.is_null() {
+            return;
+        }
+        self.count += delta;
+        let number = to_numeric(value);
+        match number {
+            Value::Integer(i) => self.integer_sum += i as i128 * delta as i128,
+            _ => self.non_integers += delta,
+        }
+        self.real_sum += to_f64(&number).unwrap_or(0.0) * delta as f64;
+        if keep_values {
+            let count = self.values.entry(value.clone()).or_insert(0);
+            *count += delta;
+            if *count == 0 {
+                self.values.remove(value);
+            }
+        }
+    }
+
+    /// Returns the value of an aggregate function over the values of the state,
+    /// the same `call_aggregate` computes from all of them
+    fn value(&self, name: &str) -> Result<Value> {
+        // Sums of INTEGERs are exact, sums involving REALs may drift in their last digits
+        let real = match self.non_integers {
+            0 => self.integer_sum as f64,
+            _ => self.real_sum,
+        };
+        match name {
+            "count" => Ok(Value::Integer(self.count)),
+            "total" => Ok(Value::Real(real)),
+            _ if self.count == 0 => Ok(Value::Null),
+            "avg" => Ok(Value::Real(real / self.count as f64)),
+            "sum" if self.non_integers == 0 => i64::try_from(self.integer_sum)
+                .map(Value::Integer)
+                .map_err(|_| SQLRiteError::General("integer overflow".to_string())),
+            "sum" => Ok(Value::Real(real)),
+            "min" => Ok(self.values.keys().next().cloned().unwrap_or(Value::Null)),
+            "max" => Ok(self.values.keys().last().cloned().unwrap_or(Value::Null)),
+            _ => Err(SQLRiteError::Internal(format!(
+                "{}() can not be maintained incrementally",
+                name
+            ))),
+        }
+    }
+}
+
+/// A group of rows of the table an incrementally maintained materialized view reads from
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct Group {
+    /// ROWID of the row of the view holding the group, `None` until it is stored
+    pub rowid: Option<i64>,
+    /// Number of rows in the group
+    pub rows: i64,
+    /// State of every aggregate function of the view
+    pub aggregates: Vec<AggregateState>,
+}
+
+/// How a column of an incrementally maintained view is computed
+enum Output {
+    /// The GROUP BY term at this position
+    Key(usize),
+    /// The aggregate function at this position
+    Aggregate(usize),
+}
+
+/// How a materialized view computing aggregates over a single table is kept up to date
+/// as rows of the table are inserted, updated and deleted: only the group of the changed
+/// row is updated, from the running state of its aggregates.
+pub struct Maintenance<'a> {
+    /// The table the view reads from
+    scope: Scope<'a>,
+    /// WHERE clause of the view
+    selection: Option<Expr>,
+    /// GROUP BY terms of the view, with aliases and positions resolved
+    pub group_by: Vec<Expr>,
+    /// Name and argument of every aggregate function, the argument being `None` for count(*)
+    aggregates: Vec<(String, Option<Expr>)>,
+    /// How every column of the view is computed
+    outputs: Vec<Output>,
+    /// Names of the columns of the view's query
+    pub columns: Vec<String>,
+}
+
+impl<'a> Maintenance<'a> {
+    /// Plans the incremental maintenance of a view query. Returns `None` unless the query
+    /// reads a single table, without DISTINCT, HAVING, ORDER BY or LIMIT, and only selects
+    /// GROUP BY terms and calls of count(), sum(), total(), avg(), min() and max()
+    /// without DISTINCT.
+    pub fn new(query: &SelectQuery, db: &'a Database) -> Result<Option<Maintenance<'a>>> {
+        let simple = query.from.len() == 1
+            && db.contains_table(query.from[0].name.to_string())
+            && !query.distinct
+            && query.having.is_none()
+            && query.order_by.is_empty()
+            && query.limit.is_none()
+            && query.offset.is_none()
+            && query.is_aggregate();
+        if !simple {
+            return Ok(None);
+        }
+
+        let prepared = prepare_select(query, db, &[], None)?;
+        let mut aggregates: Vec<(String, Option<Expr>)> = vec![];
+        let mut outputs: Vec<Output> = vec![];
+        for column in &prepared.columns {
+            if let Some(i) = prepared
+                .group_by
+                .iter()
+                .position(|term| *term == column.expr)
+            {
+                outputs.push(Output::Key(i));
+                continue;
+            }
+            let function = match &column.expr {
+                Expr::Function(function) if is_aggregate_call(function) && !function.distinct => {
+                    function
+                }
+                _ => return Ok(None),
+            };
+            let name = function.name.to_string().to_lowercase();
+            let arg = match (name.as_ref(), function.args.as_slice()) {
+                ("count", [FunctionArg::Unnamed(FunctionArgExpr::Wildcard)]) => None,
+                ("group_concat", _) => return Ok(None),
+                (_, [FunctionArg::Unnamed(FunctionArgExpr::Expr(arg))])
+                    if !contains_aggregate(arg) =>
+                {
+                    Some(arg.clone())
+                }
+                _ => return Ok(None),
+            };
+            outputs.push(Output::Aggregate(aggregates.len()));
+            aggregates.push((name, arg));
+        }
+
+        Ok(Some(Maintenance {
+            columns: prepared
+                .columns
+                .iter()
+                .map(|col| col.name.to_string())
+                .collect(),
+            scope: prepared.scope,
+            selection: query.selection.clone(),
+            group_by: prepared.group_by,
+            aggregates,
+            outputs,
+        }))
+    }
+
+    /// Returns the groups of the view before any row is added. A query without GROUP BY
+    /// has a single group, which exists even when it has no rows.
+    pub fn empty_groups(&self) -> BTreeMap<Vec<Value>, Group> {
+        let mut groups = BTreeMap::new();
+        if self.group_by.is_empty() {
+            groups.insert(vec![], self.new_group());
+        }
+        groups
+    }
+
+    fn new_group(&self) -> Group {
+        Group {
+            rowid: None,
+            rows: 0,
+            aggregates: vec![AggregateState::default(); self.aggregates.len()],
+        }
+    }
+
+    /// Adds a row of the table to its group, or removes it when `delta` is -1. Returns the
+    /// key of the group, or `None` if the row is filtered out by the WHERE clause.
+    pub fn apply(
+        &self,
+        groups: &mut BTreeMap<Vec<Value>, Group>,
+        rowid: i64,
+        delta: i64,
+    ) -> Result<Option<Vec<Value>>> {
+        let rowids = [Some(rowid)];
+        let row = JoinedRow {
+            scope: &self.scope,
+            rowids: &rowids,
+        };
+        if let Some(selection) = &self.selection {
+            if is_truthy(&evaluate(selection, &row)?) != Some(true) {
+                return Ok(None);
+            }
+        }
+        let mut key: Vec<Value> = vec![];
+        for term in &self.group_by {
+            key.push(evaluate(term, &row)?);
+        }
+        let mut values: Vec<Value> = vec![];
+        for (_, arg) in &self.aggregates {
+            values.push(match arg {
+                Some(arg) => evaluate(arg, &row)?,
+                None => Value::Integer(1),
+            });
+        }
+
+        let group = groups
+            .entry(key.clone())
+            .or_insert_with(|| self.new_group());
+        group.rows += delta;
+        for ((name, _), (state, value)) in self
+            .aggregates
+            .iter()
+            .zip(group.aggregates.iter_mut().zip(&values))
+        {
+            state.apply(value, delta, matches!(name.as_ref(), "min" | "max"));
+        }
+        Ok(Some(key))
+    }
+
+    /// Returns the row of the view for a group
+    pub fn output(&self, key: &[Value], group: &Group) -> Result<Vec<Value>> {
+        let mut values: Vec<Value> = vec![];
+        for output in &self.outputs {
+            values.push(match output {
+                Output::Key(i) => key[*i].clone(),
+                Output::Aggregate(i) => group.aggregates[*i].value(&self.aggregates[*i].0)?,
+            });
+        }
+        Ok(values)
+    }
+}
@@ -0,0 +1,98 @@
+use crate::error::{Result, SQLRiteError};
+use crate::sql::db::value::Value;
+use crate::sql::executor::expr::referenced_columns;
+use serde::{Deserialize, Serialize};
+use sqlparser::ast::Expr;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// An SQL index over one or more columns or expressions of a table.
+///
+/// Every entry maps the values of the indexed expressions (the key) to the set of ROWIDs
+/// holding those values, so non-unique indexes can point at many rows per key.
+/// A partial index only holds the rows that satisfy its `predicate`.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct Index {
+    /// Name of the index
+    pub name: String,
+    /// Names of the key parts, in key order. Expressions are named by their SQL text
+    pub columns: Vec<String>,
+    /// Expressions computing each key part, a plain column is an `Expr::Identifier`
+    pub expressions: Vec<Expr>,
+    /// WHERE clause of a partial index
+    pub predicate: Option<Expr>,
+    /// Value representing if the index was declared UNIQUE
+    pub is_unique: bool,
+    /// BTreeMap mapping every key to the ROWIDs that hold it
+    pub entries: BTreeMap<Vec<Value>, BTreeSet<i64>>,
+}
+
+impl Index {
+    pub fn new(
+        name: String,
+        expressions: Vec<Expr>,
+        predicate: Option<Expr>,
+        is_unique: bool,
+    ) -> Self {
+        let columns = expressions
+            .iter()
+            .map(|expr| match expr {
+                Expr::Identifier(ident) => ident.value.to_string(),
+                _ => expr.to_string(),
+            })
+            .collect();
+        Index {
+            name,
+            columns,
+            expressions,
+            predicate,
+            is_unique,
+            entries: BTreeMap::new(),
+        }
+    }
+
+    /// Returns true if the index reads the given column, either in its key or in its predicate
+    pub fn contains_column(&self, column: &str) -> bool {
+        self.expressions
+            .iter()
+            .chain(self.predicate.iter())
+            .any(|expr| referenced_columns(expr).iter().any(|col| col == column))
+    }
+
+    /// Checks that a key could be added without breaking the UNIQUE constraint.
+    /// Like SQLite, keys containing a NULL never conflict with each other.
+    /// `rowid` is the row the key belongs to, it does not conflict with itself.
+    pub fn validate_unique(&self, key: &[Value], rowid: Option<i64>) -> Result<()> {
+        if !self.is_unique || key.iter().any(|val| val.is_null()) {
+            return Ok(());
+        }
+        if let Some(rowids) = self.entries.get(key) {
+            if rowids.iter().any(|id| Some(*id) != rowid) {
+                return Err(SQLRiteError::General(format!(
+                    "UNIQUE constraint failed: index '{}' already contains ({}) for column(s) {}",
+                    self.name,
+                    key.iter()
+                        .map(|val| val.to_string())
+                        .collect::<Vec<String>>()
+                        .join(", "),
+                    self.columns.join(", ")
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds a ROWID under the given key
+    pub fn insert(&mut self, key: Vec<Value>, rowid: i64) {
+        self.entries.entry(key).or_default().insert(rowid);
+    }
+
+    /// Removes a ROWID from the given key, dropping the key once no rows hold it
+    pub fn remove(&mut self, key: &[Value], rowid: i64) {
+        if let Some(rowids) = self.entries.get_mut(key) {
+            rowids.remove(&rowid);
+            if rowids.is_empty() {
+                self.entries.remove(key);
+            }
+        }
+    }
+}
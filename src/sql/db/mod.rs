@@ -1,2 +1,9 @@
 pub mod database;
+pub mod foreign_key;
+pub mod incremental;
+pub mod index;
+pub mod stats;
 pub mod table;
+pub mod trigger;
+pub mod value;
+pub mod view;
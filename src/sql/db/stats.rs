@@ -0,0 +1,214 @@
+use crate::sql::db::index::Index;
+use crate::sql::db::table::Table;
+use crate::sql::db::value::Value;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Maximum number of buckets in the histogram of a column
+const HISTOGRAM_BUCKETS: usize = 16;
+
+/// Statistics about the rows of a table, collected by ANALYZE and used by the
+/// query planner to estimate how many rows each access path reads.
+/// They are not kept up to date by later writes, like in SQLite.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct TableStats {
+    /// Number of rows in the table
+    pub row_count: usize,
+    /// Statistics of every column, keyed by column name
+    pub columns: HashMap<String, ColumnStats>,
+    /// Statistics of every index, keyed by index name
+    pub indexes: HashMap<String, IndexStats>,
+}
+
+/// Statistics about the values stored in a column
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct ColumnStats {
+    /// Number of different values, NULL not included
+    pub distinct_count: usize,
+    /// Number of rows holding NULL
+    pub null_count: usize,
+    /// Equi-depth histogram of the values that are not NULL, in ascending order
+    pub histogram: Vec<Bucket>,
+}
+
+/// A bucket of an equi-depth histogram, holding the values greater than the upper
+/// bound of the previous bucket and lower or equal to its own
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct Bucket {
+    /// Greatest value in the bucket
+    pub upper: Value,
+    /// Number of rows in the bucket
+    pub count: usize,
+    /// Number of different values in the bucket
+    pub distinct_count: usize,
+    /// Number of rows holding exactly the `upper` value
+    pub upper_count: usize,
+}
+
+/// Statistics about the keys of an index
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct IndexStats {
+    /// Number of rows in the index, lower than the rows of the table for partial indexes
+    pub row_count: usize,
+    /// Number of different values of every key prefix: the first key part,
+    /// the first two key parts and so on
+    pub distinct_prefixes: Vec<usize>,
+}
+
+impl TableStats {
+    /// Collects the statistics of a table by reading all of its rows and indexes
+    pub fn collect(table: &Table) -> TableStats {
+        let rowids = table.rowids();
+        let columns = table
+            .columns
+            .iter()
+            .map(|col| {
+                let values: Vec<Value> = rowids
+                    .iter()
+                    .map(|rowid| table.get_value(&col.column_name, *rowid))
+                    .collect();
+                (col.column_name.to_string(), ColumnStats::collect(values))
+            })
+            .collect();
+        let indexes = table
+            .indexes
+            .values()
+            .map(|index| (index.name.to_string(), IndexStats::collect(index)))
+            .collect();
+        TableStats {
+            row_count: rowids.len(),
+            columns,
+            indexes,
+        }
+    }
+}
+
+impl ColumnStats {
+    fn collect(mut values: Vec<Value>) -> ColumnStats {
+        let total = values.len();
+        values.retain(|val| !val.is_null());
+        values.sort();
+        let null_count = total - values.len();
+
+        // Runs of equal values, as (value, number of rows)
+        let mut runs: Vec<(Value, usize)> = vec![];
+        for val in values.iter() {
+            match runs.last_mut() {
+                Some((last, count)) if last == val => *count += 1,
+                _ => runs.push((val.clone(), 1)),
+            }
+        }
+
+        // Every bucket takes whole runs until it holds its share of rows
+        let bucket_size = values.len().div_ceil(HISTOGRAM_BUCKETS);
+        let mut histogram: Vec<Bucket> = vec![];
+        let mut current: Option<Bucket> = None;
+        for (val, count) in runs.iter() {
+            let bucket = current.get_or_insert(Bucket {
+                upper: val.clone(),
+                count: 0,
+                distinct_count: 0,
+                upper_count: 0,
+            });
+            bucket.upper = val.clone();
+            bucket.count += count;
+            bucket.distinct_count += 1;
+            bucket.upper_count = *count;
+            if bucket.count >= bucket_size {
+                histogram.extend(current.take());
+            }
+        }
+        histogram.extend(current);
+
+        ColumnStats {
+            distinct_count: runs.len(),
+            null_count,
+            histogram,
+        }
+    }
+
+    /// Number of rows holding a value that is not NULL
+    fn non_null_count(&self) -> usize {
+        self.histogram.iter().map(|bucket| bucket.count).sum()
+    }
+
+    /// Estimates how many rows hold a value equal to `value`, or to any value
+    /// when it is not known before running the query
+    pub fn estimate_equal(&self, value: Option<&Value>) -> f64 {
+        let average = self.non_null_count() as f64 / self.distinct_count.max(1) as f64;
+        let value = match value {
+            Some(value) if !value.is_null() => value,
+            Some(_) => return 0.0,
+            None => return average,
+        };
+        match self.histogram.iter().find(|bucket| *value <= bucket.upper) {
+            Some(bucket) if *value == bucket.upper => bucket.upper_count as f64,
+            Some(bucket) => {
+                let others = bucket.distinct_count.saturating_sub(1).max(1);
+                (bucket.count - bucket.upper_count) as f64 / others as f64
+            }
+            // Greater than any value in the table
+            None => 0.0,
+        }
+    }
+
+    /// Estimates how many rows hold a value lower than `value`, including the rows
+    /// equal to it if `inclusive` is set
+    pub fn estimate_less(&self, value: &Value, inclusive: bool) -> f64 {
+        let mut rows = 0.0;
+        for bucket in &self.histogram {
+            if bucket.upper < *value || (inclusive && bucket.upper == *value) {
+                rows += bucket.count as f64;
+            } else {
+                if bucket.upper == *value {
+                    rows += (bucket.count - bucket.upper_count) as f64;
+                } else {
+                    // Assuming the values are spread evenly inside the bucket
+                    rows += bucket.count as f64 / 2.0;
+                }
+                break;
+            }
+        }
+        rows
+    }
+}
+
+impl IndexStats {
+    fn collect(index: &Index) -> IndexStats {
+        let parts = index.expressions.len();
+        let mut distinct_prefixes = vec![0; parts];
+        let mut previous: Option<&Vec<Value>> = None;
+        let mut row_count = 0;
+        // Keys are sorted, so a prefix is new whenever it differs from the previous key's
+        for (key, rowids) in index.entries.iter() {
+            let common = match previous {
+                Some(previous) => key
+                    .iter()
+                    .zip(previous.iter())
+                    .take_while(|(a, b)| a == b)
+                    .count(),
+                None => 0,
+            };
+            for distinct in distinct_prefixes.iter_mut().skip(common) {
+                *distinct += 1;
+            }
+            row_count += rowids.len();
+            previous = Some(key);
+        }
+        IndexStats {
+            row_count,
+            distinct_prefixes,
+        }
+    }
+
+    /// Estimates how many rows share the same values for the first `parts` key parts
+    pub fn estimate_equal(&self, parts: usize) -> f64 {
+        match parts
+            .checked_sub(1)
+            .and_then(|i| self.distinct_prefixes.get(i))
+        {
+            Some(distinct) => self.row_count as f64 / (*distinct).max(1) as f64,
+            None => self.row_count as f64,
+        }
+    }
+}
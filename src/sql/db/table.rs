@@ -1,22 +1,37 @@
 use crate::error::{Result, SQLRiteError};
-use crate::sql::parser::create::CreateQuery;
+use crate::sql::db::foreign_key::ForeignKey;
+use crate::sql::db::index::Index;
+use crate::sql::db::stats::TableStats;
+use crate::sql::db::value::Value;
+use crate::sql::executor::expr::{
+    evaluate, is_rowid_alias, is_truthy, referenced_columns, rename_column, NoRow, RowSource,
+    TableRow, ValuesRow,
+};
+use crate::sql::parser::create::{
+    generated_order, CreateQuery, ParsedCheck, ParsedColumn, ParsedGenerated,
+};
 use serde::{Deserialize, Serialize};
+use sqlparser::ast::{Expr, Ident};
 use std::cell::RefCell;
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fmt;
 use std::rc::Rc;
 
 use prettytable::{Cell as PrintCell, Row as PrintRow, Table as PrintTable};
 
+/// Prefix of the names given to indexes created implicitly for PRIMARY KEY and UNIQUE columns
+pub const AUTOINDEX_PREFIX: &str = "sqlite_autoindex_";
+
 /// SQLRite data types
 /// Mapped after SQLite Data Type Storage Classes and SQLite Affinity Type
 /// (Datatypes In SQLite Version 3)[https://www.sqlite.org/datatype3.html]
-#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
 pub enum DataType {
     Integer,
     Text,
     Real,
     Bool,
+    Blob,
     None,
     Invalid,
 }
@@ -28,11 +43,9 @@ impl DataType {
             "text" => DataType::Text,
             "real" => DataType::Real,
             "bool" => DataType::Bool,
+            "blob" => DataType::Blob,
             "none" => DataType::None,
-            _ => {
-                eprintln!("Invalid data type given {}", cmd);
-                return DataType::Invalid;
-            }
+            _ => DataType::Invalid,
         }
     }
 }
@@ -44,6 +57,7 @@ impl fmt::Display for DataType {
             DataType::Text => f.write_str("Text"),
             DataType::Real => f.write_str("Real"),
             DataType::Bool => f.write_str("Boolean"),
+            DataType::Blob => f.write_str("Blob"),
             DataType::None => f.write_str("None"),
             DataType::Invalid => f.write_str("Invalid"),
         }
@@ -60,26 +74,85 @@ pub struct Table {
     pub columns: Vec<Column>,
     /// HashMap with information about each row
     pub rows: Rc<RefCell<HashMap<String, Row>>>,
-    /// HashMap of SQL indexes on this table.
-    pub indexes: HashMap<String, String>,
+    /// HashMap of SQL indexes on this table, keyed by index name
+    pub indexes: HashMap<String, Index>,
     /// ROWID of most recent insert
     pub last_rowid: i64,
-    /// PRIMARY KEY Column name, if table does not have PRIMARY KEY this would be -1
-    pub primary_key: String,
+    /// Value representing if the INTEGER PRIMARY KEY of the table was declared AUTOINCREMENT
+    pub autoincrement: bool,
+    /// Largest ROWID ever stored in an AUTOINCREMENT table, like the `sqlite_sequence` table
+    /// of SQLite, so ROWIDs of deleted rows are never handed out again
+    pub sequence: i64,
+    /// PRIMARY KEY of the table, `None` if the table does not have one
+    pub primary_key: Option<PrimaryKey>,
+    /// Statistics collected by the last ANALYZE of the table, if any
+    pub stats: Option<TableStats>,
+    /// CHECK constraints every row of the table must satisfy
+    pub checks: Vec<CheckConstraint>,
+    /// ROWIDs of every row in the table, including the rows holding only NULLs,
+    /// which have no value stored in any column
+    pub stored_rowids: BTreeSet<i64>,
+    /// FOREIGN KEY constraints of the table, referring to rows of other tables
+    pub foreign_keys: Vec<ForeignKey>,
+}
+
+/// Copies the whole table, rows included, so the copy can be changed
+/// without touching the original, e.g. to undo a transaction
+impl Clone for Table {
+    fn clone(&self) -> Self {
+        Table {
+            tb_name: self.tb_name.to_string(),
+            columns: self.columns.clone(),
+            rows: Rc::new(RefCell::new(self.rows.borrow().clone())),
+            indexes: self.indexes.clone(),
+            last_rowid: self.last_rowid,
+            autoincrement: self.autoincrement,
+            sequence: self.sequence,
+            primary_key: self.primary_key.clone(),
+            stats: self.stats.clone(),
+            checks: self.checks.clone(),
+            stored_rowids: self.stored_rowids.clone(),
+            foreign_keys: self.foreign_keys.clone(),
+        }
+    }
 }
 
 impl Table {
     pub fn new(create_query: CreateQuery) -> Self {
         let table_name = create_query.table_name;
-        let mut primary_key: String = String::from("-1");
+        let mut primary_key: Option<PrimaryKey> = None;
         let columns = create_query.columns;
 
         let mut table_cols: Vec<Column> = vec![];
+        let mut table_indexes: HashMap<String, Index> = HashMap::new();
         let table_rows: Rc<RefCell<HashMap<String, Row>>> = Rc::new(RefCell::new(HashMap::new()));
+        let mut checks: Vec<CheckConstraint> = vec![];
+        let mut foreign_keys: Vec<ForeignKey> = vec![];
         for col in &columns {
             let col_name = &col.name;
-            if col.is_pk {
-                primary_key = col_name.to_string();
+            if col.is_pk && primary_key.is_none() {
+                primary_key = Some(PrimaryKey {
+                    columns: vec![col_name.to_string()],
+                    rowid_alias: col.declared_integer,
+                });
+            }
+            // PRIMARY KEY and UNIQUE columns are backed by an index named the same way SQLite does
+            if col.is_unique {
+                let index_name = format!(
+                    "{}{}_{}",
+                    AUTOINDEX_PREFIX,
+                    table_name,
+                    table_indexes.len() + 1
+                );
+                table_indexes.insert(
+                    index_name.to_string(),
+                    Index::new(
+                        index_name,
+                        vec![Expr::Identifier(Ident::new(col_name))],
+                        None,
+                        true,
+                    ),
+                );
             }
             table_cols.push(Column::new(
                 col_name.to_string(),
@@ -87,69 +160,121 @@ impl Table {
                 col.is_pk,
                 col.not_null,
                 col.is_unique,
+                col.default.clone(),
+                col.generated.as_ref().map(Generated::new),
             ));
+            for check in &col.checks {
+                checks.push(CheckConstraint::new(check, Some(col_name.to_string())));
+            }
+            foreign_keys.extend(col.foreign_key.iter().map(ForeignKey::new));
 
-            match DataType::new(col.datatype.to_string()) {
-                DataType::Integer => table_rows
-                    .clone()
-                    .borrow_mut()
-                    .insert(col.name.to_string(), Row::Integer(BTreeMap::new())),
-                DataType::Real => table_rows
-                    .clone()
-                    .borrow_mut()
-                    .insert(col.name.to_string(), Row::Real(BTreeMap::new())),
-                DataType::Text => table_rows
-                    .clone()
-                    .borrow_mut()
-                    .insert(col.name.to_string(), Row::Text(BTreeMap::new())),
-                DataType::Bool => table_rows
-                    .clone()
-                    .borrow_mut()
-                    .insert(col.name.to_string(), Row::Bool(BTreeMap::new())),
-                DataType::Invalid => table_rows
-                    .clone()
-                    .borrow_mut()
-                    .insert(col.name.to_string(), Row::None),
-                DataType::None => table_rows
-                    .clone()
-                    .borrow_mut()
-                    .insert(col.name.to_string(), Row::None),
-            };
+            table_rows.borrow_mut().insert(
+                col.name.to_string(),
+                Row::new(&DataType::new(col.datatype.to_string())),
+            );
         }
 
+        // Keys over several columns are backed by a composite index
+        for key in &create_query.keys {
+            if key.is_primary {
+                let rowid_alias = match key.columns.as_slice() {
+                    [col_name] => columns
+                        .iter()
+                        .any(|col| col.name == *col_name && col.declared_integer),
+                    _ => false,
+                };
+                primary_key = Some(PrimaryKey {
+                    columns: key.columns.to_vec(),
+                    rowid_alias,
+                });
+            }
+            let index_name = format!(
+                "{}{}_{}",
+                AUTOINDEX_PREFIX,
+                table_name,
+                table_indexes.len() + 1
+            );
+            table_indexes.insert(
+                index_name.to_string(),
+                Index::new(
+                    index_name,
+                    key.columns
+                        .iter()
+                        .map(|col_name| Expr::Identifier(Ident::new(col_name)))
+                        .collect(),
+                    None,
+                    true,
+                ),
+            );
+            for col in table_cols.iter_mut() {
+                if key.columns.contains(&col.column_name) {
+                    col.is_indexed = true;
+                }
+            }
+        }
+
+        for check in &create_query.checks {
+            checks.push(CheckConstraint::new(check, None));
+        }
+        foreign_keys.extend(create_query.foreign_keys.iter().map(ForeignKey::new));
+
         Table {
             tb_name: table_name,
             columns: table_cols,
             rows: table_rows,
-            indexes: HashMap::new(),
+            indexes: table_indexes,
             last_rowid: 0,
-            primary_key: primary_key,
+            autoincrement: columns.iter().any(|col| col.autoincrement),
+            sequence: 0,
+            primary_key,
+            stats: None,
+            checks,
+            stored_rowids: BTreeSet::new(),
+            foreign_keys,
         }
     }
 
-    /// Returns a `bool` informing if a `Column` with a specific name exists or not
+    /// Returns the INTEGER PRIMARY KEY column of the table, which is an alias of the ROWID.
+    /// Like in SQLite, only a PRIMARY KEY made of a single column declared with the exact
+    /// type name INTEGER is one, INT or BIGINT are not.
     ///
-    pub fn contains_column(&self, column: String) -> bool {
-        self.columns.iter().any(|col| col.column_name == column)
+    pub fn rowid_column(&self) -> Option<&str> {
+        match &self.primary_key {
+            Some(primary_key) if primary_key.rowid_alias => primary_key
+                .columns
+                .first()
+                .map(|col_name| col_name.as_str()),
+            _ => None,
+        }
     }
 
-    /// Returns an immutable reference of `sql::db::table::Column` if the table contains a
-    /// column with the specified key as a column name.
+    /// Picks the ROWID of a row inserted without one. Like SQLite, it is one more than the
+    /// largest ROWID in the table, or than the largest one ever used by an AUTOINCREMENT table.
+    /// Once the largest possible ROWID is taken, an unused one is looked for instead,
+    /// except for AUTOINCREMENT tables which can not take more rows.
     ///
-    pub fn get_column(&mut self, column_name: String) -> Result<&Column> {
-        if let Some(column) = self
-            .columns
-            .iter()
-            .filter(|c| c.column_name == column_name)
-            .collect::<Vec<&Column>>()
-            .first()
-        {
-            Ok(column)
-        } else {
-            Err(SQLRiteError::General(String::from("Column not found.")))
+    pub fn new_rowid(&self) -> Result<i64> {
+        let mut largest = self.stored_rowids.iter().next_back().copied().unwrap_or(0);
+        if self.autoincrement {
+            largest = largest.max(self.sequence);
+        }
+        match largest.checked_add(1) {
+            Some(rowid) => Ok(rowid),
+            None if self.autoincrement => Err(SQLRiteError::General(String::from(
+                "database or disk is full",
+            ))),
+            None => (1..=i64::MAX)
+                .find(|rowid| !self.contains_rowid(*rowid))
+                .ok_or_else(|| SQLRiteError::General(String::from("database or disk is full"))),
         }
     }
 
+    /// Returns a `bool` informing if a `Column` with a specific name exists or not
+    ///
+    pub fn contains_column(&self, column: String) -> bool {
+        self.columns.iter().any(|col| col.column_name == column)
+    }
+
     /// Returns an mutable reference of `sql::db::table::Column` if the table contains a
     /// column with the specified key as a column name.
     ///
@@ -162,194 +287,854 @@ impl Table {
         Err(SQLRiteError::General(String::from("Column not found.")))
     }
 
-    /// Validates if columns and values being inserted violate the UNIQUE constraint
-    /// As a reminder the PRIMARY KEY column automatically also is a UNIQUE column.
+    /// Removes an index from the table. Indexes backing a PRIMARY KEY or UNIQUE constraint
+    /// are part of the table definition and can only go away with the table itself.
     ///
-    pub fn validate_unique_constraint(
-        &mut self,
-        cols: &Vec<String>,
-        values: &Vec<String>,
-    ) -> Result<()> {
-        for (idx, name) in cols.iter().enumerate() {
-            let column = self.get_column_mut(name.to_string()).unwrap();
-            // println!(
-            //     "name: {} | is_pk: {} | is_unique: {}, not_null: {}",
-            //     name, column.is_pk, column.is_unique, column.not_null
-            // );
-            if column.is_unique {
-                let col_idx = &column.index;
-                if *name == *column.column_name {
-                    let val = &values[idx];
-                    match col_idx {
-                        Index::Integer(index) => {
-                            if index.contains_key(&val.parse::<i32>().unwrap()) {
-                                return Err(SQLRiteError::General(format!(
-                                    "Error: unique constraint violation for column {}.
-                        Value {} already exists for column {}",
-                                    *name, val, *name
-                                )));
-                            }
-                        }
-                        Index::Text(index) => {
-                            if index.contains_key(val) {
-                                return Err(SQLRiteError::General(format!(
-                                    "Error: unique constraint violation for column {}.
-                        Value {} already exists for column {}",
-                                    *name, val, *name
-                                )));
-                            }
-                        }
-                        Index::None => {
-                            return Err(SQLRiteError::General(format!(
-                                "Error: cannot find index for column {}",
-                                name
-                            )));
-                        }
-                    };
+    pub fn drop_index(&mut self, index_name: String) -> Result<()> {
+        if index_name.starts_with(AUTOINDEX_PREFIX) {
+            return Err(SQLRiteError::General(format!(
+                "index associated with UNIQUE or PRIMARY KEY constraint cannot be dropped: {}",
+                index_name
+            )));
+        }
+        match self.indexes.remove(&index_name) {
+            Some(_) => {
+                if let Some(stats) = self.stats.as_mut() {
+                    stats.indexes.remove(&index_name);
+                }
+                for col in self.columns.iter_mut() {
+                    col.is_indexed = self
+                        .indexes
+                        .values()
+                        .any(|index| index.contains_column(&col.column_name));
                 }
+                Ok(())
             }
+            None => Err(SQLRiteError::General(format!(
+                "no such index: {}",
+                index_name
+            ))),
         }
-        return Ok(());
     }
 
-    /// Inserts all VALUES in its approprieta COLUMNS, using the ROWID an embedded INDEX on all ROWS
-    /// Every `Table` keeps track of the `last_rowid` in order to facilitate what the next one would be.
-    /// One limitation of this data structure is that we can only have one write transaction at a time, otherwise
-    /// we could have a race condition on the last_rowid.println!
+    /// Returns the ROWIDs of every row stored in the table, in ascending order
     ///
-    /// Since we are loosely modeling after SQLite, this is also a limitation of SQLite (allowing only one write transcation at a time),
-    /// So we are good. :)
+    pub fn rowids(&self) -> Vec<i64> {
+        self.stored_rowids.iter().copied().collect()
+    }
+
+    /// Adds a new column to the table. Every existing ROWID is back-filled with the
+    /// DEFAULT of the column, or left as Null if the column has no DEFAULT.
     ///
-    pub fn insert_row(&mut self, cols: &Vec<String>, values: &Vec<String>) {
-        let mut next_rowid = self.last_rowid + i64::from(1);
-
-        // Checks if table has a PRIMARY KEY
-        if self.primary_key != "-1" {
-            // Checking if primary key is in INSERT QUERY columns
-            // If it is not, assign the next_rowid to it
-            if !cols.iter().any(|col| col == &self.primary_key) {
-                let rows_clone = Rc::clone(&self.rows);
-                let mut row_data = rows_clone.as_ref().borrow_mut();
-                let mut table_col_data = row_data.get_mut(&self.primary_key).unwrap();
-
-                // Getting the header based on the column name
-                let column_headers = self.get_column_mut(self.primary_key.to_string()).unwrap();
-
-                // Getting index for column, if it exist
-                let col_index = column_headers.get_mut_index();
-
-                // We only AUTO ASSIGN in case the ROW is a PRIMARY KEY and INTEGER type
-                match &mut table_col_data {
-                    Row::Integer(tree) => {
-                        let val = next_rowid as i32;
-                        tree.insert(next_rowid.clone(), val);
-                        if let Index::Integer(index) = col_index {
-                            index.insert(val, next_rowid.clone());
-                        }
-                    }
-                    _ => (),
-                }
-            } else {
-                // If PRIMARY KEY Column is in the Column list from INSERT Query,
-                // We get the value assigned to it in the VALUES part of the query
-                // and assign it to next_rowid, so every value if indexed by same rowid
-                // Also, next ROWID should keep AUTO INCREMENTING from last ROWID
-                let rows_clone = Rc::clone(&self.rows);
-                let mut row_data = rows_clone.as_ref().borrow_mut();
-                let mut table_col_data = row_data.get_mut(&self.primary_key).unwrap();
-
-                // Again, this is only valid for PRIMARY KEYs of INTEGER type
-                match &mut table_col_data {
-                    Row::Integer(_) => {
-                        for i in 0..cols.len() {
-                            // Getting column name
-                            let key = &cols[i];
-                            if key == &self.primary_key {
-                                let val = &values[i];
-                                next_rowid = val.parse::<i64>().unwrap();
-                            }
-                        }
-                    }
-                    _ => (),
+    pub fn add_column(&mut self, column: ParsedColumn) -> Result<()> {
+        if self.contains_column(column.name.to_string()) {
+            return Err(SQLRiteError::General(format!(
+                "duplicate column name: {}",
+                column.name
+            )));
+        }
+        if column.is_pk {
+            return Err(SQLRiteError::General(String::from(
+                "Cannot add a PRIMARY KEY column",
+            )));
+        }
+        if column.is_unique {
+            return Err(SQLRiteError::General(String::from(
+                "Cannot add a UNIQUE column",
+            )));
+        }
+        // Like SQLite, existing rows are back-filled with a single value,
+        // so defaults such as CURRENT_TIMESTAMP are not allowed
+        let default = match &column.default {
+            Some(Expr::Function(function)) if function.args.is_empty() => {
+                return Err(SQLRiteError::General(String::from(
+                    "Cannot add a column with non-constant default",
+                )))
+            }
+            Some(default) => evaluate(default, &NoRow)?,
+            None => Value::Null,
+        };
+        // A generated column is computed for the existing rows instead, checked below
+        if column.not_null && default.is_null() && column.generated.is_none() {
+            return Err(SQLRiteError::General(String::from(
+                "Cannot add a NOT NULL column with default value NULL",
+            )));
+        }
+        if column.foreign_key.is_some() && !default.is_null() {
+            return Err(SQLRiteError::General(String::from(
+                "Cannot add a REFERENCES column with non-NULL default value",
+            )));
+        }
+
+        let table_col = Column::new(
+            column.name.to_string(),
+            column.datatype.to_string(),
+            column.is_pk,
+            column.not_null,
+            column.is_unique,
+            column.default.clone(),
+            column.generated.as_ref().map(Generated::new),
+        );
+        let generated = column.generated.iter().map(|generated| &generated.expr);
+        for expr in column
+            .checks
+            .iter()
+            .map(|check| &check.expr)
+            .chain(generated)
+        {
+            for col_name in referenced_columns(expr) {
+                if col_name != column.name
+                    && !is_rowid_alias(&col_name)
+                    && !self.contains_column(col_name.to_string())
+                {
+                    return Err(SQLRiteError::General(format!(
+                        "no such column: {}",
+                        col_name
+                    )));
                 }
             }
         }
+        let mut col_data = Row::new(&table_col.datatype);
+        let rowids = self.rowids();
+        for rowid in rowids.iter() {
+            col_data.set_value(*rowid, &default)?;
+        }
 
-        // This block checks if there are any columns from table missing
-        // from INSERT statement. If there are, we add "Null" to the column.
-        // We do this because otherwise the ROWID reference for each value would be wrong
-        // Since rows not always have the same length.
-        let column_names = self
-            .columns
+        self.rows
+            .borrow_mut()
+            .insert(column.name.to_string(), col_data);
+        self.columns.push(table_col);
+
+        // Like SQLite, the rows already in the table must satisfy the new CHECK constraints,
+        // and the NOT NULL constraint of a generated column
+        let checks: Vec<CheckConstraint> = column
+            .checks
             .iter()
-            .map(|col| col.column_name.to_string())
-            .collect::<Vec<String>>();
-        let mut j: usize = 0;
-        // For every column in the INSERT statement
-        for i in 0..column_names.len() {
-            let mut val = String::from("Null");
-            let key = &column_names[i];
-
-            if let Some(key) = &cols.get(j) {
-                if &key.to_string() == &column_names[i] {
-                    // Getting column name
-                    val = values[j].to_string();
-                    j += 1;
-                } else {
-                    if &self.primary_key == &column_names[i] {
-                        continue;
-                    }
+            .map(|check| CheckConstraint::new(check, Some(column.name.to_string())))
+            .collect();
+        for rowid in rowids {
+            let row = TableRow { table: self, rowid };
+            let result = checks
+                .iter()
+                .try_for_each(|check| check.validate(&row))
+                .and_then(|_| self.validate_generated_not_null(rowid));
+            if let Err(err) = result {
+                self.columns.pop();
+                self.rows.borrow_mut().remove(&column.name);
+                return Err(err);
+            }
+        }
+        self.checks.extend(checks);
+        self.foreign_keys
+            .extend(column.foreign_key.iter().map(ForeignKey::new));
+        Ok(())
+    }
+
+    /// Renames a column, moving its data to the new name and updating the PRIMARY KEY
+    /// and any index referencing it.
+    ///
+    pub fn rename_column(&mut self, old_name: String, new_name: String) -> Result<()> {
+        if self.contains_column(new_name.to_string()) {
+            return Err(SQLRiteError::General(format!(
+                "duplicate column name: {}",
+                new_name
+            )));
+        }
+        let column = self
+            .get_column_mut(old_name.to_string())
+            .map_err(|_| SQLRiteError::General(format!("no such column: \"{}\"", old_name)))?;
+        column.column_name = new_name.to_string();
+
+        let mut row_data = self.rows.borrow_mut();
+        if let Some(col_data) = row_data.remove(&old_name) {
+            row_data.insert(new_name.to_string(), col_data);
+        }
+        if let Some(primary_key) = self.primary_key.as_mut() {
+            for col in primary_key.columns.iter_mut() {
+                if *col == old_name {
+                    *col = new_name.to_string();
                 }
-            } else {
-                if &self.primary_key == &column_names[i] {
-                    continue;
+            }
+        }
+        for index in self.indexes.values_mut() {
+            for (name, expr) in index.columns.iter_mut().zip(index.expressions.iter_mut()) {
+                rename_column(expr, &old_name, &new_name);
+                if let Expr::Identifier(ident) = expr {
+                    *name = ident.value.to_string();
+                }
+            }
+            if let Some(predicate) = index.predicate.as_mut() {
+                rename_column(predicate, &old_name, &new_name);
+            }
+        }
+        for generated in self
+            .columns
+            .iter_mut()
+            .filter_map(|col| col.generated.as_mut())
+        {
+            rename_column(&mut generated.expr, &old_name, &new_name);
+        }
+        for check in self.checks.iter_mut() {
+            rename_column(&mut check.expr, &old_name, &new_name);
+            if check.column.as_ref() == Some(&old_name) {
+                check.column = Some(new_name.to_string());
+            }
+        }
+        for foreign_key in self.foreign_keys.iter_mut() {
+            for col in foreign_key.columns.iter_mut() {
+                if *col == old_name {
+                    *col = new_name.to_string();
                 }
             }
+        }
+        if let Some(stats) = self.stats.as_mut() {
+            if let Some(column_stats) = stats.columns.remove(&old_name) {
+                stats.columns.insert(new_name, column_stats);
+            }
+        }
+        Ok(())
+    }
 
-            // Getting the rows from the column name
-            let rows_clone = Rc::clone(&self.rows);
-            let mut row_data = rows_clone.as_ref().borrow_mut();
-            let mut table_col_data = row_data.get_mut(key).unwrap();
+    /// Removes a column and all of its data from the table. Like SQLite, columns that are
+    /// PRIMARY KEY, UNIQUE or indexed can not be dropped.
+    ///
+    pub fn drop_column(&mut self, name: String) -> Result<()> {
+        let column = self
+            .get_column_mut(name.to_string())
+            .map_err(|_| SQLRiteError::General(format!("no such column: \"{}\"", name)))?;
+        if column.is_pk {
+            return Err(SQLRiteError::General(format!(
+                "cannot drop PRIMARY KEY column: \"{}\"",
+                name
+            )));
+        }
+        if column.is_unique {
+            return Err(SQLRiteError::General(format!(
+                "cannot drop UNIQUE column: \"{}\"",
+                name
+            )));
+        }
+        if self
+            .indexes
+            .values()
+            .any(|index| index.contains_column(&name))
+        {
+            return Err(SQLRiteError::General(format!(
+                "cannot drop indexed column: \"{}\"",
+                name
+            )));
+        }
+        if self
+            .foreign_keys
+            .iter()
+            .any(|foreign_key| foreign_key.columns.contains(&name))
+        {
+            return Err(SQLRiteError::General(format!(
+                "cannot drop column used in a FOREIGN KEY constraint: \"{}\"",
+                name
+            )));
+        }
+        // CHECK constraints declared on the column itself go away with it
+        if self.checks.iter().any(|check| {
+            check.column.as_ref() != Some(&name) && referenced_columns(&check.expr).contains(&name)
+        }) {
+            return Err(SQLRiteError::General(format!(
+                "cannot drop column used in a CHECK constraint: \"{}\"",
+                name
+            )));
+        }
+        if self.columns.iter().any(|col| {
+            col.column_name != name
+                && col
+                    .generated
+                    .as_ref()
+                    .is_some_and(|generated| referenced_columns(&generated.expr).contains(&name))
+        }) {
+            return Err(SQLRiteError::General(format!(
+                "cannot drop column used in a generated column: \"{}\"",
+                name
+            )));
+        }
+        if self.columns.len() == 1 {
+            return Err(SQLRiteError::General(format!(
+                "cannot drop column \"{}\": no other columns exist",
+                name
+            )));
+        }
 
-            // Getting the header based on the column name
-            let column_headers = self.get_column_mut(key.to_string()).unwrap();
+        self.columns.retain(|col| col.column_name != name);
+        self.checks
+            .retain(|check| check.column.as_ref() != Some(&name));
+        self.rows.borrow_mut().remove(&name);
+        if let Some(stats) = self.stats.as_mut() {
+            stats.columns.remove(&name);
+        }
+        Ok(())
+    }
 
-            // Getting index for column, if it exist
-            let col_index = column_headers.get_mut_index();
+    /// Creates a new index over the given columns or expressions and back-fills it with
+    /// every row already in the table that satisfies the `predicate` of a partial index.
+    /// Fails without creating anything if a UNIQUE index would end up with duplicated keys.
+    ///
+    pub fn create_index(
+        &mut self,
+        index_name: String,
+        expressions: Vec<Expr>,
+        predicate: Option<Expr>,
+        is_unique: bool,
+    ) -> Result<()> {
+        for expr in expressions.iter().chain(predicate.iter()) {
+            for col_name in referenced_columns(expr) {
+                if !self.contains_column(col_name.to_string()) {
+                    return Err(SQLRiteError::General(format!(
+                        "no such column: {}",
+                        col_name
+                    )));
+                }
+            }
+        }
+
+        let mut index = Index::new(index_name.to_string(), expressions, predicate, is_unique);
+        for rowid in self.rowids() {
+            if let Some(key) = self.get_index_key(&index, rowid)? {
+                index.validate_unique(&key, None)?;
+                index.insert(key, rowid);
+            }
+        }
+
+        for col in self.columns.iter_mut() {
+            if index.contains_column(&col.column_name) {
+                col.is_indexed = true;
+            }
+        }
+        self.indexes.insert(index_name, index);
+        Ok(())
+    }
 
-            match &mut table_col_data {
-                Row::Integer(tree) => {
-                    let val = val.parse::<i32>().unwrap();
-                    tree.insert(next_rowid.clone(), val);
-                    if let Index::Integer(index) = col_index {
-                        index.insert(val, next_rowid.clone());
+    /// Returns the value stored in a column for the given ROWID, Null if there is none
+    ///
+    pub fn get_value(&self, column_name: &str, rowid: i64) -> Value {
+        if let Some(generated) = self.virtual_column(column_name) {
+            if !self.contains_rowid(rowid) {
+                return Value::Null;
+            }
+            // The expression already evaluated fine when the row was written,
+            // and evaluates the same way from the same values
+            return self
+                .compute_generated(generated, &TableRow { table: self, rowid }, column_name)
+                .unwrap_or(Value::Null);
+        }
+        let row_data = self.rows.borrow();
+        match row_data.get(column_name) {
+            Some(col_data) => col_data.get_value(rowid),
+            None => Value::Null,
+        }
+    }
+
+    /// Checks that an UPDATE can assign a column, which generated columns can not be
+    ///
+    pub fn validate_assignment(&self, column_name: &str) -> Result<()> {
+        match self
+            .columns
+            .iter()
+            .find(|col| col.column_name == column_name)
+        {
+            Some(column) if column.generated.is_some() => Err(SQLRiteError::General(format!(
+                "cannot UPDATE generated column \"{}\"",
+                column_name
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns the GENERATED ALWAYS AS clause of a VIRTUAL column, whose value is not stored
+    fn virtual_column(&self, column_name: &str) -> Option<&Generated> {
+        self.columns
+            .iter()
+            .find(|col| col.column_name == column_name)
+            .and_then(|col| col.generated.as_ref())
+            .filter(|generated| !generated.stored)
+    }
+
+    /// Returns the positions of the generated columns, in the order they are computed in
+    fn generated_columns(&self) -> Result<Vec<usize>> {
+        let columns: Vec<(&str, Option<&Expr>)> = self
+            .columns
+            .iter()
+            .map(|col| {
+                (
+                    col.column_name.as_str(),
+                    col.generated.as_ref().map(|generated| &generated.expr),
+                )
+            })
+            .collect();
+        generated_order(&columns)
+    }
+
+    /// Computes the value of a generated column for a row, converted to the type of the column
+    fn compute_generated(
+        &self,
+        generated: &Generated,
+        row: &dyn RowSource,
+        column_name: &str,
+    ) -> Result<Value> {
+        let value = evaluate(&generated.expr, row)?;
+        let datatype = self
+            .columns
+            .iter()
+            .find(|col| col.column_name == column_name)
+            .map_or(DataType::Invalid, |col| col.datatype.clone());
+        let mut col_data = Row::new(&datatype);
+        if matches!(col_data, Row::None) {
+            return Ok(value);
+        }
+        col_data.set_value(0, &value)?;
+        Ok(col_data.get_value(0))
+    }
+
+    /// Recomputes the STORED generated columns of a row after some of its columns changed
+    fn store_generated(&mut self, rowid: i64) -> Result<()> {
+        for i in self.generated_columns()? {
+            let column = &self.columns[i];
+            let generated = match &column.generated {
+                Some(generated) if generated.stored => generated,
+                _ => continue,
+            };
+            let value = self.compute_generated(
+                generated,
+                &TableRow { table: self, rowid },
+                &column.column_name,
+            )?;
+            let column_name = column.column_name.to_string();
+            self.set_values(rowid, &[(column_name, value)])?;
+        }
+        Ok(())
+    }
+
+    /// Checks that the generated NOT NULL columns of a row are not NULL
+    fn validate_generated_not_null(&self, rowid: i64) -> Result<()> {
+        for column in &self.columns {
+            if column.generated.is_some()
+                && column.not_null
+                && self.get_value(&column.column_name, rowid).is_null()
+            {
+                return Err(SQLRiteError::General(format!(
+                    "NOT NULL constraint failed: {}.{}",
+                    self.tb_name, column.column_name
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns true if a row is stored under the given ROWID
+    ///
+    pub fn contains_rowid(&self, rowid: i64) -> bool {
+        self.stored_rowids.contains(&rowid)
+    }
+
+    /// Builds the key a row is stored under in an index.
+    /// Returns `None` if the row is left out of a partial index.
+    fn get_index_key(&self, index: &Index, rowid: i64) -> Result<Option<Vec<Value>>> {
+        let row = TableRow { table: self, rowid };
+        if let Some(predicate) = &index.predicate {
+            if is_truthy(&evaluate(predicate, &row)?) != Some(true) {
+                return Ok(None);
+            }
+        }
+        let mut key: Vec<Value> = vec![];
+        for expr in &index.expressions {
+            key.push(evaluate(expr, &row)?);
+        }
+        Ok(Some(key))
+    }
+
+    /// Adds the row stored under `rowid` to every index of the table.
+    /// Nothing is indexed if the row breaks the UNIQUE constraint of any index.
+    fn index_row(&mut self, rowid: i64) -> Result<()> {
+        let mut keys: Vec<(String, Vec<Value>)> = vec![];
+        for index in self.indexes.values() {
+            if let Some(key) = self.get_index_key(index, rowid)? {
+                index.validate_unique(&key, Some(rowid))?;
+                keys.push((index.name.to_string(), key));
+            }
+        }
+        for (index_name, key) in keys {
+            if let Some(index) = self.indexes.get_mut(&index_name) {
+                index.insert(key, rowid);
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes the row stored under `rowid` from every index of the table
+    fn unindex_row(&mut self, rowid: i64) -> Result<()> {
+        let mut keys: Vec<(String, Vec<Value>)> = vec![];
+        for index in self.indexes.values() {
+            if let Some(key) = self.get_index_key(index, rowid)? {
+                keys.push((index.name.to_string(), key));
+            }
+        }
+        for (index_name, key) in keys {
+            if let Some(index) = self.indexes.get_mut(&index_name) {
+                index.remove(&key, rowid);
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that a row about to be inserted has a value for every NOT NULL column
+    fn validate_not_null(&self, row: &NewRow) -> Result<()> {
+        for (column, (key, val)) in self.columns.iter().zip(row.values.iter()) {
+            if column.not_null && val.is_null() {
+                return Err(SQLRiteError::General(format!(
+                    "NOT NULL constraint failed: {}.{}",
+                    self.tb_name, key
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks a row against every CHECK constraint of the table
+    fn validate_checks(&self, row: &dyn RowSource) -> Result<()> {
+        self.checks.iter().try_for_each(|check| check.validate(row))
+    }
+
+    /// Deletes the row stored under `rowid`, removing it from every index first
+    ///
+    pub fn delete_row(&mut self, rowid: i64) -> Result<()> {
+        self.unindex_row(rowid)?;
+        for col_data in self.rows.borrow_mut().values_mut() {
+            col_data.remove_value(rowid);
+        }
+        self.stored_rowids.remove(&rowid);
+        Ok(())
+    }
+
+    /// Updates the given columns of the row stored under `rowid` and re-indexes it. Updating the
+    /// INTEGER PRIMARY KEY moves the row to the new ROWID. Returns the ROWID of the row.
+    /// If the new values break a NOT NULL, CHECK or UNIQUE constraint the row is left untouched.
+    ///
+    pub fn update_row(&mut self, rowid: i64, assignments: &[(String, Value)]) -> Result<i64> {
+        for (col_name, value) in assignments {
+            let column = self
+                .columns
+                .iter()
+                .find(|col| col.column_name == *col_name)
+                .ok_or_else(|| SQLRiteError::General(format!("no such column: {}", col_name)))?;
+            if column.not_null && value.is_null() {
+                return Err(SQLRiteError::General(format!(
+                    "NOT NULL constraint failed: {}.{}",
+                    self.tb_name, col_name
+                )));
+            }
+        }
+
+        // The STORED generated columns are computed again from the new values
+        let stored = self.columns.iter().filter(|col| {
+            col.generated
+                .as_ref()
+                .is_some_and(|generated| generated.stored)
+        });
+        let old_values: Vec<(String, Value)> = assignments
+            .iter()
+            .map(|(col_name, _)| col_name)
+            .chain(stored.map(|col| &col.column_name))
+            .map(|col_name| (col_name.to_string(), self.get_value(col_name, rowid)))
+            .collect();
+
+        let new_rowid = self.updated_rowid(rowid, assignments)?;
+        self.unindex_row(rowid)?;
+        self.move_row(rowid, new_rowid)?;
+        let result = self
+            .set_values(new_rowid, assignments)
+            .and_then(|_| self.store_generated(new_rowid))
+            .and_then(|_| self.validate_generated_not_null(new_rowid))
+            .and_then(|_| {
+                self.validate_checks(&TableRow {
+                    table: self,
+                    rowid: new_rowid,
+                })
+            })
+            .and_then(|_| self.index_row(new_rowid));
+        if let Err(err) = result {
+            // Restoring the previous values, which were indexed fine before
+            self.set_values(new_rowid, &old_values)?;
+            self.move_row(new_rowid, rowid)?;
+            self.index_row(rowid)?;
+            return Err(err);
+        }
+        Ok(new_rowid)
+    }
+
+    /// Returns the ROWID a row is stored under once updated, the new value of its
+    /// INTEGER PRIMARY KEY when the update assigns one
+    fn updated_rowid(&self, rowid: i64, assignments: &[(String, Value)]) -> Result<i64> {
+        let value = match self.rowid_column() {
+            Some(rowid_column) => assignments.iter().find(|(col, _)| col == rowid_column),
+            None => None,
+        };
+        let new_rowid = match value {
+            None => return Ok(rowid),
+            Some((_, Value::Integer(new_rowid))) => *new_rowid,
+            Some((_, val)) => {
+                return Err(SQLRiteError::General(format!(
+                    "Invalid value for column type: {}",
+                    val
+                )))
+            }
+        };
+        if new_rowid != rowid && self.contains_rowid(new_rowid) {
+            return Err(SQLRiteError::General(format!(
+                "UNIQUE constraint failed: {}.{}",
+                self.tb_name,
+                self.rowid_column().unwrap_or("rowid")
+            )));
+        }
+        Ok(new_rowid)
+    }
+
+    /// Moves the values of a row to another ROWID, without touching indexes
+    fn move_row(&mut self, rowid: i64, new_rowid: i64) -> Result<()> {
+        if new_rowid == rowid {
+            return Ok(());
+        }
+        for col_data in self.rows.borrow_mut().values_mut() {
+            let value = col_data.get_value(rowid);
+            col_data.remove_value(rowid);
+            col_data.set_value(new_rowid, &value)?;
+        }
+        self.stored_rowids.remove(&rowid);
+        self.stored_rowids.insert(new_rowid);
+        if self.autoincrement && new_rowid > self.sequence {
+            self.sequence = new_rowid;
+        }
+        Ok(())
+    }
+
+    /// Applies the updates of a whole UPDATE statement, each one being the ROWID of a row and
+    /// its new column values. If any row fails, the rows already updated are restored.
+    /// Returns the ROWIDs of the updated rows, in order.
+    ///
+    pub fn update_rows(&mut self, updates: Vec<(i64, Vec<(String, Value)>)>) -> Result<Vec<i64>> {
+        let mut applied: Vec<(i64, Vec<(String, Value)>)> = vec![];
+        for (rowid, assignments) in updates {
+            let old_values: Vec<(String, Value)> = assignments
+                .iter()
+                .map(|(col_name, _)| (col_name.to_string(), self.get_value(col_name, rowid)))
+                .collect();
+            match self.update_row(rowid, &assignments) {
+                Ok(new_rowid) => applied.push((new_rowid, old_values)),
+                Err(err) => {
+                    for (rowid, old_values) in applied.into_iter().rev() {
+                        self.update_row(rowid, &old_values)?;
                     }
+                    return Err(err);
                 }
-                Row::Text(tree) => {
-                    tree.insert(next_rowid.clone(), val.to_string());
-                    if let Index::Text(index) = col_index {
-                        index.insert(val.to_string(), next_rowid.clone());
-                    }
+            }
+        }
+        Ok(applied.into_iter().map(|(rowid, _)| rowid).collect())
+    }
+
+    /// Stores typed values in the given columns of a row, without touching indexes
+    fn set_values(&mut self, rowid: i64, values: &[(String, Value)]) -> Result<()> {
+        let mut row_data = self.rows.borrow_mut();
+        for (col_name, value) in values {
+            match row_data.get_mut(col_name) {
+                Some(col_data) => col_data.set_value(rowid, value)?,
+                None => {
+                    return Err(SQLRiteError::General(format!(
+                        "no such column: {}",
+                        col_name
+                    )))
                 }
-                Row::Real(tree) => {
-                    let val = val.parse::<f32>().unwrap();
-                    tree.insert(next_rowid.clone(), val);
+            }
+        }
+        Ok(())
+    }
+
+    /// Builds the row an INSERT statement stores, without storing it. The row gets a value for
+    /// every column of the table: columns missing from the INSERT statement take their DEFAULT,
+    /// or NULL when they have none, except an INTEGER PRIMARY KEY which is AUTO ASSIGNED the ROWID.
+    ///
+    pub fn prepare_row(&self, cols: &[String], values: &[Value]) -> Result<NewRow> {
+        if let Some(column) = self
+            .columns
+            .iter()
+            .find(|col| col.generated.is_some() && cols.contains(&col.column_name))
+        {
+            return Err(SQLRiteError::General(format!(
+                "cannot INSERT into generated column \"{}\"",
+                column.column_name
+            )));
+        }
+        // We only use the PRIMARY KEY as ROWID in case it is a single INTEGER column.
+        // Whatever the PRIMARY KEY, the ROWID can also be given through one of its aliases.
+        let rowid_column = self.rowid_column().map(|col| col.to_string());
+        let position = cols.iter().position(|col| {
+            Some(col) == rowid_column.as_ref()
+                || (is_rowid_alias(col) && !self.contains_column(col.to_string()))
+        });
+
+        // If the ROWID is in the Column list from INSERT Query,
+        // We get the value assigned to it in the VALUES part of the query
+        // and assign it to next_rowid, so every value if indexed by same rowid.
+        // A NULL ROWID is AUTO ASSIGNED, like an omitted one.
+        let next_rowid = match position.map(|i| &values[i]) {
+            None | Some(Value::Null) => self.new_rowid()?,
+            Some(Value::Integer(rowid)) => *rowid,
+            Some(val) => {
+                return Err(SQLRiteError::General(format!(
+                    "Invalid value for column type: {}",
+                    val
+                )))
+            }
+        };
+
+        let mut row_values: Vec<(String, Value)> = vec![];
+        for column in &self.columns {
+            let key = &column.column_name;
+            let val = match cols.iter().position(|col| col == key) {
+                Some(i) => values[i].clone(),
+                None => match &column.default {
+                    Some(default) => evaluate(default, &NoRow)?,
+                    None => Value::Null,
+                },
+            };
+            let val = match val {
+                Value::Null if rowid_column.as_ref() == Some(key) => Value::Integer(next_rowid),
+                val => val,
+            };
+            row_values.push((key.to_string(), val));
+        }
+        // Generated columns are computed once the other values are known
+        for i in self.generated_columns()? {
+            let column = &self.columns[i];
+            if let Some(generated) = &column.generated {
+                let row = ValuesRow {
+                    table: self,
+                    rowid: next_rowid,
+                    values: &row_values,
+                };
+                row_values[i].1 = self.compute_generated(generated, &row, &column.column_name)?;
+            }
+        }
+        Ok(NewRow {
+            rowid: next_rowid,
+            values: row_values,
+        })
+    }
+
+    /// Checks a row about to be inserted against the NOT NULL and CHECK constraints of the table
+    ///
+    pub fn validate_new_row(&self, row: &NewRow) -> Result<()> {
+        self.validate_not_null(row)?;
+        self.validate_checks(&ValuesRow {
+            table: self,
+            rowid: row.rowid,
+            values: &row.values,
+        })
+    }
+
+    /// Returns the rows a row about to be inserted conflicts with, each given by the columns
+    /// of the PRIMARY KEY or UNIQUE constraint the rows share a key of, and its ROWID.
+    /// Keys containing a NULL never conflict.
+    ///
+    pub fn conflicts(&self, row: &NewRow) -> Result<Vec<(Vec<String>, i64)>> {
+        let mut conflicts: Vec<(Vec<String>, i64)> = vec![];
+        if self.contains_rowid(row.rowid) {
+            let rowid_column = self.rowid_column().unwrap_or("rowid");
+            conflicts.push((vec![rowid_column.to_string()], row.rowid));
+        }
+        let source = ValuesRow {
+            table: self,
+            rowid: row.rowid,
+            values: &row.values,
+        };
+        for index in self.indexes.values().filter(|index| index.is_unique) {
+            if let Some(predicate) = &index.predicate {
+                if is_truthy(&evaluate(predicate, &source)?) != Some(true) {
+                    continue;
                 }
-                Row::Bool(tree) => {
-                    let val = val.parse::<bool>().unwrap();
-                    tree.insert(next_rowid.clone(), val);
+            }
+            let mut key: Vec<Value> = vec![];
+            for expr in &index.expressions {
+                key.push(evaluate(expr, &source)?);
+            }
+            if key.iter().any(|val| val.is_null()) {
+                continue;
+            }
+            if let Some(rowids) = index.entries.get(&key) {
+                for rowid in rowids {
+                    conflicts.push((index.columns.to_vec(), *rowid));
                 }
-                Row::None => panic!("None data Found"),
             }
         }
+        Ok(conflicts)
+    }
+
+    /// Stores a row built by `prepare_row` in its approprieta COLUMNS, using the ROWID an embedded INDEX on all ROWS
+    /// Every `Table` keeps track of the `last_rowid` in order to facilitate what the next one would be.
+    /// One limitation of this data structure is that we can only have one write transaction at a time, otherwise
+    /// we could have a race condition on the last_rowid.println!
+    ///
+    /// Since we are loosely modeling after SQLite, this is also a limitation of SQLite (allowing only one write transcation at a time),
+    /// So we are good. :)
+    ///
+    /// If the row breaks a NOT NULL, CHECK or UNIQUE constraint nothing is stored.
+    /// Returns the ROWID of the new row.
+    ///
+    pub fn store_row(&mut self, row: &NewRow) -> Result<i64> {
+        let next_rowid = row.rowid;
+        // Storing the row would overwrite the one already holding this ROWID
+        if self.contains_rowid(next_rowid) {
+            return Err(SQLRiteError::General(format!(
+                "UNIQUE constraint failed: {}.{}",
+                self.tb_name,
+                self.rowid_column().unwrap_or("rowid")
+            )));
+        }
+        self.validate_not_null(row)?;
+
+        // The values of VIRTUAL columns are computed every time they are read instead
+        let values: Vec<(String, Value)> = row
+            .values
+            .iter()
+            .filter(|(col_name, _)| self.virtual_column(col_name).is_none())
+            .cloned()
+            .collect();
+        self.stored_rowids.insert(next_rowid);
+        let result = self
+            .set_values(next_rowid, &values)
+            .and_then(|_| {
+                self.validate_checks(&TableRow {
+                    table: self,
+                    rowid: next_rowid,
+                })
+            })
+            .and_then(|_| self.index_row(next_rowid));
+        if let Err(err) = result {
+            // Undoing the partially stored row
+            for col_data in self.rows.borrow_mut().values_mut() {
+                col_data.remove_value(next_rowid);
+            }
+            self.stored_rowids.remove(&next_rowid);
+            return Err(err);
+        }
+
         self.last_rowid = next_rowid;
+        if self.autoincrement && next_rowid > self.sequence {
+            self.sequence = next_rowid;
+        }
+        Ok(next_rowid)
     }
 
     /// Print the table schema to standard output in a pretty formatted way
     ///
     /// # Example
     ///
-    /// ```
+    /// ```text
     /// let table = Table::new(payload);
     /// table.print_table_schema();
     ///
@@ -390,11 +1175,57 @@ impl Table {
         Ok(lines)
     }
 
-    /// Print the table data to standard output in a pretty formatted way
+    /// Print the statistics collected by the last ANALYZE of the table to standard output,
+    /// one line per column. Nothing is printed if the table was never analyzed.
     ///
     /// # Example
     ///
+    /// ```text
+    /// db.analyze(Some("users".to_string()))?;
+    /// db.get_table("users".to_string())?.print_table_stats();
+    ///
+    /// Prints to standard output:
+    ///    +-------+-------------+------+-----------------+-------------+-------------------+
+    ///    | Table | Column Name | Rows | Distinct Values | NULL Values | Histogram Buckets |
+    ///    +-------+-------------+------+-----------------+-------------+-------------------+
+    ///    | users | id          | 3    | 3               | 0           | 3                 |
+    ///    +-------+-------------+------+-----------------+-------------+-------------------+
     /// ```
+    ///
+    pub fn print_table_stats(&self) {
+        let stats = match &self.stats {
+            Some(stats) => stats,
+            None => return,
+        };
+        let mut table = PrintTable::new();
+        table.add_row(row![
+            "Table",
+            "Column Name",
+            "Rows",
+            "Distinct Values",
+            "NULL Values",
+            "Histogram Buckets"
+        ]);
+        for col in &self.columns {
+            if let Some(col_stats) = stats.columns.get(&col.column_name) {
+                table.add_row(row![
+                    self.tb_name,
+                    col.column_name,
+                    stats.row_count,
+                    col_stats.distinct_count,
+                    col_stats.null_count,
+                    col_stats.histogram.len()
+                ]);
+            }
+        }
+        table.printstd();
+    }
+
+    /// Print the table data to standard output in a pretty formatted way
+    ///
+    /// # Example
+    ///
+    /// ```text
     /// let db_table = db.get_table_mut(table_name.to_string()).unwrap();
     /// db_table.print_table_data();
     ///
@@ -428,25 +1259,26 @@ impl Table {
 
         let rows_clone = Rc::clone(&self.rows);
         let row_data = rows_clone.as_ref().borrow();
-        let first_col_data = row_data
-            .get(&self.columns.first().unwrap().column_name)
-            .unwrap();
-        let num_rows = first_col_data.count();
-        let mut print_table_rows: Vec<PrintRow> = vec![PrintRow::new(vec![]); num_rows];
-
-        for col_name in &column_names {
-            let col_val = row_data
-                .get(col_name)
-                .expect("Can't find any rows with the given column");
-            let columns: Vec<String> = col_val.get_serialized_col_data();
-
-            for i in 0..num_rows {
-                if let Some(cell) = &columns.get(i) {
-                    print_table_rows[i].add_cell(PrintCell::new(cell));
-                } else {
-                    print_table_rows[i].add_cell(PrintCell::new(""));
-                }
+        let mut print_table_rows: Vec<PrintRow> = vec![];
+
+        // Cells are looked up by ROWID, so a column without a value for a given row
+        // (e.g. a column added after the row was inserted) is printed as Null
+        for rowid in self.rowids() {
+            let mut print_row = PrintRow::new(vec![]);
+            for col_name in &column_names {
+                let cell = match self.virtual_column(col_name) {
+                    Some(_) => match self.get_value(col_name, rowid) {
+                        Value::Null => String::from("Null"),
+                        value => value.to_string(),
+                    },
+                    None => row_data
+                        .get(col_name)
+                        .and_then(|row| row.get_serialized_value(rowid))
+                        .unwrap_or_else(|| String::from("Null")),
+                };
+                print_row.add_cell(PrintCell::new(&cell));
             }
+            print_table_rows.push(print_row);
         }
 
         print_table.add_row(header_row);
@@ -458,9 +1290,28 @@ impl Table {
     }
 }
 
+/// A row about to be inserted into a table, built by `Table::prepare_row`
+#[derive(Debug, Clone)]
+pub struct NewRow {
+    /// ROWID the row is stored under
+    pub rowid: i64,
+    /// Value of every column of the table, in declaration order
+    pub values: Vec<(String, Value)>,
+}
+
+/// The PRIMARY KEY of a table, over one or more of its columns.
+/// It is backed by one of the UNIQUE indexes of the table.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct PrimaryKey {
+    /// Columns making up the key, in key order
+    pub columns: Vec<String>,
+    /// Value representing if the key is an INTEGER PRIMARY KEY, an alias of the ROWID
+    pub rowid_alias: bool,
+}
+
 /// The schema for each SQL column in every table is represented in memory
 /// by following structure
-#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
 pub struct Column {
     /// Name of the column
     pub column_name: String,
@@ -474,9 +1325,10 @@ pub struct Column {
     pub is_unique: bool,
     /// Value representing if column is Indexed or not
     pub is_indexed: bool,
-    /// BtreeMap mapping the index to a payload value on the corresponding Row
-    /// Mapped using a ROWID
-    pub index: Index,
+    /// Expression giving the value of the column when an INSERT omits it, NULL if there is none
+    pub default: Option<Expr>,
+    /// GENERATED ALWAYS AS clause computing the value of the column, `None` for ordinary columns
+    pub generated: Option<Generated>,
 }
 
 impl Column {
@@ -486,16 +1338,10 @@ impl Column {
         is_pk: bool,
         not_null: bool,
         is_unique: bool,
+        default: Option<Expr>,
+        generated: Option<Generated>,
     ) -> Self {
         let dt = DataType::new(datatype);
-        let index = match dt {
-            DataType::Integer => Index::Integer(BTreeMap::new()),
-            DataType::Bool => Index::None,
-            DataType::Text => Index::Text(BTreeMap::new()),
-            DataType::Real => Index::None,
-            DataType::Invalid => Index::None,
-            DataType::None => Index::None,
-        };
 
         Column {
             column_name: name,
@@ -503,23 +1349,68 @@ impl Column {
             is_pk,
             not_null,
             is_unique,
-            is_indexed: if is_pk { true } else { false },
-            index,
+            is_indexed: is_unique,
+            default,
+            generated,
         }
     }
+}
+
+/// The expression of a generated column, which can not be inserted or updated. Like in SQLite,
+/// the value of a STORED column is written along with the rest of the row, and the value of
+/// a VIRTUAL column is computed every time it is read.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct Generated {
+    /// Expression computing the value of the column from the other columns of the row
+    pub expr: Expr,
+    /// Value representing if the value of the column is stored with the row
+    pub stored: bool,
+}
 
-    pub fn get_mut_index(&mut self) -> &mut Index {
-        return &mut self.index;
+impl Generated {
+    pub fn new(generated: &ParsedGenerated) -> Self {
+        Generated {
+            expr: generated.expr.clone(),
+            stored: generated.stored,
+        }
     }
 }
 
-/// The schema for each SQL column index in every table is represented in memory
-/// by following structure
-#[derive(Serialize, Deserialize, PartialEq, Debug)]
-pub enum Index {
-    Integer(BTreeMap<i32, i64>),
-    Text(BTreeMap<String, i64>),
-    None,
+/// A CHECK constraint of a table. Like in SQLite, a row satisfies the constraint
+/// unless its expression evaluates to false, so NULL results pass.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct CheckConstraint {
+    /// Name given to the constraint with `CONSTRAINT <name>`, if any
+    pub name: Option<String>,
+    /// Expression evaluated against every inserted or updated row
+    pub expr: Expr,
+    /// Column the constraint was declared on, `None` for table constraints
+    pub column: Option<String>,
+}
+
+impl CheckConstraint {
+    pub fn new(check: &ParsedCheck, column: Option<String>) -> Self {
+        CheckConstraint {
+            name: check.name.clone(),
+            expr: check.expr.clone(),
+            column,
+        }
+    }
+
+    /// Evaluates the constraint against a row, failing if the row breaks it.
+    /// The error names the constraint the way SQLite does.
+    fn validate(&self, row: &dyn RowSource) -> Result<()> {
+        match is_truthy(&evaluate(&self.expr, row)?) {
+            Some(false) => Err(SQLRiteError::General(format!(
+                "CHECK constraint failed: {}",
+                match &self.name {
+                    Some(name) => name.to_string(),
+                    None => self.expr.to_string(),
+                }
+            ))),
+            _ => Ok(()),
+        }
+    }
 }
 
 /// The schema for each SQL row in every table is represented in memory
@@ -527,33 +1418,175 @@ pub enum Index {
 ///
 /// This is an enum representing each of the available types organized in a BTreeMap
 /// data structure, using the ROWID and key and each corresponding type as value
-#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
 pub enum Row {
-    Integer(BTreeMap<i64, i32>),
+    Integer(BTreeMap<i64, i64>),
     Text(BTreeMap<i64, String>),
     Real(BTreeMap<i64, f32>),
     Bool(BTreeMap<i64, bool>),
+    Blob(BTreeMap<i64, Vec<u8>>),
     None,
 }
 
 impl Row {
-    fn get_serialized_col_data(&self) -> Vec<String> {
+    fn get_serialized_value(&self, rowid: i64) -> Option<String> {
+        match self {
+            Row::Integer(cd) => cd.get(&rowid).map(|v| v.to_string()),
+            Row::Real(cd) => cd.get(&rowid).map(|v| v.to_string()),
+            Row::Text(cd) => cd.get(&rowid).map(|v| v.to_string()),
+            Row::Bool(cd) => cd.get(&rowid).map(|v| v.to_string()),
+            Row::Blob(cd) => cd.get(&rowid).map(|v| encode_blob(v)),
+            Row::None => None,
+        }
+    }
+
+    /// Returns the typed value stored under the given ROWID, Null if there is none
+    fn get_value(&self, rowid: i64) -> Value {
+        match self {
+            Row::Integer(cd) => cd.get(&rowid).map_or(Value::Null, |v| Value::Integer(*v)),
+            Row::Real(cd) => cd
+                .get(&rowid)
+                .map_or(Value::Null, |v| Value::Real(*v as f64)),
+            Row::Text(cd) => cd
+                .get(&rowid)
+                .map_or(Value::Null, |v| Value::Text(v.to_string())),
+            Row::Bool(cd) => cd.get(&rowid).map_or(Value::Null, |v| Value::Bool(*v)),
+            Row::Blob(cd) => cd
+                .get(&rowid)
+                .map_or(Value::Null, |v| Value::Blob(v.to_vec())),
+            Row::None => Value::Null,
+        }
+    }
+
+    /// Stores a value given in its `String` representation under the given ROWID,
+    /// converting it to the type of the column
+    fn insert_serialized_value(&mut self, rowid: i64, val: &str) -> Result<()> {
+        let invalid = || SQLRiteError::General(format!("Invalid value for column type: {}", val));
         match self {
-            Row::Integer(cd) => cd.iter().map(|(_i, v)| v.to_string()).collect(),
-            Row::Real(cd) => cd.iter().map(|(_i, v)| v.to_string()).collect(),
-            Row::Text(cd) => cd.iter().map(|(_i, v)| v.to_string()).collect(),
-            Row::Bool(cd) => cd.iter().map(|(_i, v)| v.to_string()).collect(),
-            Row::None => panic!("Found None in columns"),
+            Row::Integer(cd) => {
+                cd.insert(rowid, val.parse::<i64>().map_err(|_| invalid())?);
+            }
+            Row::Real(cd) => {
+                cd.insert(rowid, val.parse::<f32>().map_err(|_| invalid())?);
+            }
+            Row::Text(cd) => {
+                cd.insert(rowid, val.to_string());
+            }
+            Row::Bool(cd) => {
+                cd.insert(rowid, val.parse::<bool>().map_err(|_| invalid())?);
+            }
+            Row::Blob(cd) => {
+                cd.insert(
+                    rowid,
+                    decode_blob(val).unwrap_or_else(|| val.as_bytes().to_vec()),
+                );
+            }
+            Row::None => return Err(invalid()),
+        }
+        Ok(())
+    }
+
+    /// Stores a typed value under the given ROWID, converting it to the type of the column.
+    /// Storing NULL removes the value, since a missing ROWID is read back as NULL.
+    fn set_value(&mut self, rowid: i64, val: &Value) -> Result<()> {
+        if val.is_null() {
+            self.remove_value(rowid);
+            return Ok(());
+        }
+        let invalid = || SQLRiteError::General(format!("Invalid value for column type: {}", val));
+        match (self, val) {
+            (Row::Integer(cd), Value::Integer(i)) => {
+                cd.insert(rowid, *i);
+            }
+            (Row::Integer(cd), Value::Real(f)) if f.fract() == 0.0 => {
+                // Casting saturates, so whole numbers out of range are rejected beforehand
+                if !(i64::MIN as f64..i64::MAX as f64).contains(f) {
+                    return Err(invalid());
+                }
+                cd.insert(rowid, *f as i64);
+            }
+            (Row::Integer(cd), Value::Bool(b)) => {
+                cd.insert(rowid, *b as i64);
+            }
+            (Row::Real(cd), Value::Integer(i)) => {
+                cd.insert(rowid, *i as f32);
+            }
+            (Row::Real(cd), Value::Real(f)) => {
+                cd.insert(rowid, *f as f32);
+            }
+            (Row::Bool(cd), Value::Bool(b)) => {
+                cd.insert(rowid, *b);
+            }
+            (Row::Bool(cd), Value::Integer(i)) => {
+                cd.insert(rowid, *i != 0);
+            }
+            (Row::Blob(cd), Value::Blob(b)) => {
+                cd.insert(rowid, b.to_vec());
+            }
+            (Row::None, _) => return Err(invalid()),
+            // Any other combination goes through the textual representation,
+            // the same way values coming from an INSERT statement do
+            (row, val) => row.insert_serialized_value(rowid, &val.to_string())?,
         }
+        Ok(())
     }
 
-    fn count(&self) -> usize {
+    /// Removes the value stored under the given ROWID, if any
+    fn remove_value(&mut self, rowid: i64) {
         match self {
-            Row::Integer(cd) => cd.len(),
-            Row::Real(cd) => cd.len(),
-            Row::Text(cd) => cd.len(),
-            Row::Bool(cd) => cd.len(),
-            Row::None => panic!("Found None in columns"),
+            Row::Integer(cd) => {
+                cd.remove(&rowid);
+            }
+            Row::Real(cd) => {
+                cd.remove(&rowid);
+            }
+            Row::Text(cd) => {
+                cd.remove(&rowid);
+            }
+            Row::Bool(cd) => {
+                cd.remove(&rowid);
+            }
+            Row::Blob(cd) => {
+                cd.remove(&rowid);
+            }
+            Row::None => (),
+        }
+    }
+
+    /// Creates an empty column storage for the given data type
+    fn new(datatype: &DataType) -> Row {
+        match datatype {
+            DataType::Integer => Row::Integer(BTreeMap::new()),
+            DataType::Real => Row::Real(BTreeMap::new()),
+            DataType::Text => Row::Text(BTreeMap::new()),
+            DataType::Bool => Row::Bool(BTreeMap::new()),
+            DataType::Blob => Row::Blob(BTreeMap::new()),
+            DataType::Invalid => Row::None,
+            DataType::None => Row::None,
         }
     }
 }
+
+/// Renders a BLOB as a SQLite hex literal, e.g. `X'DEADBEEF'`.
+/// This is the format blob values travel in between the parser and the table,
+/// and the format they are printed in.
+pub fn encode_blob(bytes: &[u8]) -> String {
+    let hex: String = bytes.iter().map(|b| format!("{:02X}", b)).collect();
+    format!("X'{}'", hex)
+}
+
+/// Decodes a hex literal of the form `X'..'` back into its bytes.
+/// Returns `None` if the value is not a well formed hex literal.
+pub fn decode_blob(value: &str) -> Option<Vec<u8>> {
+    let hex = value
+        .strip_prefix("X'")
+        .or_else(|| value.strip_prefix("x'"))?
+        .strip_suffix('\'')?;
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
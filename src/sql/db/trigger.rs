@@ -0,0 +1,52 @@
+use crate::sql::parser::trigger::{CreateTriggerQuery, TriggerEvent, TriggerTiming};
+use serde::{Deserialize, Serialize};
+use sqlparser::ast::Expr;
+
+/// A trigger, statements run FOR EACH ROW inserted, updated or deleted in a table, or
+/// in place of the changes made to a view. The statements read the row through `NEW`
+/// and `OLD`, and `RAISE()` can stop the statement that fired the trigger.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct Trigger {
+    /// Name of the trigger
+    pub name: String,
+    /// Table or view the trigger is attached to
+    pub table_name: String,
+    /// When the trigger runs, relative to the change of the row
+    pub timing: TriggerTiming,
+    /// The kind of statement the trigger fires on
+    pub event: TriggerEvent,
+    /// WHEN clause, the trigger only running for the rows it is true for
+    pub when: Option<Expr>,
+    /// Statements of the trigger body
+    pub body: Vec<String>,
+}
+
+impl Trigger {
+    pub fn new(create_query: CreateTriggerQuery) -> Self {
+        Trigger {
+            name: create_query.name,
+            table_name: create_query.table_name,
+            timing: create_query.timing,
+            event: create_query.event,
+            when: create_query.when,
+            body: create_query.body,
+        }
+    }
+
+    /// Returns true if the trigger fires on a change of a table. An UPDATE OF trigger
+    /// only fires when one of its columns is assigned.
+    ///
+    pub fn fires_on(&self, table_name: &str, timing: TriggerTiming, event: &TriggerEvent) -> bool {
+        if self.table_name != table_name || self.timing != timing {
+            return false;
+        }
+        match (&self.event, event) {
+            (TriggerEvent::Insert, TriggerEvent::Insert) => true,
+            (TriggerEvent::Delete, TriggerEvent::Delete) => true,
+            (TriggerEvent::Update(columns), TriggerEvent::Update(assigned)) => {
+                columns.is_empty() || columns.iter().any(|col| assigned.contains(col))
+            }
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,243 @@
+use crate::error::{Result, SQLRiteError};
+use crate::sql::db::database::Database;
+use crate::sql::db::incremental::{Group, Maintenance};
+use crate::sql::db::table::{DataType, Table};
+use crate::sql::db::value::Value;
+use crate::sql::executor::select::{execute_select, ResultSet};
+use crate::sql::parser::create::{CreateQuery, ParsedColumn};
+use crate::sql::parser::select::SelectQuery;
+use crate::sql::parser::view::CreateViewQuery;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// A view, a SELECT query stored under a name. Queries reading from a view read
+/// the rows its query returns at that time, as if they were those of a table.
+/// A materialized view instead stores its rows in a table, recomputed by REFRESH
+/// MATERIALIZED VIEW or, for simple aggregates over a single table, kept up to date
+/// as rows of the table change.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct View {
+    /// Name of the view
+    pub name: String,
+    /// Names given to the columns of the view, empty when the columns are named
+    /// after the result of the query
+    pub columns: Vec<String>,
+    /// Query giving the rows of the view
+    pub query: SelectQuery,
+    /// Value representing if the view was declared CREATE MATERIALIZED VIEW
+    pub materialized: bool,
+    /// Rows of a materialized view, stored in a table queries read in place of the view.
+    /// `None` for other views, whose rows are computed every time they are read.
+    pub rows: Option<Table>,
+    /// Groups of a materialized view maintained incrementally, by GROUP BY key.
+    /// `None` when the view is only recomputed by REFRESH MATERIALIZED VIEW.
+    pub groups: Option<BTreeMap<Vec<Value>, Group>>,
+}
+
+impl View {
+    pub fn new(create_query: CreateViewQuery) -> Self {
+        View {
+            name: create_query.name,
+            columns: create_query.columns,
+            query: create_query.query,
+            materialized: create_query.materialized,
+            rows: None,
+            groups: None,
+        }
+    }
+
+    /// Runs the query of the view, returning its rows under the column names of the view
+    ///
+    pub fn execute(&self, db: &Database) -> Result<ResultSet> {
+        let mut result = execute_select(&self.query, db)?;
+        result.columns = self.column_names(result.columns)?;
+        Ok(result)
+    }
+
+    /// Returns the names of the columns of the view given those of the result of its query
+    fn column_names(&self, columns: Vec<String>) -> Result<Vec<String>> {
+        if self.columns.is_empty() {
+            return Ok(columns);
+        }
+        if self.columns.len() != columns.len() {
+            return Err(SQLRiteError::General(format!(
+                "expected {} columns for '{}' but got {}",
+                self.columns.len(),
+                self.name,
+                columns.len()
+            )));
+        }
+        Ok(self.columns.to_vec())
+    }
+
+    /// Builds a table holding the rows of the view, which queries read in place of the view.
+    /// Like the tables created with `CREATE TABLE ... AS SELECT`, its column types are
+    /// inferred from the rows.
+    ///
+    pub fn materialize(&self, db: &Database) -> Result<Table> {
+        let result = self.execute(db)?;
+        self.build_table(&result).map(|(table, _)| table)
+    }
+
+    /// Builds a table holding the rows of a result set, returning it with the ROWID of every row
+    fn build_table(&self, result: &ResultSet) -> Result<(Table, Vec<i64>)> {
+        let columns = ParsedColumn::from_result_set(result);
+        let column_names: Vec<String> = columns.iter().map(|col| col.name.to_string()).collect();
+        let mut table = Table::new(CreateQuery {
+            table_name: self.name.to_string(),
+            columns,
+            checks: vec![],
+            keys: vec![],
+            foreign_keys: vec![],
+            select: None,
+        });
+        let mut rowids: Vec<i64> = vec![];
+        for row in &result.rows {
+            let new_row = table.prepare_row(&column_names, row)?;
+            rowids.push(table.store_row(&new_row)?);
+        }
+        Ok((table, rowids))
+    }
+
+    /// Recomputes the rows of a materialized view. The groups of a view computing simple
+    /// aggregates over a single table are computed along, for the view to be maintained
+    /// incrementally from then on.
+    ///
+    pub fn refresh(&mut self, db: &Database) -> Result<()> {
+        let maintenance = match Maintenance::new(&self.query, db)? {
+            Some(maintenance) => maintenance,
+            None => {
+                self.rows = Some(self.materialize(db)?);
+                self.groups = None;
+                return Ok(());
+            }
+        };
+        let mut groups = maintenance.empty_groups();
+        for rowid in db.get_table(self.query.from[0].name.to_string())?.rowids() {
+            maintenance.apply(&mut groups, rowid, 1)?;
+        }
+        self.rows = Some(self.store_groups(&maintenance, &mut groups)?);
+        self.groups = Some(groups);
+        Ok(())
+    }
+
+    /// Builds the table holding the rows of an incrementally maintained view from its groups
+    fn store_groups(
+        &self,
+        maintenance: &Maintenance,
+        groups: &mut BTreeMap<Vec<Value>, Group>,
+    ) -> Result<Table> {
+        let mut rows: Vec<Vec<Value>> = vec![];
+        for (key, group) in groups.iter() {
+            rows.push(maintenance.output(key, group)?);
+        }
+        let result = ResultSet {
+            columns: self.column_names(maintenance.columns.to_vec())?,
+            rows,
+        };
+        let (table, rowids) = self.build_table(&result)?;
+        for (group, rowid) in groups.values_mut().zip(rowids) {
+            group.rowid = Some(rowid);
+        }
+        Ok(table)
+    }
+
+    /// Updates an incrementally maintained view for a row of the table it reads from being
+    /// added (`delta` of 1) or removed (`delta` of -1). Only the group of the row changes;
+    /// the table holding the rows is rebuilt when a value no longer fits its column types.
+    ///
+    pub fn maintain(&mut self, db: &Database, rowid: i64, delta: i64) -> Result<()> {
+        let maintenance = match Maintenance::new(&self.query, db)? {
+            Some(maintenance) => maintenance,
+            None => return Ok(()),
+        };
+        let (mut groups, mut table) = match (self.groups.take(), self.rows.take()) {
+            (Some(groups), Some(table)) => (groups, table),
+            (groups, table) => {
+                self.groups = groups;
+                self.rows = table;
+                return Ok(());
+            }
+        };
+        let result = self.update_group(&maintenance, &mut groups, &mut table, rowid, delta);
+        self.groups = Some(groups);
+        self.rows = Some(table);
+        result
+    }
+
+    /// Applies the change of a row to its group and writes the group to the table
+    /// holding the rows of the view
+    fn update_group(
+        &self,
+        maintenance: &Maintenance,
+        groups: &mut BTreeMap<Vec<Value>, Group>,
+        table: &mut Table,
+        rowid: i64,
+        delta: i64,
+    ) -> Result<()> {
+        let key = match maintenance.apply(groups, rowid, delta)? {
+            Some(key) => key,
+            None => return Ok(()),
+        };
+        let group = &groups[&key];
+        let written = if group.rows == 0 && !maintenance.group_by.is_empty() {
+            let result = match group.rowid {
+                Some(rowid) => table.delete_row(rowid),
+                None => Ok(()),
+            };
+            groups.remove(&key);
+            result
+        } else {
+            let values = maintenance.output(&key, group)?;
+            let columns: Vec<String> = table
+                .columns
+                .iter()
+                .map(|col| col.column_name.to_string())
+                .collect();
+            // Column types were inferred from the rows of the view, e.g. a column holding
+            // only NULLs so far, so the table is rebuilt when a value does not fit them
+            let fits = table
+                .columns
+                .iter()
+                .zip(&values)
+                .all(|(column, value)| fits_type(&column.datatype, value));
+            match group.rowid {
+                _ if !fits => Err(SQLRiteError::Internal(
+                    "value does not fit the column types of the view".to_string(),
+                )),
+                Some(rowid) => {
+                    let assignments: Vec<(String, Value)> =
+                        columns.into_iter().zip(values).collect();
+                    table.update_row(rowid, &assignments).map(|_| ())
+                }
+                None => table
+                    .prepare_row(&columns, &values)
+                    .and_then(|row| table.store_row(&row))
+                    .map(|rowid| groups.get_mut(&key).unwrap().rowid = Some(rowid)),
+            }
+        };
+        if written.is_err() {
+            *table = self.store_groups(maintenance, groups)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the names of the tables and views the query of the view reads from
+    ///
+    pub fn dependencies(&self) -> impl Iterator<Item = &String> {
+        self.query.from.iter().map(|table| &table.name)
+    }
+}
+
+/// Returns true if a value is stored as is by a column of the given type
+fn fits_type(datatype: &DataType, value: &Value) -> bool {
+    matches!(
+        (datatype, value),
+        (_, Value::Null)
+            | (DataType::Integer, Value::Integer(_) | Value::Bool(_))
+            | (DataType::Real, Value::Real(_) | Value::Integer(_))
+            | (DataType::Text, Value::Text(_))
+            | (DataType::Bool, Value::Bool(_))
+            | (DataType::Blob, Value::Blob(_))
+    )
+}
@@ -0,0 +1,47 @@
+use crate::error::Result;
+use crate::sql::db::database::Database;
+use crate::sql::executor::planner::QueryPlan;
+use crate::sql::executor::select::{execute_returning, find_rowids, ResultSet};
+use crate::sql::executor::trigger;
+use crate::sql::parser::delete::DeleteQuery;
+use crate::sql::parser::trigger::TriggerEvent;
+
+/// Runs a DELETE query, the triggers of the table firing for every deleted row. A DELETE
+/// from a view runs its INSTEAD OF DELETE triggers instead.
+/// Returns the rowids of the deleted rows, none for a view, and the RETURNING clause computed
+/// over them, as they were before the DELETE. The rows to delete are found following `plan`
+/// if there is one, see `find_rowids`.
+pub fn execute_delete(
+    query: DeleteQuery,
+    db: &mut Database,
+    plan: Option<QueryPlan>,
+) -> Result<(Vec<i64>, Option<ResultSet>)> {
+    let table_name = query.table_name;
+    if trigger::has_instead_of_triggers(db, &table_name, &TriggerEvent::Delete) {
+        if let Some(returning) = &query.returning {
+            execute_returning(returning, db, &table_name, &[])?;
+        }
+        let selection = query.selection;
+        db.run_statement(false, |db| {
+            trigger::delete_from_view(db, &table_name, selection.as_ref())
+        })?;
+        return Ok((vec![], None));
+    }
+
+    // Rows are selected before deleting any, so the WHERE clause sees the whole table
+    let rowids = find_rowids(db, &table_name, query.selection.as_ref(), plan)?;
+    let result = match &query.returning {
+        Some(returning) => Some(execute_returning(returning, db, &table_name, &rowids)?),
+        None => None,
+    };
+    let deleted = db.run_statement(false, |db| {
+        let mut deleted: Vec<i64> = vec![];
+        for rowid in rowids {
+            if trigger::delete_row(db, &table_name, rowid)? {
+                deleted.push(rowid);
+            }
+        }
+        Ok(deleted)
+    })?;
+    Ok((deleted, result))
+}
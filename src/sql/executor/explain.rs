@@ -0,0 +1,356 @@
+use sqlparser::ast::{Expr, Statement};
+
+use crate::error::{Result, SQLRiteError};
+use crate::sql::db::database::Database;
+use crate::sql::db::table::Table;
+use crate::sql::executor::planner::{AccessPath, Bound, QueryPlan, Scope, TablePlan};
+use crate::sql::executor::select::{materialize_views, plan_table_rows, prepare_select};
+use crate::sql::parser::delete::DeleteQuery;
+use crate::sql::parser::select::{JoinKind, SelectQuery};
+use crate::sql::parser::update::UpdateQuery;
+
+/// A node of the tree printed by EXPLAIN and EXPLAIN QUERY PLAN
+struct Node {
+    label: String,
+    children: Vec<Node>,
+}
+
+impl Node {
+    fn new(label: String, children: Vec<Node>) -> Node {
+        Node { label, children }
+    }
+
+    /// Renders the children of the node one per line, drawing the tree the way
+    /// the SQLite shell prints query plans
+    fn render_children(&self, indent: &str, lines: &mut Vec<String>) {
+        for (i, child) in self.children.iter().enumerate() {
+            let is_last = i == self.children.len() - 1;
+            lines.push(format!(
+                "{}{}{}",
+                indent,
+                if is_last { "`--" } else { "|--" },
+                child.label
+            ));
+            child.render_children(
+                &format!("{}{}", indent, if is_last { "   " } else { "|  " }),
+                lines,
+            );
+        }
+    }
+
+    fn render(&self) -> Vec<String> {
+        let mut lines = vec![self.label.to_string()];
+        self.render_children("", &mut lines);
+        lines
+    }
+}
+
+/// Returns the lines printed by `EXPLAIN QUERY PLAN <statement>`: the access path
+/// chosen for every table, how it is joined and the temporary B-trees used for sorting
+pub fn explain_query_plan(statement: &Statement, db: &Database) -> Result<Vec<String>> {
+    let mut steps: Vec<Node> = vec![];
+    // Tables the views read by the query are materialized into, which the plan refers to
+    let views: Vec<Table>;
+    let (scope, plan) = match statement {
+        Statement::Query(_) => {
+            let query = SelectQuery::new(statement)?;
+            views = materialize_views(&query.from, db)?;
+            let prepared = prepare_select(&query, db, &views, None)?;
+            if prepared.scope.tables.is_empty() {
+                steps.push(Node::new("SCAN CONSTANT ROW".to_string(), vec![]));
+            }
+            if !prepared.group_by.is_empty() {
+                steps.push(Node::new(
+                    "USE TEMP B-TREE FOR GROUP BY".to_string(),
+                    vec![],
+                ));
+            }
+            if query.distinct {
+                steps.push(Node::new(
+                    "USE TEMP B-TREE FOR DISTINCT".to_string(),
+                    vec![],
+                ));
+            }
+            (prepared.scope, prepared.plan)
+        }
+        _ => table_rows_plan(statement, db)?,
+    };
+
+    for (level, table_plan) in plan.tables.iter().enumerate() {
+        let mut detail = describe_access(&scope, table_plan, false);
+        if level > 0 {
+            detail = format!("{} ({})", detail, describe_join(table_plan));
+        }
+        steps.insert(level, Node::new(detail, vec![]));
+    }
+    if plan.sort {
+        steps.push(Node::new(
+            "USE TEMP B-TREE FOR ORDER BY".to_string(),
+            vec![],
+        ));
+    }
+    Ok(Node::new("QUERY PLAN".to_string(), steps).render())
+}
+
+/// Returns the lines printed by `EXPLAIN <statement>`: the tree of physical operators
+/// the executor runs, each reading the rows produced by its children
+pub fn explain(statement: &Statement, db: &Database) -> Result<Vec<String>> {
+    let root = match statement {
+        Statement::Query(_) => {
+            let query = SelectQuery::new(statement)?;
+            let views = materialize_views(&query.from, db)?;
+            let prepared = prepare_select(&query, db, &views, None)?;
+            let mut node = join_tree(&prepared.scope, &prepared.plan);
+            if !prepared.plan.residual.is_empty() {
+                node = Node::new(
+                    format!("Filter ({})", join_conditions(&prepared.plan.residual)),
+                    vec![node],
+                );
+            }
+            if prepared.aggregate {
+                let terms: Vec<String> = prepared
+                    .group_by
+                    .iter()
+                    .map(|term| term.to_string())
+                    .collect();
+                node = Node::new(format!("Aggregate ({})", terms.join(", ")), vec![node]);
+                if let Some(having) = &query.having {
+                    node = Node::new(format!("Filter ({})", having), vec![node]);
+                }
+            }
+            let columns: Vec<String> = prepared
+                .columns
+                .iter()
+                .map(|col| match &col.expr {
+                    Expr::Identifier(ident) if ident.value == col.name => col.expr.to_string(),
+                    Expr::CompoundIdentifier(idents)
+                        if idents[idents.len() - 1].value == col.name =>
+                    {
+                        col.expr.to_string()
+                    }
+                    expr if expr.to_string() == col.name => col.name.to_string(),
+                    expr => format!("{} AS {}", expr, col.name),
+                })
+                .collect();
+            node = Node::new(format!("Project ({})", columns.join(", ")), vec![node]);
+            if query.distinct {
+                node = Node::new("Distinct".to_string(), vec![node]);
+            }
+            if prepared.plan.sort {
+                let terms: Vec<String> = prepared
+                    .order_by
+                    .iter()
+                    .map(|term| term.to_string())
+                    .collect();
+                node = Node::new(format!("Sort ({})", terms.join(", ")), vec![node]);
+            }
+            if query.limit.is_some() || query.offset.is_some() {
+                let mut detail: Vec<String> = vec![];
+                if let Some(limit) = &query.limit {
+                    detail.push(format!("limit={}", limit));
+                }
+                if let Some(offset) = &query.offset {
+                    detail.push(format!("offset={}", offset));
+                }
+                node = Node::new(format!("Limit ({})", detail.join(", ")), vec![node]);
+            }
+            node
+        }
+        Statement::Update { .. } => {
+            let query = UpdateQuery::new(statement, None)?;
+            let (scope, plan) =
+                plan_table_rows(db, &query.table_name, query.selection.as_ref(), None)?;
+            let assignments: Vec<String> = query
+                .assignments
+                .iter()
+                .map(|(column, expr)| format!("{} = {}", column, expr))
+                .collect();
+            Node::new(
+                format!(
+                    "Update {} (SET {})",
+                    query.table_name,
+                    assignments.join(", ")
+                ),
+                vec![join_tree(&scope, &plan)],
+            )
+        }
+        Statement::Delete { .. } => {
+            let query = DeleteQuery::new(statement, None)?;
+            let (scope, plan) =
+                plan_table_rows(db, &query.table_name, query.selection.as_ref(), None)?;
+            Node::new(
+                format!("Delete {}", query.table_name),
+                vec![join_tree(&scope, &plan)],
+            )
+        }
+        _ => return Err(not_explainable()),
+    };
+    Ok(root.render())
+}
+
+/// Plans the rows an UPDATE or DELETE statement changes
+fn table_rows_plan<'a>(statement: &Statement, db: &'a Database) -> Result<(Scope<'a>, QueryPlan)> {
+    match statement {
+        Statement::Update { .. } => {
+            let query = UpdateQuery::new(statement, None)?;
+            plan_table_rows(db, &query.table_name, query.selection.as_ref(), None)
+        }
+        Statement::Delete { .. } => {
+            let query = DeleteQuery::new(statement, None)?;
+            plan_table_rows(db, &query.table_name, query.selection.as_ref(), None)
+        }
+        _ => Err(not_explainable()),
+    }
+}
+
+fn not_explainable() -> SQLRiteError {
+    SQLRiteError::NotImplemented(
+        "EXPLAIN is only supported for SELECT, UPDATE and DELETE statements.".to_string(),
+    )
+}
+
+/// Builds the operator tree reading and joining the tables of a plan, outermost table
+/// at the bottom left. Every table is read by a scan operator, below a filter with the
+/// conditions checked once the table is joined.
+fn join_tree(scope: &Scope, plan: &QueryPlan) -> Node {
+    let mut node: Option<Node> = None;
+    for table_plan in plan.tables.iter() {
+        let mut scan = Node::new(
+            format!(
+                "{} (~{} rows)",
+                describe_access(scope, table_plan, true),
+                table_plan.estimated_rows.ceil()
+            ),
+            vec![],
+        );
+        if !table_plan.filter.is_empty() {
+            scan = Node::new(
+                format!("Filter ({})", join_conditions(&table_plan.filter)),
+                vec![scan],
+            );
+        }
+        node = Some(match node {
+            None => scan,
+            Some(outer) => Node::new(
+                format!("NestedLoopJoin ({})", describe_join(table_plan)),
+                vec![outer, scan],
+            ),
+        });
+    }
+    node.unwrap_or_else(|| Node::new("ConstantRow".to_string(), vec![]))
+}
+
+fn join_conditions(conditions: &[Expr]) -> String {
+    conditions
+        .iter()
+        .map(|condition| condition.to_string())
+        .collect::<Vec<String>>()
+        .join(" AND ")
+}
+
+/// Describes the kind of join and the algorithm used to find the matching rows
+fn describe_join(table_plan: &TablePlan) -> String {
+    let kind = match table_plan.join {
+        JoinKind::From | JoinKind::Inner => "INNER JOIN",
+        JoinKind::Left => "LEFT JOIN",
+        JoinKind::Cross => "CROSS JOIN",
+    };
+    let algorithm = match table_plan.access {
+        AccessPath::FullScan => "NESTED LOOP",
+        _ => "INDEX NESTED LOOP",
+    };
+    format!("{}, {}", kind, algorithm)
+}
+
+/// Describes how a table is read. With `physical` set the description names the
+/// operator and shows the expressions used for lookups, otherwise it follows the
+/// EXPLAIN QUERY PLAN format of SQLite, with `?` standing for looked up values.
+fn describe_access(scope: &Scope, table_plan: &TablePlan, physical: bool) -> String {
+    let name = match table_plan.name == table_plan.table {
+        true => table_plan.table.to_string(),
+        false => format!("{} AS {}", table_plan.table, table_plan.name),
+    };
+    let value = |expr: &Expr| match physical {
+        true => expr.to_string(),
+        false => "?".to_string(),
+    };
+    let bound = |key: &str, bound: &Option<Bound>, lower: bool| {
+        bound.as_ref().map(|bound| {
+            let op = match (lower, bound.inclusive) {
+                (true, true) => ">=",
+                (true, false) => ">",
+                (false, true) => "<=",
+                (false, false) => "<",
+            };
+            format!("{}{}{}", key, op, value(&bound.expr))
+        })
+    };
+
+    let description = match &table_plan.access {
+        AccessPath::FullScan => match physical {
+            true => format!("TableScan {}", name),
+            false => format!("SCAN {}", name),
+        },
+        AccessPath::RowidLookup(expr) => match physical {
+            true => format!("RowidLookup {} (rowid={})", name, value(expr)),
+            false => format!("SEARCH {} USING INTEGER PRIMARY KEY (rowid=?)", name),
+        },
+        AccessPath::RowidRange { lower, upper } => {
+            let constraints: Vec<String> =
+                vec![bound("rowid", lower, true), bound("rowid", upper, false)]
+                    .into_iter()
+                    .flatten()
+                    .collect();
+            match physical {
+                true => format!("RowidRangeScan {} ({})", name, constraints.join(" AND ")),
+                false => format!(
+                    "SEARCH {} USING INTEGER PRIMARY KEY ({})",
+                    name,
+                    constraints.join(" AND ")
+                ),
+            }
+        }
+        AccessPath::IndexScan {
+            index,
+            prefix,
+            lower,
+            upper,
+        } => {
+            let key_names: Vec<String> = scope.tables[table_plan.position]
+                .1
+                .indexes
+                .get(index)
+                .map(|index| index.columns.to_vec())
+                .unwrap_or_default();
+            let mut constraints: Vec<String> = prefix
+                .iter()
+                .zip(key_names.iter())
+                .map(|(expr, key)| format!("{}={}", key, value(expr)))
+                .collect();
+            if let Some(key) = key_names.get(prefix.len()) {
+                constraints.extend(bound(key, lower, true));
+                constraints.extend(bound(key, upper, false));
+            }
+            match (physical, constraints.is_empty()) {
+                (true, true) => format!("IndexScan {} USING INDEX {}", name, index),
+                (true, false) => format!(
+                    "IndexSearch {} USING INDEX {} ({})",
+                    name,
+                    index,
+                    constraints.join(" AND ")
+                ),
+                (false, true) => format!("SCAN {} USING INDEX {}", name, index),
+                (false, false) => format!(
+                    "SEARCH {} USING INDEX {} ({})",
+                    name,
+                    index,
+                    constraints.join(" AND ")
+                ),
+            }
+        }
+    };
+    match physical && table_plan.reverse {
+        true => format!("{} REVERSE", description),
+        false => description,
+    }
+}
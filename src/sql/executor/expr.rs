@@ -0,0 +1,977 @@
+use sqlparser::ast::{
+    BinaryOperator, DataType as SqlDataType, Expr, Function, FunctionArg, FunctionArgExpr,
+    UnaryOperator, Value as SqlValue,
+};
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::error::{RaiseAction, Result, SQLRiteError};
+use crate::sql::db::table::{decode_blob, Table};
+use crate::sql::db::value::Value;
+
+/// Largest number of bytes a TEXT or BLOB value can have, SQLITE_MAX_LENGTH in SQLite
+pub const MAX_LENGTH: i64 = 1_000_000_000;
+
+/// Supplies the column values an expression is evaluated against
+pub trait RowSource {
+    /// Returns the value of `column`, optionally qualified by a table name or alias
+    fn get_column(&self, table: Option<&str>, column: &str) -> Result<Value>;
+
+    /// Returns the value of an aggregate function call over the rows of the current group.
+    /// Only the rows of an aggregate query have one.
+    fn get_aggregate(&self, function: &Function) -> Result<Value> {
+        Err(SQLRiteError::General(format!(
+            "misuse of aggregate function {}()",
+            function.name.to_string().to_lowercase()
+        )))
+    }
+}
+
+/// `RowSource` for expressions that can not reference any column, e.g. a VALUES list
+pub struct NoRow;
+
+impl RowSource for NoRow {
+    fn get_column(&self, table: Option<&str>, column: &str) -> Result<Value> {
+        Err(no_such_column(table, column))
+    }
+}
+
+/// `RowSource` reading the row stored under `rowid` in a table
+pub struct TableRow<'a> {
+    pub table: &'a Table,
+    pub rowid: i64,
+}
+
+impl<'a> RowSource for TableRow<'a> {
+    fn get_column(&self, table: Option<&str>, column: &str) -> Result<Value> {
+        if let Some(table) = table {
+            if table != self.table.tb_name {
+                return Err(no_such_column(Some(table), column));
+            }
+        }
+        if self.table.contains_column(column.to_string()) {
+            Ok(self.table.get_value(column, self.rowid))
+        } else if is_rowid_alias(column) {
+            Ok(Value::Integer(self.rowid))
+        } else {
+            Err(no_such_column(table, column))
+        }
+    }
+}
+
+/// `RowSource` reading a row about to be stored under `rowid` in a table, given by the
+/// value of each of its columns
+pub struct ValuesRow<'a> {
+    pub table: &'a Table,
+    pub rowid: i64,
+    pub values: &'a [(String, Value)],
+}
+
+impl<'a> RowSource for ValuesRow<'a> {
+    fn get_column(&self, table: Option<&str>, column: &str) -> Result<Value> {
+        if let Some(table) = table {
+            if table != self.table.tb_name {
+                return Err(no_such_column(Some(table), column));
+            }
+        }
+        match self.values.iter().find(|(col, _)| col == column) {
+            Some((_, val)) => Ok(val.clone()),
+            None if is_rowid_alias(column) => Ok(Value::Integer(self.rowid)),
+            None => Err(no_such_column(table, column)),
+        }
+    }
+}
+
+/// Returns true for the names SQLite accepts for the implicit ROWID column
+pub fn is_rowid_alias(column: &str) -> bool {
+    matches!(column.to_lowercase().as_ref(), "rowid" | "oid" | "_rowid_")
+}
+
+fn no_such_column(table: Option<&str>, column: &str) -> SQLRiteError {
+    match table {
+        Some(table) => SQLRiteError::General(format!("no such column: {}.{}", table, column)),
+        None => SQLRiteError::General(format!("no such column: {}", column)),
+    }
+}
+
+/// Evaluates an expression against a row, following SQLite semantics:
+/// NULL propagates through operators and comparisons, and boolean results
+/// are returned as the integers 1 and 0.
+pub fn evaluate(expr: &Expr, row: &dyn RowSource) -> Result<Value> {
+    match expr {
+        Expr::Identifier(ident) => match row.get_column(None, &ident.value) {
+            Ok(value) => Ok(value),
+            // Like SQLite, a double quoted identifier that is not a column is a string literal
+            Err(err) => match ident.quote_style {
+                Some('"') => Ok(Value::Text(ident.value.to_string())),
+                _ => Err(err),
+            },
+        },
+        Expr::CompoundIdentifier(idents) => match idents.as_slice() {
+            [table, column] => row.get_column(Some(&table.value), &column.value),
+            _ => Err(SQLRiteError::NotImplemented(format!(
+                "Column reference not supported: {}",
+                expr
+            ))),
+        },
+        Expr::Value(value) => evaluate_literal(value),
+        Expr::Nested(e) => evaluate(e, row),
+        Expr::UnaryOp { op, expr } => {
+            let value = evaluate(expr, row)?;
+            match op {
+                UnaryOperator::Plus => Ok(value),
+                UnaryOperator::Minus => match to_numeric(&value) {
+                    Value::Integer(i) => Ok(i
+                        .checked_neg()
+                        .map_or(Value::Real(-(i as f64)), Value::Integer)),
+                    Value::Real(f) => Ok(Value::Real(-f)),
+                    _ => Ok(Value::Null),
+                },
+                UnaryOperator::Not => Ok(from_bool(is_truthy(&value).map(|b| !b))),
+                _ => Err(SQLRiteError::NotImplemented(format!(
+                    "Operator not supported: {}",
+                    op
+                ))),
+            }
+        }
+        Expr::BinaryOp { left, op, right } => evaluate_binary_op(left, op, right, row),
+        Expr::IsNull(e) => Ok(from_bool(Some(evaluate(e, row)?.is_null()))),
+        Expr::IsNotNull(e) => Ok(from_bool(Some(!evaluate(e, row)?.is_null()))),
+        Expr::IsDistinctFrom(a, b) => Ok(from_bool(Some(evaluate(a, row)? != evaluate(b, row)?))),
+        Expr::IsNotDistinctFrom(a, b) => {
+            Ok(from_bool(Some(evaluate(a, row)? == evaluate(b, row)?)))
+        }
+        Expr::Between {
+            expr,
+            negated,
+            low,
+            high,
+        } => {
+            let value = evaluate(expr, row)?;
+            let low = evaluate(low, row)?;
+            let high = evaluate(high, row)?;
+            let result = and(
+                compare(&value, &low, |o| o.is_ge()),
+                compare(&value, &high, |o| o.is_le()),
+            );
+            Ok(from_bool(result.map(|b| b != *negated)))
+        }
+        Expr::InList {
+            expr,
+            list,
+            negated,
+        } => {
+            let value = evaluate(expr, row)?;
+            if value.is_null() {
+                return Ok(Value::Null);
+            }
+            let mut saw_null = false;
+            for item in list {
+                let item = evaluate(item, row)?;
+                if item.is_null() {
+                    saw_null = true;
+                } else if item == value {
+                    return Ok(from_bool(Some(!*negated)));
+                }
+            }
+            match saw_null {
+                true => Ok(Value::Null),
+                false => Ok(from_bool(Some(*negated))),
+            }
+        }
+        Expr::Cast { expr, data_type } | Expr::TryCast { expr, data_type } => {
+            Ok(cast(evaluate(expr, row)?, data_type))
+        }
+        Expr::Case {
+            operand,
+            conditions,
+            results,
+            else_result,
+        } => {
+            let operand = match operand {
+                Some(operand) => Some(evaluate(operand, row)?),
+                None => None,
+            };
+            for (condition, result) in conditions.iter().zip(results) {
+                let condition = evaluate(condition, row)?;
+                let matched = match &operand {
+                    Some(operand) => !operand.is_null() && *operand == condition,
+                    None => is_truthy(&condition) == Some(true),
+                };
+                if matched {
+                    return evaluate(result, row);
+                }
+            }
+            match else_result {
+                Some(else_result) => evaluate(else_result, row),
+                None => Ok(Value::Null),
+            }
+        }
+        Expr::Substring {
+            expr,
+            substring_from,
+            substring_for,
+        } => {
+            let mut args = vec![evaluate(expr, row)?];
+            args.push(match substring_from {
+                Some(from) => evaluate(from, row)?,
+                None => Value::Integer(1),
+            });
+            if let Some(length) = substring_for {
+                args.push(evaluate(length, row)?);
+            }
+            call_function("substr", &args)
+        }
+        Expr::Trim { expr, trim_where } => match trim_where {
+            None => call_function("trim", &[evaluate(expr, row)?]),
+            Some(_) => Err(SQLRiteError::NotImplemented(format!(
+                "Expression not supported: {}",
+                expr
+            ))),
+        },
+        Expr::Function(function) if is_aggregate_call(function) => row.get_aggregate(function),
+        Expr::Function(function) => evaluate_function(function, row),
+        _ => Err(SQLRiteError::NotImplemented(format!(
+            "Expression not supported: {}",
+            expr
+        ))),
+    }
+}
+
+/// Converts a SQL literal into a `Value`
+pub fn evaluate_literal(value: &SqlValue) -> Result<Value> {
+    match value {
+        SqlValue::Number(n, _) => match n.parse::<i64>() {
+            Ok(i) => Ok(Value::Integer(i)),
+            Err(_) => n
+                .parse::<f64>()
+                .map(Value::Real)
+                .map_err(|_| SQLRiteError::General(format!("Invalid number: {}", n))),
+        },
+        SqlValue::SingleQuotedString(s) => Ok(Value::Text(s.to_string())),
+        SqlValue::DoubleQuotedString(s) => Ok(Value::Text(s.to_string())),
+        SqlValue::Boolean(b) => Ok(Value::Bool(*b)),
+        SqlValue::HexStringLiteral(hex) => match decode_blob(&format!("X'{}'", hex)) {
+            Some(bytes) => Ok(Value::Blob(bytes)),
+            None => Err(SQLRiteError::General(format!(
+                "Malformed blob literal: X'{}'",
+                hex
+            ))),
+        },
+        SqlValue::Null => Ok(Value::Null),
+        _ => Err(SQLRiteError::NotImplemented(format!(
+            "Value not supported yet: {}",
+            value
+        ))),
+    }
+}
+
+/// Returns the truth value of a value, `None` standing for the unknown result of NULL
+pub fn is_truthy(value: &Value) -> Option<bool> {
+    match to_numeric(value) {
+        Value::Null => None,
+        Value::Integer(i) => Some(i != 0),
+        Value::Real(f) => Some(f != 0.0),
+        _ => Some(false),
+    }
+}
+
+fn from_bool(value: Option<bool>) -> Value {
+    match value {
+        Some(b) => Value::Integer(b as i64),
+        None => Value::Null,
+    }
+}
+
+fn and(a: Option<bool>, b: Option<bool>) -> Option<bool> {
+    match (a, b) {
+        (Some(false), _) | (_, Some(false)) => Some(false),
+        (Some(true), Some(true)) => Some(true),
+        _ => None,
+    }
+}
+
+fn or(a: Option<bool>, b: Option<bool>) -> Option<bool> {
+    match (a, b) {
+        (Some(true), _) | (_, Some(true)) => Some(true),
+        (Some(false), Some(false)) => Some(false),
+        _ => None,
+    }
+}
+
+fn compare(a: &Value, b: &Value, test: fn(std::cmp::Ordering) -> bool) -> Option<bool> {
+    if a.is_null() || b.is_null() {
+        None
+    } else {
+        Some(test(a.cmp(b)))
+    }
+}
+
+/// Converts a value to INTEGER or REAL the way SQLite does for arithmetic:
+/// text is read as a number when it looks like one and as 0 otherwise.
+pub fn to_numeric(value: &Value) -> Value {
+    match value {
+        Value::Null => Value::Null,
+        Value::Integer(i) => Value::Integer(*i),
+        Value::Real(f) => Value::Real(*f),
+        Value::Bool(b) => Value::Integer(*b as i64),
+        Value::Text(s) => {
+            let s = s.trim();
+            if let Ok(i) = s.parse::<i64>() {
+                Value::Integer(i)
+            } else if let Ok(f) = s.parse::<f64>() {
+                Value::Real(f)
+            } else {
+                Value::Integer(0)
+            }
+        }
+        Value::Blob(_) => Value::Integer(0),
+    }
+}
+
+pub fn to_f64(value: &Value) -> Option<f64> {
+    match value {
+        Value::Integer(i) => Some(*i as f64),
+        Value::Real(f) => Some(*f),
+        _ => None,
+    }
+}
+
+fn to_text(value: &Value) -> Option<String> {
+    match value {
+        Value::Null => None,
+        Value::Blob(bytes) => Some(String::from_utf8_lossy(bytes).to_string()),
+        other => Some(other.to_string()),
+    }
+}
+
+fn to_integer(value: &Value) -> Option<i64> {
+    match to_numeric(value) {
+        Value::Integer(i) => Some(i),
+        Value::Real(f) => Some(f as i64),
+        _ => None,
+    }
+}
+
+fn evaluate_binary_op(
+    left: &Expr,
+    op: &BinaryOperator,
+    right: &Expr,
+    row: &dyn RowSource,
+) -> Result<Value> {
+    let l = evaluate(left, row)?;
+    // AND and OR only need the right hand side when the left one does not decide the result
+    match op {
+        BinaryOperator::And if is_truthy(&l) == Some(false) => return Ok(from_bool(Some(false))),
+        BinaryOperator::Or if is_truthy(&l) == Some(true) => return Ok(from_bool(Some(true))),
+        _ => (),
+    }
+    let r = evaluate(right, row)?;
+
+    match op {
+        BinaryOperator::And => Ok(from_bool(and(is_truthy(&l), is_truthy(&r)))),
+        BinaryOperator::Or => Ok(from_bool(or(is_truthy(&l), is_truthy(&r)))),
+        BinaryOperator::Eq => Ok(from_bool(compare(&l, &r, |o| o.is_eq()))),
+        BinaryOperator::NotEq => Ok(from_bool(compare(&l, &r, |o| o.is_ne()))),
+        BinaryOperator::Lt => Ok(from_bool(compare(&l, &r, |o| o.is_lt()))),
+        BinaryOperator::LtEq => Ok(from_bool(compare(&l, &r, |o| o.is_le()))),
+        BinaryOperator::Gt => Ok(from_bool(compare(&l, &r, |o| o.is_gt()))),
+        BinaryOperator::GtEq => Ok(from_bool(compare(&l, &r, |o| o.is_ge()))),
+        BinaryOperator::StringConcat => match (to_text(&l), to_text(&r)) {
+            (Some(a), Some(b)) => Ok(Value::Text(a + &b)),
+            _ => Ok(Value::Null),
+        },
+        BinaryOperator::Like | BinaryOperator::NotLike => match (to_text(&l), to_text(&r)) {
+            (Some(text), Some(pattern)) => Ok(from_bool(Some(
+                like(&pattern, &text) == (*op == BinaryOperator::Like),
+            ))),
+            _ => Ok(Value::Null),
+        },
+        BinaryOperator::Plus
+        | BinaryOperator::Minus
+        | BinaryOperator::Multiply
+        | BinaryOperator::Divide
+        | BinaryOperator::Modulo => Ok(arithmetic(&to_numeric(&l), op, &to_numeric(&r))),
+        _ => Err(SQLRiteError::NotImplemented(format!(
+            "Operator not supported: {}",
+            op
+        ))),
+    }
+}
+
+fn arithmetic(l: &Value, op: &BinaryOperator, r: &Value) -> Value {
+    match (l, r) {
+        (Value::Null, _) | (_, Value::Null) => Value::Null,
+        (Value::Integer(a), Value::Integer(b)) => {
+            let result = match op {
+                BinaryOperator::Plus => a.checked_add(*b),
+                BinaryOperator::Minus => a.checked_sub(*b),
+                BinaryOperator::Multiply => a.checked_mul(*b),
+                // Division by zero yields NULL in SQLite
+                BinaryOperator::Divide if *b == 0 => return Value::Null,
+                BinaryOperator::Modulo if *b == 0 => return Value::Null,
+                BinaryOperator::Divide => a.checked_div(*b),
+                _ => a.checked_rem(*b),
+            };
+            match result {
+                Some(i) => Value::Integer(i),
+                // Integer overflow falls back to floating point
+                None => arithmetic(&Value::Real(*a as f64), op, &Value::Real(*b as f64)),
+            }
+        }
+        _ => {
+            let (a, b) = match (to_f64(l), to_f64(r)) {
+                (Some(a), Some(b)) => (a, b),
+                _ => return Value::Null,
+            };
+            match op {
+                BinaryOperator::Plus => Value::Real(a + b),
+                BinaryOperator::Minus => Value::Real(a - b),
+                BinaryOperator::Multiply => Value::Real(a * b),
+                _ if b == 0.0 => Value::Null,
+                BinaryOperator::Divide => Value::Real(a / b),
+                _ => Value::Real(a % b),
+            }
+        }
+    }
+}
+
+/// Case insensitive LIKE matching with `%` and `_` wildcards
+fn like(pattern: &str, text: &str) -> bool {
+    fn matches(pattern: &[char], text: &[char]) -> bool {
+        match pattern.split_first() {
+            None => text.is_empty(),
+            Some(('%', rest)) => (0..=text.len()).any(|i| matches(rest, &text[i..])),
+            Some(('_', rest)) => !text.is_empty() && matches(rest, &text[1..]),
+            Some((c, rest)) => match text.split_first() {
+                Some((t, text_rest)) => {
+                    c.to_lowercase().eq(t.to_lowercase()) && matches(rest, text_rest)
+                }
+                None => false,
+            },
+        }
+    }
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    matches(&pattern, &text)
+}
+
+fn cast(value: Value, data_type: &SqlDataType) -> Value {
+    if value.is_null() {
+        return value;
+    }
+    match data_type {
+        SqlDataType::SmallInt(_)
+        | SqlDataType::Int(_)
+        | SqlDataType::BigInt(_)
+        | SqlDataType::TinyInt(_) => to_integer(&value).map_or(Value::Null, Value::Integer),
+        SqlDataType::Real
+        | SqlDataType::Float(_)
+        | SqlDataType::Double
+        | SqlDataType::Decimal(_, _) => match to_numeric(&value) {
+            Value::Integer(i) => Value::Real(i as f64),
+            other => other,
+        },
+        SqlDataType::Text
+        | SqlDataType::Varchar(_)
+        | SqlDataType::Char(_)
+        | SqlDataType::String => to_text(&value).map_or(Value::Null, Value::Text),
+        SqlDataType::Blob(_) | SqlDataType::Bytea => match value {
+            Value::Blob(bytes) => Value::Blob(bytes),
+            other => Value::Blob(to_text(&other).unwrap_or_default().into_bytes()),
+        },
+        SqlDataType::Boolean => is_truthy(&value).map_or(Value::Null, Value::Bool),
+        _ => value,
+    }
+}
+
+/// Evaluates the arguments of a scalar function and calls it
+fn evaluate_function(function: &Function, row: &dyn RowSource) -> Result<Value> {
+    let name = function.name.to_string().to_lowercase();
+    if name == "raise" {
+        return evaluate_raise(function, row);
+    }
+    let mut args: Vec<Value> = vec![];
+    for arg in &function.args {
+        match arg {
+            FunctionArg::Unnamed(FunctionArgExpr::Expr(e)) => args.push(evaluate(e, row)?),
+            _ => {
+                return Err(SQLRiteError::General(format!(
+                    "Unsupported argument for function {}: {}",
+                    name, arg
+                )))
+            }
+        }
+    }
+    call_function(&name, &args)
+}
+
+/// Evaluates `RAISE(IGNORE)` or `RAISE(ABORT|FAIL|ROLLBACK, message)`, which stops the
+/// trigger calling it and the statement that fired the trigger
+fn evaluate_raise(function: &Function, row: &dyn RowSource) -> Result<Value> {
+    let (action, message) = match function.args.as_slice() {
+        [FunctionArg::Unnamed(FunctionArgExpr::Expr(Expr::Identifier(action)))] => (action, None),
+        [FunctionArg::Unnamed(FunctionArgExpr::Expr(Expr::Identifier(action))), FunctionArg::Unnamed(FunctionArgExpr::Expr(message))] => {
+            (action, Some(message))
+        }
+        _ => {
+            return Err(SQLRiteError::General(String::from(
+                "RAISE() expects IGNORE, or ABORT, FAIL or ROLLBACK and a message",
+            )))
+        }
+    };
+    let action = match (action.value.to_uppercase().as_ref(), message) {
+        ("IGNORE", None) => RaiseAction::Ignore,
+        ("ROLLBACK", Some(_)) => RaiseAction::Rollback,
+        ("ABORT", Some(_)) => RaiseAction::Abort,
+        ("FAIL", Some(_)) => RaiseAction::Fail,
+        _ => {
+            return Err(SQLRiteError::General(String::from(
+                "RAISE() expects IGNORE, or ABORT, FAIL or ROLLBACK and a message",
+            )))
+        }
+    };
+    let message = match message {
+        Some(message) => evaluate(message, row)?.to_string(),
+        None => String::new(),
+    };
+    Err(SQLRiteError::Raise(action, message))
+}
+
+/// Returns true if `name` is one of the aggregate functions
+pub fn is_aggregate_function(name: &str) -> bool {
+    matches!(
+        name.to_lowercase().as_ref(),
+        "count" | "sum" | "total" | "avg" | "min" | "max" | "group_concat"
+    )
+}
+
+/// Returns true if a function call is an aggregate, min() and max() being scalar
+/// functions when given more than one argument
+pub fn is_aggregate_call(function: &Function) -> bool {
+    let name = function.name.to_string().to_lowercase();
+    is_aggregate_function(&name)
+        && !(matches!(name.as_ref(), "min" | "max") && function.args.len() > 1)
+}
+
+/// Returns true if an expression calls an aggregate function
+pub fn contains_aggregate(expr: &Expr) -> bool {
+    let mut found = false;
+    walk_expr(expr, &mut |e| {
+        if let Expr::Function(function) = e {
+            found |= is_aggregate_call(function);
+        }
+    });
+    found
+}
+
+/// Computes an aggregate function over the values its argument took in every row of a group.
+/// `count(*)` is given one value per row. Like in SQLite, NULLs are skipped, `sum()` of
+/// integers stays an integer and `total()` is always a REAL.
+pub fn call_aggregate(name: &str, values: &[Value], separator: Option<&str>) -> Result<Value> {
+    let not_null: Vec<&Value> = values.iter().filter(|value| !value.is_null()).collect();
+    match name {
+        "count" => Ok(Value::Integer(not_null.len() as i64)),
+        "sum" | "total" | "avg" => {
+            let numbers: Vec<Value> = not_null.iter().map(|value| to_numeric(value)).collect();
+            let real: f64 = numbers.iter().filter_map(to_f64).sum();
+            match name {
+                "total" => Ok(Value::Real(real)),
+                _ if numbers.is_empty() => Ok(Value::Null),
+                "avg" => Ok(Value::Real(real / numbers.len() as f64)),
+                _ if numbers.iter().all(|n| matches!(n, Value::Integer(_))) => {
+                    let mut sum: i64 = 0;
+                    for n in &numbers {
+                        sum = to_integer(n)
+                            .and_then(|n| sum.checked_add(n))
+                            .ok_or_else(|| SQLRiteError::General("integer overflow".to_string()))?;
+                    }
+                    Ok(Value::Integer(sum))
+                }
+                _ => Ok(Value::Real(real)),
+            }
+        }
+        "min" => Ok(not_null.into_iter().min().cloned().unwrap_or(Value::Null)),
+        "max" => Ok(not_null.into_iter().max().cloned().unwrap_or(Value::Null)),
+        "group_concat" => match not_null.is_empty() {
+            true => Ok(Value::Null),
+            false => Ok(Value::Text(
+                not_null
+                    .iter()
+                    .filter_map(|value| to_text(value))
+                    .collect::<Vec<String>>()
+                    .join(separator.unwrap_or(",")),
+            )),
+        },
+        _ => Err(SQLRiteError::General(format!("no such function: {}", name))),
+    }
+}
+
+/// Calls a scalar function with already evaluated arguments
+fn call_function(name: &str, args: &[Value]) -> Result<Value> {
+    let wrong_arguments =
+        || SQLRiteError::General(format!("wrong number of arguments to function {}()", name));
+    match (name, args) {
+        // zeroblob(N) returns a BLOB consisting of N bytes of 0x00
+        ("zeroblob", [n]) => match to_numeric(n) {
+            Value::Integer(n) if n > MAX_LENGTH => Err(SQLRiteError::General(String::from(
+                "string or blob too big",
+            ))),
+            Value::Integer(n) => Ok(Value::Blob(vec![0; n.max(0) as usize])),
+            _ => Err(SQLRiteError::General(format!(
+                "zeroblob() expects an integer argument, got {}",
+                n
+            ))),
+        },
+        // length(X) returns the number of bytes for a BLOB and the number of characters otherwise
+        ("length", [x]) => match x {
+            Value::Null => Ok(Value::Null),
+            Value::Blob(bytes) => Ok(Value::Integer(bytes.len() as i64)),
+            other => Ok(Value::Integer(other.to_string().chars().count() as i64)),
+        },
+        ("lower", [x]) => Ok(to_text(x).map_or(Value::Null, |s| Value::Text(s.to_lowercase()))),
+        ("upper", [x]) => Ok(to_text(x).map_or(Value::Null, |s| Value::Text(s.to_uppercase()))),
+        ("trim", [x]) => Ok(to_text(x).map_or(Value::Null, |s| Value::Text(s.trim().to_string()))),
+        ("ltrim", [x]) => {
+            Ok(to_text(x).map_or(Value::Null, |s| Value::Text(s.trim_start().to_string())))
+        }
+        ("rtrim", [x]) => {
+            Ok(to_text(x).map_or(Value::Null, |s| Value::Text(s.trim_end().to_string())))
+        }
+        ("hex", [x]) => Ok(match x {
+            Value::Null => Value::Text(String::new()),
+            Value::Blob(bytes) => Value::Text(bytes.iter().map(|b| format!("{:02X}", b)).collect()),
+            other => Value::Text(
+                other
+                    .to_string()
+                    .bytes()
+                    .map(|b| format!("{:02X}", b))
+                    .collect(),
+            ),
+        }),
+        ("abs", [x]) => Ok(match to_numeric(x) {
+            Value::Integer(i) => i
+                .checked_abs()
+                .map_or(Value::Real((i as f64).abs()), Value::Integer),
+            Value::Real(f) => Value::Real(f.abs()),
+            other => other,
+        }),
+        ("round", [x]) | ("round", [x, _]) => {
+            let digits = match args.get(1) {
+                Some(digits) => to_integer(digits).unwrap_or(0).max(0),
+                None => 0,
+            };
+            Ok(match to_numeric(x) {
+                Value::Null => Value::Null,
+                Value::Integer(i) if digits == 0 => Value::Real(i as f64),
+                n => {
+                    let factor = 10f64.powi(digits as i32);
+                    Value::Real((to_f64(&n).unwrap_or(0.0) * factor).round() / factor)
+                }
+            })
+        }
+        ("typeof", [x]) => Ok(Value::Text(
+            match x {
+                Value::Null => "null",
+                Value::Integer(_) | Value::Bool(_) => "integer",
+                Value::Real(_) => "real",
+                Value::Text(_) => "text",
+                Value::Blob(_) => "blob",
+            }
+            .to_string(),
+        )),
+        ("coalesce", args) | ("ifnull", args) if args.len() >= 2 => Ok(args
+            .iter()
+            .find(|value| !value.is_null())
+            .cloned()
+            .unwrap_or(Value::Null)),
+        ("nullif", [a, b]) => Ok(if !a.is_null() && a == b {
+            Value::Null
+        } else {
+            a.clone()
+        }),
+        ("instr", [haystack, needle]) => match (to_text(haystack), to_text(needle)) {
+            (Some(h), Some(n)) => Ok(Value::Integer(match h.find(&n) {
+                Some(pos) => h[..pos].chars().count() as i64 + 1,
+                None => 0,
+            })),
+            _ => Ok(Value::Null),
+        },
+        ("replace", [x, from, to]) => match (to_text(x), to_text(from), to_text(to)) {
+            (Some(x), Some(from), Some(to)) if !from.is_empty() => {
+                Ok(Value::Text(x.replace(&from, &to)))
+            }
+            (Some(x), Some(_), Some(_)) => Ok(Value::Text(x)),
+            _ => Ok(Value::Null),
+        },
+        ("substr", [x, start])
+        | ("substr", [x, start, _])
+        | ("substring", [x, start])
+        | ("substring", [x, start, _]) => {
+            let (text, start) = match (to_text(x), to_integer(start)) {
+                (Some(text), Some(start)) => (text, start),
+                _ => return Ok(Value::Null),
+            };
+            let chars: Vec<char> = text.chars().collect();
+            let len = chars.len() as i64;
+            // SQLite positions are 1-based, negative positions count from the end
+            let begin = if start > 0 {
+                start - 1
+            } else if start < 0 {
+                (len + start).max(0)
+            } else {
+                0
+            };
+            let count = match args.get(2) {
+                Some(count) => match to_integer(count) {
+                    Some(count) => count.max(0),
+                    None => return Ok(Value::Null),
+                },
+                None => len,
+            };
+            let begin = begin.min(len) as usize;
+            let end = (begin as i64 + count).min(len) as usize;
+            Ok(Value::Text(chars[begin..end].iter().collect()))
+        }
+        // With more than one argument min() and max() are scalar functions
+        ("min", args) | ("max", args) if args.len() >= 2 => {
+            if args.iter().any(|value| value.is_null()) {
+                return Ok(Value::Null);
+            }
+            let result = match name {
+                "min" => args.iter().min(),
+                _ => args.iter().max(),
+            };
+            Ok(result.cloned().unwrap_or(Value::Null))
+        }
+        // The current date and time in UTC, formatted the way SQLite does
+        ("current_timestamp", []) => Ok(Value::Text(current_datetime("%F %T"))),
+        ("current_date", []) => Ok(Value::Text(current_datetime("%F"))),
+        ("current_time", []) => Ok(Value::Text(current_datetime("%T"))),
+        _ if is_aggregate_function(name) => Err(SQLRiteError::General(format!(
+            "misuse of aggregate function {}()",
+            name
+        ))),
+        (
+            "zeroblob" | "length" | "lower" | "upper" | "trim" | "ltrim" | "rtrim" | "hex" | "abs"
+            | "round" | "typeof" | "coalesce" | "ifnull" | "nullif" | "instr" | "replace"
+            | "substr" | "substring" | "current_timestamp" | "current_date" | "current_time",
+            _,
+        ) => Err(wrong_arguments()),
+        _ => Err(SQLRiteError::General(format!("no such function: {}", name))),
+    }
+}
+
+/// Formats the current UTC date and time. `format` supports `%F` for `YYYY-MM-DD`
+/// and `%T` for `HH:MM:SS`.
+fn current_datetime(format: &str) -> String {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs() as i64);
+    let (days, time) = (seconds.div_euclid(86400), seconds.rem_euclid(86400));
+
+    // Converts days since 1970-01-01 to a civil date, see
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format
+        .replace("%F", &format!("{:04}-{:02}-{:02}", year, month, day))
+        .replace(
+            "%T",
+            &format!(
+                "{:02}:{:02}:{:02}",
+                time / 3600,
+                time % 3600 / 60,
+                time % 60
+            ),
+        )
+}
+
+/// Returns 1 for a call of RAISE(), whose first argument is a keyword
+/// rather than an expression, 0 for any other function
+fn raise_actions(function: &Function) -> usize {
+    function.name.to_string().eq_ignore_ascii_case("raise") as usize
+}
+
+/// Calls `visit` on an expression and every expression nested in it
+pub fn walk_expr(expr: &Expr, visit: &mut dyn FnMut(&Expr)) {
+    visit(expr);
+    match expr {
+        Expr::Nested(e)
+        | Expr::IsNull(e)
+        | Expr::IsNotNull(e)
+        | Expr::UnaryOp { expr: e, .. }
+        | Expr::Cast { expr: e, .. }
+        | Expr::TryCast { expr: e, .. }
+        | Expr::Trim { expr: e, .. }
+        | Expr::Collate { expr: e, .. } => walk_expr(e, visit),
+        Expr::IsDistinctFrom(a, b) | Expr::IsNotDistinctFrom(a, b) => {
+            walk_expr(a, visit);
+            walk_expr(b, visit);
+        }
+        Expr::BinaryOp { left, right, .. } => {
+            walk_expr(left, visit);
+            walk_expr(right, visit);
+        }
+        Expr::Between {
+            expr, low, high, ..
+        } => {
+            walk_expr(expr, visit);
+            walk_expr(low, visit);
+            walk_expr(high, visit);
+        }
+        Expr::InList { expr, list, .. } => {
+            walk_expr(expr, visit);
+            list.iter().for_each(|e| walk_expr(e, visit));
+        }
+        Expr::Substring {
+            expr,
+            substring_from,
+            substring_for,
+        } => {
+            walk_expr(expr, visit);
+            substring_from.iter().for_each(|e| walk_expr(e, visit));
+            substring_for.iter().for_each(|e| walk_expr(e, visit));
+        }
+        Expr::Case {
+            operand,
+            conditions,
+            results,
+            else_result,
+        } => {
+            operand.iter().for_each(|e| walk_expr(e, visit));
+            conditions.iter().for_each(|e| walk_expr(e, visit));
+            results.iter().for_each(|e| walk_expr(e, visit));
+            else_result.iter().for_each(|e| walk_expr(e, visit));
+        }
+        Expr::Function(function) => {
+            for arg in function.args.iter().skip(raise_actions(function)) {
+                if let FunctionArg::Unnamed(FunctionArgExpr::Expr(e))
+                | FunctionArg::Named {
+                    arg: FunctionArgExpr::Expr(e),
+                    ..
+                } = arg
+                {
+                    walk_expr(e, visit);
+                }
+            }
+        }
+        Expr::Tuple(list) => list.iter().for_each(|e| walk_expr(e, visit)),
+        _ => (),
+    }
+}
+
+/// Calls `visit` on an expression and every expression nested in it, allowing them to be
+/// rewritten in place
+pub fn walk_expr_mut(expr: &mut Expr, visit: &mut dyn FnMut(&mut Expr)) {
+    visit(expr);
+    match expr {
+        Expr::Nested(e)
+        | Expr::IsNull(e)
+        | Expr::IsNotNull(e)
+        | Expr::UnaryOp { expr: e, .. }
+        | Expr::Cast { expr: e, .. }
+        | Expr::TryCast { expr: e, .. }
+        | Expr::Trim { expr: e, .. }
+        | Expr::Collate { expr: e, .. } => walk_expr_mut(e, visit),
+        Expr::IsDistinctFrom(a, b) | Expr::IsNotDistinctFrom(a, b) => {
+            walk_expr_mut(a, visit);
+            walk_expr_mut(b, visit);
+        }
+        Expr::BinaryOp { left, right, .. } => {
+            walk_expr_mut(left, visit);
+            walk_expr_mut(right, visit);
+        }
+        Expr::Between {
+            expr, low, high, ..
+        } => {
+            walk_expr_mut(expr, visit);
+            walk_expr_mut(low, visit);
+            walk_expr_mut(high, visit);
+        }
+        Expr::InList { expr, list, .. } => {
+            walk_expr_mut(expr, visit);
+            list.iter_mut().for_each(|e| walk_expr_mut(e, visit));
+        }
+        Expr::Substring {
+            expr,
+            substring_from,
+            substring_for,
+        } => {
+            walk_expr_mut(expr, visit);
+            substring_from
+                .iter_mut()
+                .for_each(|e| walk_expr_mut(e, visit));
+            substring_for
+                .iter_mut()
+                .for_each(|e| walk_expr_mut(e, visit));
+        }
+        Expr::Case {
+            operand,
+            conditions,
+            results,
+            else_result,
+        } => {
+            operand.iter_mut().for_each(|e| walk_expr_mut(e, visit));
+            conditions.iter_mut().for_each(|e| walk_expr_mut(e, visit));
+            results.iter_mut().for_each(|e| walk_expr_mut(e, visit));
+            else_result.iter_mut().for_each(|e| walk_expr_mut(e, visit));
+        }
+        Expr::Function(function) => {
+            let skip = raise_actions(function);
+            for arg in function.args.iter_mut().skip(skip) {
+                if let FunctionArg::Unnamed(FunctionArgExpr::Expr(e))
+                | FunctionArg::Named {
+                    arg: FunctionArgExpr::Expr(e),
+                    ..
+                } = arg
+                {
+                    walk_expr_mut(e, visit);
+                }
+            }
+        }
+        Expr::Tuple(list) => list.iter_mut().for_each(|e| walk_expr_mut(e, visit)),
+        _ => (),
+    }
+}
+
+/// Returns the names of the columns an expression reads, in order of appearance
+pub fn referenced_columns(expr: &Expr) -> Vec<String> {
+    let mut columns: Vec<String> = vec![];
+    walk_expr(expr, &mut |e| {
+        let column = match e {
+            Expr::Identifier(ident) => Some(&ident.value),
+            Expr::CompoundIdentifier(idents) => idents.last().map(|ident| &ident.value),
+            _ => None,
+        };
+        if let Some(column) = column {
+            if !columns.contains(column) {
+                columns.push(column.to_string());
+            }
+        }
+    });
+    columns
+}
+
+/// Renames every reference to a column inside an expression
+pub fn rename_column(expr: &mut Expr, old_name: &str, new_name: &str) {
+    walk_expr_mut(expr, &mut |e| match e {
+        Expr::Identifier(ident) if ident.value == old_name => ident.value = new_name.to_string(),
+        Expr::CompoundIdentifier(idents) => {
+            if let Some(ident) = idents.last_mut() {
+                if ident.value == old_name {
+                    ident.value = new_name.to_string();
+                }
+            }
+        }
+        _ => (),
+    });
+}
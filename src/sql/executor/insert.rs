@@ -0,0 +1,302 @@
+use sqlparser::ast::{Expr, SqliteOnConflict};
+use std::collections::BTreeSet;
+
+use crate::error::{Result, SQLRiteError};
+use crate::sql::db::database::Database;
+use crate::sql::db::table::{NewRow, Table};
+use crate::sql::db::value::Value;
+use crate::sql::executor::expr::{
+    evaluate, is_rowid_alias, is_truthy, RowSource, TableRow, ValuesRow,
+};
+use crate::sql::executor::select::{execute_returning, execute_select};
+use crate::sql::executor::trigger::{self, TriggerRow};
+use crate::sql::parser::insert::{InsertQuery, Upsert, UpsertAction};
+use crate::sql::parser::trigger::{TriggerEvent, TriggerTiming};
+
+/// Table name the expressions of an `ON CONFLICT DO UPDATE` clause read the row being inserted by
+const EXCLUDED: &str = "excluded";
+
+/// `RowSource` for the expressions of an `ON CONFLICT DO UPDATE` clause, reading the existing
+/// row of the table, or the row being inserted when qualified with `excluded`
+struct UpsertRow<'a> {
+    existing: TableRow<'a>,
+    excluded: ValuesRow<'a>,
+}
+
+impl<'a> RowSource for UpsertRow<'a> {
+    fn get_column(&self, table: Option<&str>, column: &str) -> Result<Value> {
+        match table {
+            Some(table) if table.eq_ignore_ascii_case(EXCLUDED) => {
+                self.excluded.get_column(None, column)
+            }
+            _ => self.existing.get_column(table, column),
+        }
+    }
+}
+
+/// Runs an INSERT query, resolving the conflicts of every row with the ON CONFLICT clause of
+/// the query or else with its `OR` conflict resolution, the way SQLite does:
+///
+/// * ABORT, the default, fails the statement and undoes the rows it already inserted
+/// * FAIL fails the statement but keeps the rows it already inserted
+/// * IGNORE skips the rows breaking a NOT NULL, CHECK, PRIMARY KEY or UNIQUE constraint
+/// * REPLACE deletes the existing rows a new row conflicts with before inserting it
+/// * ROLLBACK fails the statement and rolls back the open transaction
+///
+/// The triggers of the table fire for every inserted row, and an INSERT into a view runs its
+/// INSTEAD OF INSERT triggers instead.
+/// Returns the ROWIDs of the rows inserted or updated by the statement, in order.
+pub fn execute_insert(query: InsertQuery, db: &mut Database) -> Result<Vec<i64>> {
+    let table_name = query.table_name;
+    // The rows of INSERT ... SELECT are all read before inserting any,
+    // so the query does not see the rows it inserts
+    let values = match &query.select {
+        Some(select) => execute_select(select, db)?.rows,
+        None => query.rows,
+    };
+
+    if trigger::has_instead_of_triggers(db, &table_name, &TriggerEvent::Insert) {
+        if let Some(returning) = &query.returning {
+            execute_returning(returning, db, &table_name, &[])?;
+        }
+        let columns = query.columns;
+        db.run_statement(false, |db| {
+            trigger::insert_into_view(db, &table_name, columns, &values)
+        })?;
+        return Ok(vec![]);
+    }
+    if db.contains_view(&table_name) {
+        return Err(SQLRiteError::General(format!(
+            "cannot modify {} because it is a view",
+            table_name
+        )));
+    }
+    // Checking if Table exists in Database
+    let db_table = match db.contains_table(table_name.to_string()) {
+        true => db.get_table(table_name.to_string())?,
+        false => return Err(SQLRiteError::Internal("Table doesn't exist".to_string())),
+    };
+    // Without a column list, every column of the table is given a value in declaration order,
+    // except the generated columns
+    let columns = match query.columns {
+        Some(columns) => columns,
+        None => {
+            let columns: Vec<String> = db_table
+                .columns
+                .iter()
+                .filter(|col| col.generated.is_none())
+                .map(|col| col.column_name.to_string())
+                .collect();
+            if let Some(value) = values.iter().find(|value| value.len() != columns.len()) {
+                return Err(SQLRiteError::General(format!(
+                    "table {} has {} columns but {} values were supplied",
+                    table_name,
+                    columns.len(),
+                    value.len()
+                )));
+            }
+            columns
+        }
+    };
+    // Checking if columns on INSERT query exist on Table, the ROWID being one of them
+    if !columns
+        .iter()
+        .all(|column| db_table.contains_column(column.to_string()) || is_rowid_alias(column))
+    {
+        return Err(SQLRiteError::Internal(
+            "Cannot insert, some of the columns do not exist".to_string(),
+        ));
+    }
+    if let Some(upsert) = &query.upsert {
+        validate_upsert(db_table, upsert)?;
+    }
+    // The RETURNING clause is checked before inserting anything
+    if let Some(returning) = &query.returning {
+        execute_returning(returning, db, &table_name, &[])?;
+    }
+
+    let on_conflict = query.on_conflict;
+    let upsert = query.upsert;
+    // A single row is stored as a whole or not at all, so a statement inserting one row
+    // only needs undoing when it may have deleted or updated other rows first
+    let atomic = on_conflict != SqliteOnConflict::Fail
+        && (values.len() > 1 || on_conflict == SqliteOnConflict::Replace || upsert.is_some());
+    let result = db.run_statement(atomic, |db| {
+        let mut rowids: Vec<i64> = vec![];
+        for value in &values {
+            // Checking if number of columns in query are the same as number of values
+            if columns.len() != value.len() {
+                return Err(SQLRiteError::Internal(format!(
+                    "{} values for {} columns",
+                    value.len(),
+                    columns.len()
+                )));
+            }
+            // With FAIL, the rows inserted before the failing one are kept
+            match insert_row(db, &table_name, &columns, value, &on_conflict, &upsert) {
+                Ok(Some(rowid)) => rowids.push(rowid),
+                Ok(None) => (),
+                Err(err) if on_conflict == SqliteOnConflict::Fail => {
+                    return Ok((rowids, Some(err)))
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        Ok((rowids, None))
+    });
+
+    match result {
+        Ok((rowids, None)) => Ok(rowids),
+        Ok((_, Some(err))) => Err(err),
+        Err(err) => {
+            if on_conflict == SqliteOnConflict::Rollback && db.transaction.is_some() {
+                db.rollback()?;
+            }
+            Err(err)
+        }
+    }
+}
+
+/// Checks that the columns an ON CONFLICT clause names are those of the PRIMARY KEY
+/// or of a UNIQUE constraint or index of the table
+fn validate_upsert(table: &Table, upsert: &Upsert) -> Result<()> {
+    if upsert.target.is_empty() {
+        return Ok(());
+    }
+    let target: BTreeSet<&String> = upsert.target.iter().collect();
+    let rowid_column = table.rowid_column().unwrap_or("rowid");
+    let matches_rowid = upsert.target.len() == 1 && upsert.target[0] == rowid_column;
+    let matches_index = table.indexes.values().any(|index| {
+        index.is_unique
+            && index.predicate.is_none()
+            && index.columns.iter().collect::<BTreeSet<&String>>() == target
+    });
+    match matches_rowid || matches_index {
+        true => Ok(()),
+        false => Err(SQLRiteError::General(String::from(
+            "ON CONFLICT clause does not match any PRIMARY KEY or UNIQUE constraint",
+        ))),
+    }
+}
+
+/// Inserts a single row, resolving its conflicts, and runs the BEFORE and AFTER INSERT
+/// triggers of the table around it.
+/// Returns the ROWID of the row inserted or updated, `None` if the row was skipped.
+fn insert_row(
+    db: &mut Database,
+    table_name: &str,
+    columns: &[String],
+    values: &[Value],
+    on_conflict: &SqliteOnConflict,
+    upsert: &Option<Upsert>,
+) -> Result<Option<i64>> {
+    let event = TriggerEvent::Insert;
+    let fires = trigger::has_triggers(db, table_name, &event);
+    if fires {
+        let row = db
+            .get_table(table_name.to_string())?
+            .prepare_row(columns, values)?;
+        let new = TriggerRow {
+            rowid: Some(row.rowid),
+            values: row.values,
+        };
+        let before = trigger::fire_triggers(
+            db,
+            table_name,
+            TriggerTiming::Before,
+            &event,
+            None,
+            Some(&new),
+        )?;
+        if !before {
+            return Ok(None);
+        }
+    }
+
+    // The row is prepared after the BEFORE triggers ran, since they may have changed the table
+    let table = db.get_table(table_name.to_string())?;
+    let row = table.prepare_row(columns, values)?;
+    let conflicts = table.conflicts(&row)?;
+
+    if let Some(upsert) = upsert {
+        let target: BTreeSet<&String> = upsert.target.iter().collect();
+        let conflict = conflicts.iter().find(|(columns, _)| {
+            target.is_empty() || columns.iter().collect::<BTreeSet<&String>>() == target
+        });
+        if let Some((_, rowid)) = conflict {
+            return match &upsert.action {
+                UpsertAction::Nothing => Ok(None),
+                UpsertAction::Update {
+                    assignments,
+                    selection,
+                } => update_conflicting_row(db, table_name, *rowid, &row, assignments, selection),
+            };
+        }
+    }
+
+    match on_conflict {
+        SqliteOnConflict::Ignore
+            if !conflicts.is_empty() || table.validate_new_row(&row).is_err() =>
+        {
+            return Ok(None);
+        }
+        SqliteOnConflict::Replace => {
+            let rowids: BTreeSet<i64> = conflicts.iter().map(|(_, rowid)| *rowid).collect();
+            for rowid in rowids {
+                db.delete_row(table_name, rowid)?;
+            }
+        }
+        _ => (),
+    }
+    let rowid = db.store_row(table_name, &row)?;
+    if fires {
+        let new = TriggerRow::read(db.get_table(table_name.to_string())?, rowid);
+        trigger::fire_triggers(
+            db,
+            table_name,
+            TriggerTiming::After,
+            &event,
+            None,
+            Some(&new),
+        )?;
+    }
+    Ok(Some(rowid))
+}
+
+/// Applies the `DO UPDATE` action of an ON CONFLICT clause to the existing row a new row
+/// conflicts with. Returns the ROWID of the row, `None` if the WHERE clause of the action
+/// left it untouched.
+fn update_conflicting_row(
+    db: &mut Database,
+    table_name: &str,
+    rowid: i64,
+    row: &NewRow,
+    assignments: &[(String, Expr)],
+    selection: &Option<Expr>,
+) -> Result<Option<i64>> {
+    let table = db.get_table(table_name.to_string())?;
+    let source = UpsertRow {
+        existing: TableRow { table, rowid },
+        excluded: ValuesRow {
+            table,
+            rowid: row.rowid,
+            values: &row.values,
+        },
+    };
+    if let Some(selection) = selection {
+        if is_truthy(&evaluate(selection, &source)?) != Some(true) {
+            return Ok(None);
+        }
+    }
+
+    let mut values: Vec<(String, Value)> = vec![];
+    for (column, expr) in assignments {
+        if !table.contains_column(column.to_string()) {
+            return Err(SQLRiteError::General(format!("no such column: {}", column)));
+        }
+        table.validate_assignment(column)?;
+        values.push((column.to_string(), evaluate(expr, &source)?));
+    }
+    let updated = trigger::update_rows(db, table_name, vec![(rowid, values)])?;
+    Ok(updated.first().copied())
+}
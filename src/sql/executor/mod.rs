@@ -0,0 +1,8 @@
+pub mod delete;
+pub mod explain;
+pub mod expr;
+pub mod insert;
+pub mod planner;
+pub mod select;
+pub mod trigger;
+pub mod update;
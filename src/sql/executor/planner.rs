@@ -0,0 +1,862 @@
+use sqlparser::ast::{BinaryOperator, Expr, Ident, OrderByExpr};
+use std::collections::BTreeSet;
+
+use crate::error::{Result, SQLRiteError};
+use crate::sql::db::database::Database;
+use crate::sql::db::index::Index;
+use crate::sql::db::stats::ColumnStats;
+use crate::sql::db::table::Table;
+use crate::sql::db::value::Value;
+use crate::sql::executor::expr::{evaluate, is_rowid_alias, walk_expr, NoRow};
+use crate::sql::parser::parameter::parameter_index;
+use crate::sql::parser::select::{FromTable, JoinKind};
+
+/// The tables of a query in FROM order, used to resolve column references
+/// to the table they belong to
+pub struct Scope<'a> {
+    /// Name each table is referenced by (its alias or its name) and the table itself
+    pub tables: Vec<(String, &'a Table)>,
+}
+
+impl<'a> Scope<'a> {
+    /// Builds the scope of a FROM clause. Views are read from the tables
+    /// they were materialized into, see `materialize_views`, and materialized
+    /// views from the table holding their rows.
+    pub fn new(db: &'a Database, from: &[FromTable], views: &'a [Table]) -> Result<Scope<'a>> {
+        let mut tables: Vec<(String, &'a Table)> = vec![];
+        for from_table in from {
+            let table = db
+                .tables
+                .get(&from_table.name)
+                .or_else(|| {
+                    db.views
+                        .get(&from_table.name)
+                        .and_then(|view| view.rows.as_ref())
+                })
+                .or_else(|| views.iter().find(|view| view.tb_name == from_table.name))
+                .ok_or_else(|| {
+                    SQLRiteError::General(format!("no such table: {}", from_table.name))
+                })?;
+            tables.push((from_table.reference_name().to_string(), table));
+        }
+        Ok(Scope { tables })
+    }
+
+    /// Returns the position of the table a column reference belongs to.
+    /// Unqualified columns must exist in exactly one table.
+    pub fn resolve(&self, qualifier: Option<&str>, column: &str) -> Result<usize> {
+        let has_column =
+            |table: &Table| table.contains_column(column.to_string()) || is_rowid_alias(column);
+        match qualifier {
+            Some(qualifier) => match self.tables.iter().position(|(name, _)| name == qualifier) {
+                Some(i) if has_column(self.tables[i].1) => Ok(i),
+                _ => Err(SQLRiteError::General(format!(
+                    "no such column: {}.{}",
+                    qualifier, column
+                ))),
+            },
+            None => {
+                let matches: Vec<usize> = (0..self.tables.len())
+                    .filter(|i| has_column(self.tables[*i].1))
+                    .collect();
+                match matches.as_slice() {
+                    [i] => Ok(*i),
+                    [] => Err(SQLRiteError::General(format!("no such column: {}", column))),
+                    _ => Err(SQLRiteError::General(format!(
+                        "ambiguous column name: {}",
+                        column
+                    ))),
+                }
+            }
+        }
+    }
+
+    /// Resolves a column reference expression to its table position and column name
+    pub fn resolve_column(&self, expr: &Expr) -> Option<(usize, String)> {
+        match expr {
+            Expr::Identifier(ident) => self
+                .resolve(None, &ident.value)
+                .ok()
+                .map(|i| (i, ident.value.to_string())),
+            Expr::CompoundIdentifier(idents) if idents.len() == 2 => self
+                .resolve(Some(&idents[0].value), &idents[1].value)
+                .ok()
+                .map(|i| (i, idents[1].value.to_string())),
+            Expr::Nested(expr) => self.resolve_column(expr),
+            _ => None,
+        }
+    }
+
+    /// Returns the positions of every table an expression reads from
+    pub fn referenced_tables(&self, expr: &Expr) -> Result<BTreeSet<usize>> {
+        let mut tables: BTreeSet<usize> = BTreeSet::new();
+        let mut error: Option<SQLRiteError> = None;
+        walk_expr(expr, &mut |e| {
+            let resolved = match e {
+                // Parameters of a statement planned before values are bound to them
+                // read no table, see `PreparedStatement`
+                Expr::Identifier(_) if parameter_index(e).is_some() => return,
+                Expr::Identifier(ident) => match self.resolve(None, &ident.value) {
+                    // A double quoted identifier that is not a column is a string literal
+                    Err(_) if ident.quote_style == Some('"') => return,
+                    resolved => resolved,
+                },
+                Expr::CompoundIdentifier(idents) if idents.len() == 2 => {
+                    self.resolve(Some(&idents[0].value), &idents[1].value)
+                }
+                Expr::CompoundIdentifier(idents) => Err(SQLRiteError::NotImplemented(format!(
+                    "Column reference {} is not supported.",
+                    Expr::CompoundIdentifier(idents.to_vec())
+                ))),
+                _ => return,
+            };
+            match resolved {
+                Ok(i) => {
+                    tables.insert(i);
+                }
+                Err(err) => {
+                    error.get_or_insert(err);
+                }
+            }
+        });
+        match error {
+            Some(err) => Err(err),
+            None => Ok(tables),
+        }
+    }
+
+    /// Returns true if the expression reads the ROWID of the table at `level`,
+    /// either through one of its aliases or an INTEGER PRIMARY KEY column
+    fn is_rowid(&self, level: usize, expr: &Expr) -> bool {
+        match self.resolve_column(expr) {
+            Some((i, column)) if i == level => {
+                let table = self.tables[level].1;
+                (is_rowid_alias(&column) && !table.contains_column(column.to_string()))
+                    || table.rowid_column() == Some(column.as_str())
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns true if `expr`, read from the table at `level`, computes the key part of an index
+    fn is_key_part(&self, level: usize, key_part: &Expr, expr: &Expr) -> bool {
+        match key_part {
+            Expr::Identifier(ident) => match self.resolve_column(expr) {
+                Some((i, column)) => i == level && column == ident.value,
+                None => false,
+            },
+            _ => {
+                self.referenced_tables(expr).ok() == Some(BTreeSet::from([level]))
+                    && strip_qualifiers(expr) == *key_part
+            }
+        }
+    }
+}
+
+/// One end of a range scan
+#[derive(Debug, Clone)]
+pub struct Bound {
+    /// Expression computing the bound, evaluated before the scan starts
+    pub expr: Expr,
+    /// Value representing if the bound itself is part of the range
+    pub inclusive: bool,
+}
+
+/// How the rows of a table are read
+#[derive(Debug, Clone)]
+pub enum AccessPath {
+    /// Every row, in ROWID order
+    FullScan,
+    /// The single row whose ROWID is given by the expression
+    RowidLookup(Expr),
+    /// The rows whose ROWID falls between the bounds, in ROWID order
+    RowidRange {
+        lower: Option<Bound>,
+        upper: Option<Bound>,
+    },
+    /// The rows of an index whose key starts with the `prefix` values and whose next
+    /// key part falls between the bounds, in index order.
+    /// Without prefix nor bounds it is a full scan of the index.
+    IndexScan {
+        index: String,
+        prefix: Vec<Expr>,
+        lower: Option<Bound>,
+        upper: Option<Bound>,
+    },
+}
+
+/// Tables of a query are only reordered when there are at most this many of them
+const MAX_REORDERED_TABLES: usize = 6;
+/// Rows assumed to be in a table that was never analyzed
+const DEFAULT_ROW_COUNT: f64 = 1000.0;
+/// Rows assumed to share the same value of an indexed column that was never analyzed
+const DEFAULT_EQUAL_ROWS: f64 = 10.0;
+/// Fraction of rows assumed to satisfy a range bound, or any other condition,
+/// when there are no statistics to tell
+const DEFAULT_SELECTIVITY: f64 = 0.25;
+
+/// How a single table of the FROM clause is read and joined
+#[derive(Debug, Clone)]
+pub struct TablePlan {
+    /// Name of the table
+    pub table: String,
+    /// Name the table is referenced by in the query
+    pub name: String,
+    /// Position of the table in the FROM clause, and in the `Scope` of the query
+    pub position: usize,
+    /// How the table is joined to the tables before it
+    pub join: JoinKind,
+    /// How the rows of the table are read
+    pub access: AccessPath,
+    /// Value representing if the rows are read backwards, to satisfy a descending ORDER BY
+    pub reverse: bool,
+    /// Conditions checked on every row once this table is joined
+    pub filter: Vec<Expr>,
+    /// Estimated number of rows of this table passing the filter, for every row
+    /// of the tables joined before
+    pub estimated_rows: f64,
+}
+
+/// The plan the executor follows to run a SELECT query: the tables are
+/// joined with nested loops, in the order picked by the planner
+#[derive(Debug, Clone)]
+pub struct QueryPlan {
+    /// Tables of the query, outermost loop first
+    pub tables: Vec<TablePlan>,
+    /// Conditions checked on every complete row, such as the ones reading LEFT JOINed tables
+    pub residual: Vec<Expr>,
+    /// Value representing if the rows need to be sorted for the ORDER BY, as
+    /// opposed to being read in the right order already
+    pub sort: bool,
+    /// Estimated cost of running the plan, roughly the number of rows it reads
+    pub cost: f64,
+}
+
+/// A comparison of an expression over the table being planned with a value that is
+/// known before the table is read
+struct Term {
+    /// Side of the comparison reading the table being planned
+    lhs: Expr,
+    op: BinaryOperator,
+    /// Side of the comparison only reading tables joined before
+    rhs: Expr,
+}
+
+/// A way of reading a table, with its estimated number of rows read and cost
+struct Candidate {
+    access: AccessPath,
+    rows: f64,
+    cost: f64,
+}
+
+/// Plans a SELECT query over the tables of `scope`.
+/// `order_by` holds the ORDER BY terms with output aliases and positions already
+/// replaced by the expressions they stand for.
+///
+/// The planner estimates the cost of every join order and access path using the
+/// statistics collected by ANALYZE, or default estimates for tables never analyzed,
+/// and keeps the cheapest plan. Like SQLite, queries with LEFT or CROSS joins keep
+/// the tables in FROM order.
+pub fn plan_query(
+    scope: &Scope,
+    from: &[FromTable],
+    selection: Option<&Expr>,
+    order_by: &[OrderByExpr],
+) -> Result<QueryPlan> {
+    let left_joined: BTreeSet<usize> = from
+        .iter()
+        .enumerate()
+        .filter(|(_, table)| table.join == JoinKind::Left)
+        .map(|(i, _)| i)
+        .collect();
+
+    // The ON clause of an inner join is just another condition of the WHERE clause,
+    // while the one of a LEFT JOIN decides which rows are NULL extended
+    let mut conditions: Vec<Expr> = selection.map(split_conjuncts).unwrap_or_default();
+    let mut on_filters: Vec<Vec<Expr>> = vec![vec![]; from.len()];
+    for (position, from_table) in from.iter().enumerate() {
+        if let Some(constraint) = &from_table.constraint {
+            let conjuncts = split_conjuncts(constraint);
+            for conjunct in &conjuncts {
+                if scope
+                    .referenced_tables(conjunct)?
+                    .iter()
+                    .any(|i| *i > position)
+                {
+                    return Err(SQLRiteError::General(format!(
+                        "ON clause references tables to its right: {}",
+                        conjunct
+                    )));
+                }
+            }
+            match from_table.join {
+                JoinKind::Left => on_filters[position] = conjuncts,
+                _ => conditions.extend(conjuncts),
+            }
+        }
+    }
+
+    // Conditions reading a LEFT JOINed table must wait for NULL rows to be added
+    let mut pushed: Vec<Expr> = vec![];
+    let mut residual: Vec<Expr> = vec![];
+    for condition in conditions {
+        let tables = scope.referenced_tables(&condition)?;
+        if from.is_empty() || tables.iter().any(|i| left_joined.contains(i)) {
+            residual.push(condition);
+        } else {
+            pushed.push(condition);
+        }
+    }
+
+    let keeps_order = from
+        .iter()
+        .any(|table| matches!(table.join, JoinKind::Left | JoinKind::Cross));
+    let orders = match keeps_order || from.len() > MAX_REORDERED_TABLES {
+        true => vec![(0..from.len()).collect()],
+        false => permutations(from.len()),
+    };
+
+    let mut best: Option<QueryPlan> = None;
+    for order in &orders {
+        for ordered in [false, true] {
+            if ordered && order_by.is_empty() {
+                continue;
+            }
+            let plan =
+                plan_join_order(scope, from, &pushed, &on_filters, order, order_by, ordered)?;
+            if let Some(plan) = plan {
+                let is_better = match &best {
+                    Some(best) => plan.cost < best.cost,
+                    None => true,
+                };
+                if is_better {
+                    best = Some(plan);
+                }
+            }
+        }
+    }
+
+    // The FROM order without ordered scans always gives a plan
+    let mut plan = best.unwrap();
+    plan.residual = residual;
+    Ok(plan)
+}
+
+/// Returns every ordering of the positions `0..n`, starting with `0..n` itself
+fn permutations(n: usize) -> Vec<Vec<usize>> {
+    if n == 0 {
+        return vec![vec![]];
+    }
+    let mut orders: Vec<Vec<usize>> = vec![];
+    for order in permutations(n - 1) {
+        for i in (0..=order.len()).rev() {
+            let mut order = order.to_vec();
+            order.insert(i, n - 1);
+            orders.push(order);
+        }
+    }
+    orders
+}
+
+/// Plans joining the tables in the given order, returning `None` if `ordered` is set
+/// and the outer table can not be read in the order the ORDER BY asks for
+fn plan_join_order(
+    scope: &Scope,
+    from: &[FromTable],
+    conditions: &[Expr],
+    on_filters: &[Vec<Expr>],
+    order: &[usize],
+    order_by: &[OrderByExpr],
+    ordered: bool,
+) -> Result<Option<QueryPlan>> {
+    let mut bound: BTreeSet<usize> = BTreeSet::new();
+    let mut placed = vec![false; conditions.len()];
+    let mut tables: Vec<TablePlan> = vec![];
+    let mut outer_rows = 1.0;
+    let mut cost = 0.0;
+
+    for (level, position) in order.iter().copied().enumerate() {
+        bound.insert(position);
+        // Every condition is checked as soon as all the tables it reads are joined
+        let mut filter = on_filters[position].to_vec();
+        for (i, condition) in conditions.iter().enumerate() {
+            if !placed[i] && scope.referenced_tables(condition)?.is_subset(&bound) {
+                filter.push(condition.clone());
+                placed[i] = true;
+            }
+        }
+
+        let terms = find_terms(scope, position, &bound, &filter)?;
+        let mut candidates = access_candidates(scope, position, &terms, &filter);
+        if ordered && level == 0 {
+            candidates.retain(|candidate| {
+                provides_order(scope, position, &candidate.access, order_by).is_some()
+            });
+        }
+        let best = match candidates.into_iter().reduce(|best, candidate| {
+            match candidate.cost < best.cost {
+                true => candidate,
+                false => best,
+            }
+        }) {
+            Some(best) => best,
+            None => return Ok(None),
+        };
+
+        let table_rows = estimate_table_rows(scope.tables[position].1);
+        let selectivity: f64 = filter
+            .iter()
+            .map(|condition| estimate_selectivity(scope, position, &bound, condition))
+            .product();
+        let mut rows = (table_rows * selectivity).min(best.rows);
+        // A LEFT JOIN returns at least the NULL row
+        if from[position].join == JoinKind::Left {
+            rows = rows.max(1.0);
+        }
+
+        cost += outer_rows * best.cost;
+        outer_rows *= rows;
+        tables.push(TablePlan {
+            table: from[position].name.to_string(),
+            name: from[position].reference_name().to_string(),
+            position,
+            join: from[position].join.clone(),
+            access: best.access,
+            reverse: false,
+            filter,
+            estimated_rows: rows,
+        });
+    }
+
+    let mut sort = !order_by.is_empty();
+    if let Some(outer) = tables.first_mut() {
+        if sort {
+            if let Some(reverse) = provides_order(scope, outer.position, &outer.access, order_by) {
+                outer.reverse = reverse;
+                sort = false;
+            }
+        }
+    }
+    if sort {
+        cost += outer_rows * (outer_rows + 1.0).log2();
+    }
+    Ok(Some(QueryPlan {
+        tables,
+        residual: vec![],
+        sort,
+        cost,
+    }))
+}
+
+/// Splits an expression into the conditions joined by its top level ANDs
+pub fn split_conjuncts(expr: &Expr) -> Vec<Expr> {
+    match expr {
+        Expr::BinaryOp {
+            left,
+            op: BinaryOperator::And,
+            right,
+        } => {
+            let mut conjuncts = split_conjuncts(left);
+            conjuncts.extend(split_conjuncts(right));
+            conjuncts
+        }
+        Expr::Nested(expr) => split_conjuncts(expr),
+        expr => vec![expr.clone()],
+    }
+}
+
+/// Removes table qualifiers from column references, so expressions can be
+/// compared with the ones stored in an index
+pub fn strip_qualifiers(expr: &Expr) -> Expr {
+    let mut expr = expr.clone();
+    crate::sql::executor::expr::walk_expr_mut(&mut expr, &mut |e| {
+        if let Expr::CompoundIdentifier(idents) = e {
+            if let Some(ident) = idents.last() {
+                *e = Expr::Identifier(Ident::new(&ident.value));
+            }
+        }
+    });
+    expr
+}
+
+/// Finds the comparisons that can drive an index or ROWID search of the table at
+/// `position`, given the tables already joined in `bound`
+fn find_terms(
+    scope: &Scope,
+    position: usize,
+    bound: &BTreeSet<usize>,
+    conditions: &[Expr],
+) -> Result<Vec<Term>> {
+    let known = |expr: &Expr| -> Result<bool> {
+        let tables = scope.referenced_tables(expr)?;
+        Ok(!tables.contains(&position) && tables.is_subset(bound))
+    };
+    let reads_table =
+        |expr: &Expr| -> Result<bool> { Ok(scope.referenced_tables(expr)?.contains(&position)) };
+    let mut terms: Vec<Term> = vec![];
+    for condition in conditions {
+        match condition {
+            Expr::BinaryOp { left, op, right } => {
+                let flipped = match op {
+                    BinaryOperator::Eq => BinaryOperator::Eq,
+                    BinaryOperator::Lt => BinaryOperator::Gt,
+                    BinaryOperator::LtEq => BinaryOperator::GtEq,
+                    BinaryOperator::Gt => BinaryOperator::Lt,
+                    BinaryOperator::GtEq => BinaryOperator::LtEq,
+                    _ => continue,
+                };
+                if reads_table(left)? && known(right)? {
+                    terms.push(Term {
+                        lhs: (**left).clone(),
+                        op: op.clone(),
+                        rhs: (**right).clone(),
+                    });
+                } else if reads_table(right)? && known(left)? {
+                    terms.push(Term {
+                        lhs: (**right).clone(),
+                        op: flipped,
+                        rhs: (**left).clone(),
+                    });
+                }
+            }
+            Expr::Between {
+                expr,
+                negated: false,
+                low,
+                high,
+            } if reads_table(expr)? && known(low)? && known(high)? => {
+                terms.push(Term {
+                    lhs: (**expr).clone(),
+                    op: BinaryOperator::GtEq,
+                    rhs: (**low).clone(),
+                });
+                terms.push(Term {
+                    lhs: (**expr).clone(),
+                    op: BinaryOperator::LtEq,
+                    rhs: (**high).clone(),
+                });
+            }
+            _ => (),
+        }
+    }
+    Ok(terms)
+}
+
+/// Returns the lower and upper bounds the terms matching `is_target` put on it
+fn find_bounds(
+    terms: &[Term],
+    is_target: &dyn Fn(&Expr) -> bool,
+) -> (Option<Bound>, Option<Bound>) {
+    let mut lower: Option<Bound> = None;
+    let mut upper: Option<Bound> = None;
+    for term in terms.iter().filter(|term| is_target(&term.lhs)) {
+        let bound = Bound {
+            expr: term.rhs.clone(),
+            inclusive: matches!(term.op, BinaryOperator::GtEq | BinaryOperator::LtEq),
+        };
+        match term.op {
+            BinaryOperator::Gt | BinaryOperator::GtEq if lower.is_none() => lower = Some(bound),
+            BinaryOperator::Lt | BinaryOperator::LtEq if upper.is_none() => upper = Some(bound),
+            _ => (),
+        }
+    }
+    (lower, upper)
+}
+
+/// Returns the indexes of a table that hold every row the query can return:
+/// all the regular indexes, and the partial ones whose WHERE clause is one of the conditions
+fn usable_indexes<'a>(table: &'a Table, conditions: &[Expr]) -> Vec<&'a Index> {
+    let conditions: Vec<Expr> = conditions.iter().map(strip_qualifiers).collect();
+    let mut indexes: Vec<&Index> = table
+        .indexes
+        .values()
+        .filter(|index| match &index.predicate {
+            Some(predicate) => conditions.contains(predicate),
+            None => true,
+        })
+        .collect();
+    indexes.sort_by(|a, b| a.name.cmp(&b.name));
+    indexes
+}
+
+/// Number of rows of a table, as of its last ANALYZE
+fn estimate_table_rows(table: &Table) -> f64 {
+    match &table.stats {
+        Some(stats) => stats.row_count as f64,
+        None => DEFAULT_ROW_COUNT,
+    }
+}
+
+/// Returns the value of an expression that reads no table, evaluated ahead of the query
+fn constant_value(scope: &Scope, expr: &Expr) -> Option<Value> {
+    match scope.referenced_tables(expr) {
+        Ok(tables) if tables.is_empty() => evaluate(expr, &NoRow).ok(),
+        _ => None,
+    }
+}
+
+/// Returns the statistics of the column an expression reads, if it is a plain
+/// column of the table at `position` that was analyzed
+fn column_stats<'a>(scope: &Scope<'a>, position: usize, expr: &Expr) -> Option<&'a ColumnStats> {
+    match scope.resolve_column(expr) {
+        Some((i, column)) if i == position => {
+            let table: &'a Table = scope.tables[position].1;
+            table.stats.as_ref()?.columns.get(&column)
+        }
+        _ => None,
+    }
+}
+
+/// Estimates how many rows of the table at `position` have a value of `expr`
+/// between the bounds
+fn estimate_range(
+    scope: &Scope,
+    position: usize,
+    expr: &Expr,
+    lower: &Option<Bound>,
+    upper: &Option<Bound>,
+    rows: f64,
+) -> f64 {
+    let stats = column_stats(scope, position, expr);
+    let mut estimate = rows;
+    if let Some(lower) = lower {
+        let value = constant_value(scope, &lower.expr);
+        estimate -= match (stats, value) {
+            (Some(stats), Some(value)) => stats.estimate_less(&value, !lower.inclusive),
+            _ => rows * (1.0 - DEFAULT_SELECTIVITY),
+        };
+    }
+    if let Some(upper) = upper {
+        let value = constant_value(scope, &upper.expr);
+        estimate -= match (stats, value) {
+            (Some(stats), Some(value)) => {
+                let non_null: usize = stats.histogram.iter().map(|bucket| bucket.count).sum();
+                non_null as f64 - stats.estimate_less(&value, upper.inclusive)
+            }
+            _ => rows * (1.0 - DEFAULT_SELECTIVITY),
+        };
+    }
+    estimate.max(0.0)
+}
+
+/// Estimates the fraction of the rows of the table at `position` satisfying a condition
+fn estimate_selectivity(
+    scope: &Scope,
+    position: usize,
+    bound: &BTreeSet<usize>,
+    condition: &Expr,
+) -> f64 {
+    let rows = estimate_table_rows(scope.tables[position].1).max(1.0);
+    let terms = match find_terms(scope, position, bound, std::slice::from_ref(condition)) {
+        Ok(terms) if !terms.is_empty() => terms,
+        _ => {
+            return match scope.referenced_tables(condition) {
+                Ok(tables) if tables.is_empty() => 1.0,
+                _ => DEFAULT_SELECTIVITY,
+            }
+        }
+    };
+
+    let term = &terms[0];
+    if term.op == BinaryOperator::Eq {
+        let unique = scope.is_rowid(position, &term.lhs)
+            || scope.tables[position].1.indexes.values().any(|index| {
+                index.is_unique
+                    && index.predicate.is_none()
+                    && index.expressions.len() == 1
+                    && scope.is_key_part(position, &index.expressions[0], &term.lhs)
+            });
+        let equal_rows = match column_stats(scope, position, &term.lhs) {
+            _ if unique => 1.0,
+            Some(stats) => stats.estimate_equal(constant_value(scope, &term.rhs).as_ref()),
+            None => DEFAULT_EQUAL_ROWS,
+        };
+        (equal_rows / rows).min(1.0)
+    } else {
+        let (lower, upper) = find_bounds(&terms, &|_| true);
+        (estimate_range(scope, position, &term.lhs, &lower, &upper, rows) / rows).min(1.0)
+    }
+}
+
+/// Lists the ways the table at `position` can be read, with their estimated cost:
+/// a ROWID lookup, a search or a full scan of each usable index, a ROWID range and
+/// a full scan of the table
+fn access_candidates(
+    scope: &Scope,
+    position: usize,
+    terms: &[Term],
+    conditions: &[Expr],
+) -> Vec<Candidate> {
+    let table = scope.tables[position].1;
+    let rows = estimate_table_rows(table);
+    // Cost of walking down a B-tree to the first key searched
+    let seek = (rows + 1.0).log2();
+    let is_rowid = |expr: &Expr| scope.is_rowid(position, expr);
+    let mut candidates: Vec<Candidate> = vec![];
+
+    if let Some(term) = terms
+        .iter()
+        .find(|term| term.op == BinaryOperator::Eq && is_rowid(&term.lhs))
+    {
+        candidates.push(Candidate {
+            access: AccessPath::RowidLookup(term.rhs.clone()),
+            rows: 1.0,
+            cost: seek,
+        });
+    }
+
+    for index in usable_indexes(table, conditions) {
+        let mut prefix: Vec<Expr> = vec![];
+        let mut prefix_terms: Vec<&Term> = vec![];
+        for key_part in &index.expressions {
+            match terms.iter().find(|term| {
+                term.op == BinaryOperator::Eq && scope.is_key_part(position, key_part, &term.lhs)
+            }) {
+                Some(term) => {
+                    prefix.push(term.rhs.clone());
+                    prefix_terms.push(term);
+                }
+                None => break,
+            }
+        }
+        let (lower, upper) = match index.expressions.get(prefix.len()) {
+            Some(key_part) => {
+                find_bounds(terms, &|expr| scope.is_key_part(position, key_part, expr))
+            }
+            None => (None, None),
+        };
+
+        let index_stats = table
+            .stats
+            .as_ref()
+            .and_then(|stats| stats.indexes.get(&index.name));
+        let mut index_rows = match (index_stats, &index.predicate) {
+            (Some(stats), _) => stats.row_count as f64,
+            (None, Some(_)) => rows * DEFAULT_SELECTIVITY,
+            (None, None) => rows,
+        };
+        if index.is_unique && prefix.len() == index.expressions.len() {
+            index_rows = 1.0;
+        } else if prefix.len() == 1 && column_stats(scope, position, &prefix_terms[0].lhs).is_some()
+        {
+            let stats = column_stats(scope, position, &prefix_terms[0].lhs).unwrap();
+            index_rows = stats.estimate_equal(constant_value(scope, &prefix[0]).as_ref());
+        } else if !prefix.is_empty() {
+            index_rows = match index_stats {
+                Some(stats) => stats.estimate_equal(prefix.len()),
+                None => (DEFAULT_EQUAL_ROWS / prefix.len() as f64).max(1.0),
+            };
+        }
+        if lower.is_some() || upper.is_some() {
+            let key_part = &index.expressions[prefix.len()];
+            let fraction = match rows > 0.0 {
+                true => estimate_range(scope, position, key_part, &lower, &upper, rows) / rows,
+                false => 0.0,
+            };
+            index_rows *= fraction;
+        }
+
+        let is_search = !prefix.is_empty() || lower.is_some() || upper.is_some();
+        candidates.push(Candidate {
+            access: AccessPath::IndexScan {
+                index: index.name.to_string(),
+                prefix,
+                lower,
+                upper,
+            },
+            rows: index_rows,
+            // Reading a row through an index costs a bit more than reading it in place
+            cost: match is_search {
+                true => seek + index_rows * 1.5,
+                false => index_rows * 1.5,
+            },
+        });
+    }
+
+    let (lower, upper) = find_bounds(terms, &is_rowid);
+    if lower.is_some() || upper.is_some() {
+        let range_rows = rows
+            * [&lower, &upper]
+                .iter()
+                .filter(|bound| bound.is_some())
+                .map(|_| DEFAULT_SELECTIVITY)
+                .product::<f64>();
+        candidates.push(Candidate {
+            access: AccessPath::RowidRange { lower, upper },
+            rows: range_rows,
+            cost: seek + range_rows,
+        });
+    }
+
+    candidates.push(Candidate {
+        access: AccessPath::FullScan,
+        rows,
+        cost: rows,
+    });
+    candidates
+}
+
+/// Checks if reading the table at `position` through `access` returns rows in the order
+/// the ORDER BY asks for. Returns whether the rows must be read backwards, or `None` if
+/// the rows need sorting.
+fn provides_order(
+    scope: &Scope,
+    position: usize,
+    access: &AccessPath,
+    order_by: &[OrderByExpr],
+) -> Option<bool> {
+    // Rows of the inner tables of a join come in no particular order
+    for term in order_by {
+        if scope.referenced_tables(&term.expr).ok()? != BTreeSet::from([position]) {
+            return None;
+        }
+    }
+    // Expressions the rows are ordered by, and the ones that are constant across all rows
+    let (ordering, constants): (Vec<Expr>, Vec<Expr>) = match access {
+        AccessPath::RowidLookup(_) => return Some(false),
+        AccessPath::FullScan | AccessPath::RowidRange { .. } => (vec![], vec![]),
+        AccessPath::IndexScan { index, prefix, .. } => {
+            let index = scope.tables[position].1.indexes.get(index)?;
+            if index.is_unique && prefix.len() == index.expressions.len() {
+                return Some(false);
+            }
+            (
+                index.expressions[prefix.len()..].to_vec(),
+                index.expressions[..prefix.len()].to_vec(),
+            )
+        }
+    };
+
+    let mut next = 0;
+    let mut reverse: Option<bool> = None;
+    for term in order_by {
+        if constants
+            .iter()
+            .any(|key_part| scope.is_key_part(position, key_part, &term.expr))
+        {
+            continue;
+        }
+        let matches_next = match ordering.get(next) {
+            Some(key_part) => scope.is_key_part(position, key_part, &term.expr),
+            // Rows sharing the same key are read in ROWID order
+            None => scope.is_rowid(position, &term.expr),
+        };
+        if !matches_next {
+            return None;
+        }
+        // NULLs come first when reading forwards and last when reading backwards
+        let descending = term.asc == Some(false);
+        if term.nulls_first == Some(descending) {
+            return None;
+        }
+        if *reverse.get_or_insert(descending) != descending {
+            return None;
+        }
+        // The ROWID is unique, the terms after it never decide the order
+        if next == ordering.len() {
+            break;
+        }
+        next += 1;
+    }
+    Some(reverse.unwrap_or(false))
+}
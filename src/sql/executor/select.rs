@@ -0,0 +1,794 @@
+use prettytable::{Cell as PrintCell, Row as PrintRow, Table as PrintTable};
+use sqlparser::ast::{
+    Expr, Function, FunctionArg, FunctionArgExpr, OrderByExpr, SelectItem, Value as SqlValue,
+};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::error::{Result, SQLRiteError};
+use crate::sql::db::database::Database;
+use crate::sql::db::table::Table;
+use crate::sql::db::value::Value;
+use crate::sql::executor::expr::{call_aggregate, evaluate, is_truthy, NoRow, RowSource};
+use crate::sql::executor::planner::{plan_query, AccessPath, Bound, QueryPlan, Scope};
+use crate::sql::parser::select::{FromTable, JoinKind, SelectQuery};
+
+/// The rows returned by a query, together with the names of their columns
+#[derive(Debug, PartialEq)]
+pub struct ResultSet {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<Value>>,
+}
+
+impl ResultSet {
+    /// Print the result set to standard output in a pretty formatted way,
+    /// the same way `Table::print_table_data` prints a table
+    pub fn print(&self) {
+        let mut print_table = PrintTable::new();
+        print_table.add_row(PrintRow::new(
+            self.columns
+                .iter()
+                .map(|col| PrintCell::new(col))
+                .collect::<Vec<PrintCell>>(),
+        ));
+        for row in &self.rows {
+            print_table.add_row(PrintRow::new(
+                row.iter()
+                    .map(|val| PrintCell::new(&val.to_string()))
+                    .collect::<Vec<PrintCell>>(),
+            ));
+        }
+        print_table.printstd();
+    }
+}
+
+/// `RowSource` reading a row of the joined FROM tables, each given by its ROWID.
+/// Tables not joined yet can not be read, and a `None` ROWID stands for the
+/// all NULL row a LEFT JOIN adds when nothing matches.
+pub struct JoinedRow<'a> {
+    pub scope: &'a Scope<'a>,
+    pub rowids: &'a [Option<i64>],
+}
+
+impl<'a> RowSource for JoinedRow<'a> {
+    fn get_column(&self, table: Option<&str>, column: &str) -> Result<Value> {
+        let i = self.scope.resolve(table, column)?;
+        match self.rowids.get(i) {
+            Some(Some(rowid)) => {
+                let table = self.scope.tables[i].1;
+                if table.contains_column(column.to_string()) {
+                    Ok(table.get_value(column, *rowid))
+                } else {
+                    Ok(Value::Integer(*rowid))
+                }
+            }
+            Some(None) => Ok(Value::Null),
+            None => Err(SQLRiteError::Internal(format!(
+                "column {} read before its table is joined",
+                column
+            ))),
+        }
+    }
+}
+
+/// `RowSource` reading a group of rows of an aggregate query, each row given by the ROWIDs
+/// of the joined tables. Aggregate functions are computed over every row of the group, and
+/// columns read its last row, or NULL for the group without rows of a query without GROUP BY.
+pub struct GroupRow<'a> {
+    pub scope: &'a Scope<'a>,
+    pub rows: &'a [Vec<Option<i64>>],
+}
+
+impl<'a> RowSource for GroupRow<'a> {
+    fn get_column(&self, table: Option<&str>, column: &str) -> Result<Value> {
+        let no_row = vec![None; self.scope.tables.len()];
+        let row = JoinedRow {
+            scope: self.scope,
+            rowids: self.rows.last().unwrap_or(&no_row),
+        };
+        row.get_column(table, column)
+    }
+
+    fn get_aggregate(&self, function: &Function) -> Result<Value> {
+        let name = function.name.to_string().to_lowercase();
+        // count(*) counts the rows themselves, which is counting a value that is never NULL
+        let (arg, separator) = match (name.as_ref(), function.args.as_slice()) {
+            ("count", [FunctionArg::Unnamed(FunctionArgExpr::Wildcard)]) => (None, None),
+            (_, [FunctionArg::Unnamed(FunctionArgExpr::Expr(arg))]) => (Some(arg), None),
+            (
+                "group_concat",
+                [FunctionArg::Unnamed(FunctionArgExpr::Expr(arg)), FunctionArg::Unnamed(FunctionArgExpr::Expr(separator))],
+            ) => (Some(arg), Some(separator)),
+            _ => {
+                return Err(SQLRiteError::General(format!(
+                    "wrong number of arguments to function {}()",
+                    name
+                )))
+            }
+        };
+
+        let mut values: Vec<Value> = vec![];
+        for rowids in self.rows {
+            let row = JoinedRow {
+                scope: self.scope,
+                rowids,
+            };
+            values.push(match arg {
+                Some(arg) => evaluate(arg, &row)?,
+                None => Value::Integer(1),
+            });
+        }
+        if function.distinct {
+            let mut seen: BTreeSet<Value> = BTreeSet::new();
+            values.retain(|value| seen.insert(value.clone()));
+        }
+        let separator = match separator {
+            Some(separator) => Some(evaluate(separator, self)?.to_string()),
+            None => None,
+        };
+        call_aggregate(&name, &values, separator.as_deref())
+    }
+}
+
+/// A column of the result set: its name and the expression computing it
+pub struct OutputColumn {
+    pub name: String,
+    pub expr: Expr,
+}
+
+/// A SELECT query planned against the database, ready to be executed or explained
+pub struct PreparedSelect<'a> {
+    /// Tables of the FROM clause
+    pub scope: Scope<'a>,
+    /// Plan the executor follows
+    pub plan: QueryPlan,
+    /// Columns of the result set, with wildcards expanded
+    pub columns: Vec<OutputColumn>,
+    /// ORDER BY terms, with aliases and positions replaced by the expressions they stand for
+    pub order_by: Vec<OrderByExpr>,
+    /// GROUP BY terms, with aliases and positions replaced by the expressions they stand for
+    pub group_by: Vec<Expr>,
+    /// Value representing if the query computes aggregates over groups of rows
+    pub aggregate: bool,
+}
+
+/// Builds a table holding the rows of every view read by a FROM clause, which the
+/// query reads in place of the view. Views are expanded this way every time they are read,
+/// except materialized views which already store their rows.
+pub fn materialize_views(from: &[FromTable], db: &Database) -> Result<Vec<Table>> {
+    let mut views: Vec<Table> = vec![];
+    for from_table in from {
+        if let Some(view) = db.views.get(&from_table.name) {
+            if view.rows.is_none() && !views.iter().any(|table| table.tb_name == view.name) {
+                views.push(view.materialize(db)?);
+            }
+        }
+    }
+    Ok(views)
+}
+
+/// Plans a SELECT query against the database, the views it reads from
+/// being given by `materialize_views`. A `plan` made beforehand is used as it is,
+/// see `plan_select`.
+pub fn prepare_select<'a>(
+    query: &SelectQuery,
+    db: &'a Database,
+    views: &'a [Table],
+    plan: Option<QueryPlan>,
+) -> Result<PreparedSelect<'a>> {
+    let scope = Scope::new(db, &query.from, views)?;
+    let columns = expand_projection(&query.projection, &scope)?;
+    let mut order_by: Vec<OrderByExpr> = vec![];
+    for (i, term) in query.order_by.iter().enumerate() {
+        order_by.push(OrderByExpr {
+            expr: resolve_term(&term.expr, i, "ORDER BY", &query.projection, &columns)?,
+            asc: term.asc,
+            nulls_first: term.nulls_first,
+        });
+    }
+    let mut group_by: Vec<Expr> = vec![];
+    for (i, term) in query.group_by.iter().enumerate() {
+        let expr = resolve_term(term, i, "GROUP BY", &query.projection, &columns)?;
+        scope.referenced_tables(&expr)?;
+        group_by.push(expr);
+    }
+    if let Some(having) = &query.having {
+        scope.referenced_tables(having)?;
+    }
+
+    let aggregate = query.is_aggregate();
+    let plan = match (plan, aggregate) {
+        (Some(plan), _) => plan,
+        // The ORDER BY of an aggregate query sorts the groups, not the rows they are made of
+        (None, true) => {
+            let mut plan = plan_query(&scope, &query.from, query.selection.as_ref(), &[])?;
+            plan.sort = !order_by.is_empty();
+            plan
+        }
+        (None, false) => plan_query(&scope, &query.from, query.selection.as_ref(), &order_by)?,
+    };
+    Ok(PreparedSelect {
+        scope,
+        plan,
+        columns,
+        order_by,
+        group_by,
+        aggregate,
+    })
+}
+
+/// Plans reading the rows of a single table selected by a WHERE clause,
+/// as UPDATE and DELETE statements do. A `plan` made beforehand is used as it is.
+pub fn plan_table_rows<'a>(
+    db: &'a Database,
+    table_name: &str,
+    selection: Option<&Expr>,
+    plan: Option<QueryPlan>,
+) -> Result<(Scope<'a>, QueryPlan)> {
+    if db.contains_view(table_name) {
+        return Err(SQLRiteError::General(format!(
+            "cannot modify {} because it is a view",
+            table_name
+        )));
+    }
+    let from = [FromTable {
+        name: table_name.to_string(),
+        alias: None,
+        join: JoinKind::From,
+        constraint: None,
+    }];
+    let scope = Scope::new(db, &from, &[])?;
+    let plan = match plan {
+        Some(plan) => plan,
+        None => plan_query(&scope, &from, selection, &[])?,
+    };
+    Ok((scope, plan))
+}
+
+/// Returns the ROWIDs of the rows of a table selected by a WHERE clause, using
+/// the same access paths as SELECT queries, or following `plan` if there is one
+pub fn find_rowids(
+    db: &Database,
+    table_name: &str,
+    selection: Option<&Expr>,
+    plan: Option<QueryPlan>,
+) -> Result<Vec<i64>> {
+    let (scope, plan) = plan_table_rows(db, table_name, selection, plan)?;
+    let mut found: Vec<i64> = vec![];
+    let mut emit = |rowids: &[Option<i64>]| -> Result<bool> {
+        let row = JoinedRow {
+            scope: &scope,
+            rowids,
+        };
+        for condition in &plan.residual {
+            if is_truthy(&evaluate(condition, &row)?) != Some(true) {
+                return Ok(true);
+            }
+        }
+        found.extend(rowids.iter().flatten());
+        Ok(true)
+    };
+    join_level(&plan, &scope, 0, &mut [None], &mut emit)?;
+    Ok(found)
+}
+
+/// Computes the RETURNING clause of an INSERT, UPDATE or DELETE statement for the given rows
+/// of the table the statement changed, the way a SELECT list over that table is computed
+pub fn execute_returning(
+    returning: &[SelectItem],
+    db: &Database,
+    table_name: &str,
+    rowids: &[i64],
+) -> Result<ResultSet> {
+    let (scope, _) = plan_table_rows(db, table_name, None, None)?;
+    let columns = expand_projection(returning, &scope)?;
+    let mut rows: Vec<Vec<Value>> = vec![];
+    for rowid in rowids {
+        let row = JoinedRow {
+            scope: &scope,
+            rowids: &[Some(*rowid)],
+        };
+        let mut values: Vec<Value> = vec![];
+        for column in &columns {
+            values.push(evaluate(&column.expr, &row)?);
+        }
+        rows.push(values);
+    }
+    Ok(ResultSet {
+        columns: columns.into_iter().map(|col| col.name).collect(),
+        rows,
+    })
+}
+
+/// Plans a SELECT query against the database, for the plan to be given to
+/// `execute_planned_select` as long as the schema of the tables it reads stays the same
+pub fn plan_select(query: &SelectQuery, db: &Database) -> Result<QueryPlan> {
+    let views = materialize_views(&query.from, db)?;
+    Ok(prepare_select(query, db, &views, None)?.plan)
+}
+
+/// Runs a SELECT query against the database
+pub fn execute_select(query: &SelectQuery, db: &Database) -> Result<ResultSet> {
+    execute_planned_select(query, db, None)
+}
+
+/// Runs a SELECT query against the database, following the plan given by `plan_select`
+/// if there is one instead of planning the query again
+pub fn execute_planned_select(
+    query: &SelectQuery,
+    db: &Database,
+    plan: Option<QueryPlan>,
+) -> Result<ResultSet> {
+    let views = materialize_views(&query.from, db)?;
+    let PreparedSelect {
+        scope,
+        plan,
+        columns,
+        order_by,
+        group_by,
+        aggregate,
+    } = prepare_select(query, db, &views, plan)?;
+
+    let limit = match &query.limit {
+        Some(expr) => to_count(evaluate(expr, &NoRow)?, "LIMIT")?,
+        None => None,
+    };
+    let offset = match &query.offset {
+        Some(expr) => to_count(evaluate(expr, &NoRow)?, "OFFSET")?.unwrap_or(0),
+        None => 0,
+    };
+    // Without sorting or DISTINCT the rows come out in their final order,
+    // so reading can stop as soon as enough of them were found
+    let wanted = match (plan.sort || query.distinct, limit) {
+        (false, Some(limit)) => Some(offset + limit),
+        _ => None,
+    };
+
+    let mut rows: Vec<(Vec<Value>, Vec<Value>)> = vec![];
+    let mut seen: BTreeSet<Vec<Value>> = BTreeSet::new();
+    // Computes a row of the result, returning false once no more rows are wanted
+    let mut output = |row: &dyn RowSource| -> Result<bool> {
+        let mut values: Vec<Value> = vec![];
+        for column in &columns {
+            values.push(evaluate(&column.expr, row)?);
+        }
+        if query.distinct && !seen.insert(values.clone()) {
+            return Ok(true);
+        }
+        let mut sort_key: Vec<Value> = vec![];
+        if plan.sort {
+            for term in &order_by {
+                sort_key.push(evaluate(&term.expr, row)?);
+            }
+        }
+        rows.push((values, sort_key));
+        Ok(match wanted {
+            Some(wanted) => rows.len() < wanted,
+            None => true,
+        })
+    };
+
+    match aggregate {
+        false => {
+            let mut emit = |rowids: &[Option<i64>]| -> Result<bool> {
+                let row = JoinedRow {
+                    scope: &scope,
+                    rowids,
+                };
+                for condition in &plan.residual {
+                    if is_truthy(&evaluate(condition, &row)?) != Some(true) {
+                        return Ok(true);
+                    }
+                }
+                output(&row)
+            };
+            scan_rows(&plan, &scope, &mut emit)?;
+        }
+        true => {
+            // Groups come out in the order of their GROUP BY values, like in SQLite.
+            // Without GROUP BY all the rows make a single group, even when there are none.
+            let mut groups: BTreeMap<Vec<Value>, Vec<Vec<Option<i64>>>> = BTreeMap::new();
+            if group_by.is_empty() {
+                groups.insert(vec![], vec![]);
+            }
+            let mut emit = |rowids: &[Option<i64>]| -> Result<bool> {
+                let row = JoinedRow {
+                    scope: &scope,
+                    rowids,
+                };
+                for condition in &plan.residual {
+                    if is_truthy(&evaluate(condition, &row)?) != Some(true) {
+                        return Ok(true);
+                    }
+                }
+                let mut key: Vec<Value> = vec![];
+                for term in &group_by {
+                    key.push(evaluate(term, &row)?);
+                }
+                groups.entry(key).or_default().push(rowids.to_vec());
+                Ok(true)
+            };
+            scan_rows(&plan, &scope, &mut emit)?;
+
+            for group in groups.values() {
+                let row = GroupRow {
+                    scope: &scope,
+                    rows: group,
+                };
+                if let Some(having) = &query.having {
+                    if is_truthy(&evaluate(having, &row)?) != Some(true) {
+                        continue;
+                    }
+                }
+                if !output(&row)? {
+                    break;
+                }
+            }
+        }
+    }
+
+    if plan.sort {
+        rows.sort_by(|(_, a), (_, b)| compare_sort_keys(a, b, &order_by));
+    }
+    let rows = rows
+        .into_iter()
+        .map(|(values, _)| values)
+        .skip(offset)
+        .take(limit.unwrap_or(usize::MAX))
+        .collect();
+
+    Ok(ResultSet {
+        columns: columns.into_iter().map(|col| col.name).collect(),
+        rows,
+    })
+}
+
+/// Passes every row of the joined FROM tables to `emit`, or a single row without
+/// any table for a SELECT without FROM
+fn scan_rows(
+    plan: &QueryPlan,
+    scope: &Scope,
+    emit: &mut dyn FnMut(&[Option<i64>]) -> Result<bool>,
+) -> Result<()> {
+    if scope.tables.is_empty() {
+        emit(&[])?;
+    } else {
+        let mut rowids: Vec<Option<i64>> = vec![None; scope.tables.len()];
+        join_level(plan, scope, 0, &mut rowids, emit)?;
+    }
+    Ok(())
+}
+
+/// Reads the rows of the table joined at `level` matching the rows already joined in
+/// `rowids`, recursing into the next table for every match. `rowids` holds the ROWID
+/// of every table of the scope, `None` for tables not joined yet or NULL extended. Complete rows are passed to `emit`,
+/// which returns false once no more rows are wanted.
+fn join_level(
+    plan: &QueryPlan,
+    scope: &Scope,
+    level: usize,
+    rowids: &mut [Option<i64>],
+    emit: &mut dyn FnMut(&[Option<i64>]) -> Result<bool>,
+) -> Result<bool> {
+    if level == plan.tables.len() {
+        return emit(rowids);
+    }
+    let table_plan = &plan.tables[level];
+    let position = table_plan.position;
+    let table = scope.tables[position].1;
+
+    let candidates = {
+        let row = JoinedRow { scope, rowids };
+        let mut candidates = read_access_path(table, &table_plan.access, &row)?;
+        if table_plan.reverse {
+            candidates.reverse();
+        }
+        candidates
+    };
+
+    let mut matched = false;
+    for rowid in candidates {
+        rowids[position] = Some(rowid);
+        let mut keep = true;
+        for condition in &table_plan.filter {
+            let row = JoinedRow { scope, rowids };
+            if is_truthy(&evaluate(condition, &row)?) != Some(true) {
+                keep = false;
+                break;
+            }
+        }
+        let wants_more = !keep || join_level(plan, scope, level + 1, rowids, emit)?;
+        rowids[position] = None;
+        matched |= keep;
+        if !wants_more {
+            return Ok(false);
+        }
+    }
+
+    // A LEFT JOIN keeps the rows that matched nothing, with NULLs for this table
+    if !matched && table_plan.join == JoinKind::Left {
+        return join_level(plan, scope, level + 1, rowids, emit);
+    }
+    Ok(true)
+}
+
+/// Returns the ROWIDs an access path reads, in the order it reads them
+fn read_access_path(table: &Table, access: &AccessPath, row: &JoinedRow) -> Result<Vec<i64>> {
+    match access {
+        AccessPath::FullScan => Ok(table.rowids()),
+        AccessPath::RowidLookup(expr) => match rowid_equal_to(&evaluate(expr, row)?) {
+            Some(rowid) if table.contains_rowid(rowid) => Ok(vec![rowid]),
+            _ => Ok(vec![]),
+        },
+        AccessPath::RowidRange { lower, upper } => {
+            let lower = evaluate_bound(lower, row)?;
+            let upper = evaluate_bound(upper, row)?;
+            match (first_rowid(&lower), last_rowid(&upper)) {
+                (Some(first), Some(last)) if first <= last => {
+                    Ok(table.stored_rowids.range(first..=last).copied().collect())
+                }
+                _ => Ok(vec![]),
+            }
+        }
+        AccessPath::IndexScan {
+            index,
+            prefix,
+            lower,
+            upper,
+        } => {
+            let index = table
+                .indexes
+                .get(index)
+                .ok_or_else(|| SQLRiteError::Internal(format!("index {} is missing", index)))?;
+            let mut key: Vec<Value> = vec![];
+            for expr in prefix {
+                key.push(evaluate(expr, row)?);
+            }
+            // NULL is never equal to anything
+            if key.iter().any(|val| val.is_null()) {
+                return Ok(vec![]);
+            }
+            let lower = evaluate_bound(lower, row)?;
+            let upper = evaluate_bound(upper, row)?;
+            let has_range = lower.is_some() || upper.is_some();
+
+            let mut start = key.to_vec();
+            if let Some((Some(val), _)) = &lower {
+                start.push(val.clone());
+            }
+            let mut rowids: Vec<i64> = vec![];
+            for (entry_key, entry_rowids) in index.entries.range(start..) {
+                if !entry_key.starts_with(&key) {
+                    break;
+                }
+                if has_range {
+                    let val = &entry_key[key.len()];
+                    if !within_bounds(val, &lower, &Option::None) {
+                        continue;
+                    }
+                    if !within_bounds(val, &Option::None, &upper) {
+                        break;
+                    }
+                }
+                rowids.extend(entry_rowids.iter());
+            }
+            Ok(rowids)
+        }
+    }
+}
+
+/// Evaluates the expression of a range bound. `Some((None, _))` stands for a NULL
+/// bound, which no value satisfies.
+fn evaluate_bound(bound: &Option<Bound>, row: &JoinedRow) -> Result<Option<(Option<Value>, bool)>> {
+    match bound {
+        Some(bound) => {
+            let val = evaluate(&bound.expr, row)?;
+            Ok(Some((
+                if val.is_null() { None } else { Some(val) },
+                bound.inclusive,
+            )))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Returns the ROWID a value is equal to, the way values compare, `None` if there is none:
+/// whole REAL values are equal to an INTEGER, TEXT and BLOB values never are
+fn rowid_equal_to(val: &Value) -> Option<i64> {
+    match val {
+        Value::Integer(i) => Some(*i),
+        Value::Bool(b) => Some(*b as i64),
+        Value::Real(f) if f.fract() == 0.0 && (i64::MIN as f64..i64::MAX as f64).contains(f) => {
+            Some(*f as i64)
+        }
+        _ => None,
+    }
+}
+
+/// Returns the smallest ROWID above an evaluated lower bound, `None` if there is none
+fn first_rowid(lower: &Option<(Option<Value>, bool)>) -> Option<i64> {
+    let (val, inclusive) = match lower {
+        None => return Some(i64::MIN),
+        Some((None, _)) => return None,
+        Some((Some(val), inclusive)) => (val, *inclusive),
+    };
+    let first = match val {
+        Value::Real(f) if f.is_nan() || *f >= i64::MAX as f64 => return None,
+        Value::Real(f) if *f < i64::MIN as f64 => return Some(i64::MIN),
+        // A ROWID is above a fraction it is the ceiling of, whether the bound is inclusive or not
+        Value::Real(f) if f.fract() != 0.0 => return Some(f.ceil() as i64),
+        // TEXT and BLOB values sort after every number
+        Value::Text(_) | Value::Blob(_) => return None,
+        val => rowid_equal_to(val)?,
+    };
+    match inclusive {
+        true => Some(first),
+        false => first.checked_add(1),
+    }
+}
+
+/// Returns the largest ROWID below an evaluated upper bound, `None` if there is none
+fn last_rowid(upper: &Option<(Option<Value>, bool)>) -> Option<i64> {
+    let (val, inclusive) = match upper {
+        None => return Some(i64::MAX),
+        Some((None, _)) => return None,
+        Some((Some(val), inclusive)) => (val, *inclusive),
+    };
+    let last = match val {
+        Value::Real(f) if f.is_nan() || *f < i64::MIN as f64 => return None,
+        Value::Real(f) if *f >= i64::MAX as f64 => return Some(i64::MAX),
+        Value::Real(f) if f.fract() != 0.0 => return Some(f.floor() as i64),
+        Value::Text(_) | Value::Blob(_) => return Some(i64::MAX),
+        val => rowid_equal_to(val)?,
+    };
+    match inclusive {
+        true => Some(last),
+        false => last.checked_sub(1),
+    }
+}
+
+/// Returns true if a value is between the evaluated bounds. NULL is never within bounds.
+fn within_bounds(
+    val: &Value,
+    lower: &Option<(Option<Value>, bool)>,
+    upper: &Option<(Option<Value>, bool)>,
+) -> bool {
+    if val.is_null() {
+        return false;
+    }
+    let check = |bound: &Option<(Option<Value>, bool)>, wanted: Ordering| match bound {
+        Some((Some(bound), inclusive)) => {
+            let ordering = val.cmp(bound);
+            ordering == wanted || (*inclusive && ordering == Ordering::Equal)
+        }
+        Some((None, _)) => false,
+        None => true,
+    };
+    check(lower, Ordering::Greater) && check(upper, Ordering::Less)
+}
+
+/// Turns the value of a LIMIT or OFFSET clause into a count, `None` meaning no limit
+fn to_count(val: Value, clause: &str) -> Result<Option<usize>> {
+    match val {
+        Value::Integer(i) if i < 0 => Ok(None),
+        Value::Integer(i) => Ok(Some(i as usize)),
+        _ => Err(SQLRiteError::General(format!(
+            "datatype mismatch in {} clause",
+            clause
+        ))),
+    }
+}
+
+/// Turns the SELECT list into the output columns, expanding `*` and `table.*`
+fn expand_projection(projection: &[SelectItem], scope: &Scope) -> Result<Vec<OutputColumn>> {
+    let table_columns = |i: usize| -> Vec<OutputColumn> {
+        let (name, table) = &scope.tables[i];
+        table
+            .columns
+            .iter()
+            .map(|col| OutputColumn {
+                name: col.column_name.to_string(),
+                expr: Expr::CompoundIdentifier(vec![
+                    sqlparser::ast::Ident::new(name),
+                    sqlparser::ast::Ident::new(&col.column_name),
+                ]),
+            })
+            .collect()
+    };
+
+    let mut columns: Vec<OutputColumn> = vec![];
+    for item in projection {
+        match item {
+            SelectItem::UnnamedExpr(expr) => {
+                scope.referenced_tables(expr)?;
+                let name = match expr {
+                    Expr::Identifier(ident) => ident.value.to_string(),
+                    Expr::CompoundIdentifier(idents) => idents.last().unwrap().value.to_string(),
+                    expr => expr.to_string(),
+                };
+                columns.push(OutputColumn {
+                    name,
+                    expr: expr.clone(),
+                });
+            }
+            SelectItem::ExprWithAlias { expr, alias } => {
+                scope.referenced_tables(expr)?;
+                columns.push(OutputColumn {
+                    name: alias.value.to_string(),
+                    expr: expr.clone(),
+                });
+            }
+            SelectItem::Wildcard => {
+                if scope.tables.is_empty() {
+                    return Err(SQLRiteError::General("no tables specified".to_string()));
+                }
+                for i in 0..scope.tables.len() {
+                    columns.extend(table_columns(i));
+                }
+            }
+            SelectItem::QualifiedWildcard(name) => {
+                let name = name.to_string();
+                match scope.tables.iter().position(|(table, _)| *table == name) {
+                    Some(i) => columns.extend(table_columns(i)),
+                    None => return Err(SQLRiteError::General(format!("no such table: {}", name))),
+                }
+            }
+        }
+    }
+    Ok(columns)
+}
+
+/// Replaces an ORDER BY or GROUP BY term given as a position in the SELECT list (`ORDER BY 2`)
+/// or as the alias of a selected expression by the expression it stands for. `i` is the
+/// position of the term in its clause.
+fn resolve_term(
+    term: &Expr,
+    i: usize,
+    clause: &str,
+    projection: &[SelectItem],
+    columns: &[OutputColumn],
+) -> Result<Expr> {
+    match term {
+        Expr::Value(SqlValue::Number(n, _)) => match n.parse::<usize>() {
+            Ok(n) if n >= 1 && n <= columns.len() => Ok(columns[n - 1].expr.clone()),
+            _ => Err(SQLRiteError::General(format!(
+                "{} {} term out of range - should be between 1 and {}",
+                i + 1,
+                clause,
+                columns.len()
+            ))),
+        },
+        Expr::Identifier(ident) => Ok(projection
+            .iter()
+            .find_map(|item| match item {
+                SelectItem::ExprWithAlias { expr, alias } if alias.value == ident.value => {
+                    Some(expr.clone())
+                }
+                _ => None,
+            })
+            .unwrap_or_else(|| term.clone())),
+        expr => Ok(expr.clone()),
+    }
+}
+
+/// Compares the ORDER BY values of two rows. NULLs come first in ascending
+/// order and last in descending order, unless NULLS FIRST or NULLS LAST says otherwise.
+fn compare_sort_keys(a: &[Value], b: &[Value], order_by: &[OrderByExpr]) -> Ordering {
+    for ((a, b), term) in a.iter().zip(b.iter()).zip(order_by.iter()) {
+        let descending = term.asc == Some(false);
+        let nulls_first = term.nulls_first.unwrap_or(!descending);
+        let ordering = match (a.is_null(), b.is_null()) {
+            (true, true) => Ordering::Equal,
+            (true, false) if nulls_first => Ordering::Less,
+            (true, false) => Ordering::Greater,
+            (false, true) if nulls_first => Ordering::Greater,
+            (false, true) => Ordering::Less,
+            (false, false) if descending => b.cmp(a),
+            (false, false) => a.cmp(b),
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    Ordering::Equal
+}
@@ -0,0 +1,463 @@
+use sqlparser::ast::{Expr, Statement, Value as SqlValue};
+use sqlparser::dialect::SQLiteDialect;
+use sqlparser::parser::{Parser, ParserError};
+use sqlparser::tokenizer::{Token, Tokenizer};
+
+use crate::error::{RaiseAction, Result, SQLRiteError};
+use crate::sql::db::database::Database;
+use crate::sql::db::table::{encode_blob, Table};
+use crate::sql::db::trigger::Trigger;
+use crate::sql::db::value::Value;
+use crate::sql::executor::delete::execute_delete;
+use crate::sql::executor::expr::{evaluate, is_rowid_alias, is_truthy, RowSource};
+use crate::sql::executor::insert::execute_insert;
+use crate::sql::executor::planner::Scope;
+use crate::sql::executor::select::{execute_select, JoinedRow};
+use crate::sql::executor::update::execute_update;
+use crate::sql::parser::delete::DeleteQuery;
+use crate::sql::parser::insert::{split_upsert, InsertQuery};
+use crate::sql::parser::select::{FromTable, JoinKind, SelectQuery};
+use crate::sql::parser::trigger::{tokens_to_string, TriggerEvent, TriggerTiming};
+use crate::sql::parser::update::UpdateQuery;
+
+/// A row as triggers see it through `NEW` or `OLD`: the value of every column,
+/// and the ROWID for the rows of a table
+#[derive(Debug, Clone)]
+pub struct TriggerRow {
+    pub rowid: Option<i64>,
+    pub values: Vec<(String, Value)>,
+}
+
+impl TriggerRow {
+    /// Reads a row stored in a table
+    pub fn read(table: &Table, rowid: i64) -> TriggerRow {
+        TriggerRow {
+            rowid: Some(rowid),
+            values: table
+                .columns
+                .iter()
+                .map(|col| {
+                    (
+                        col.column_name.to_string(),
+                        table.get_value(&col.column_name, rowid),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    /// Returns the value of a column, `None` if the row has no such column
+    fn get(&self, column: &str) -> Option<Value> {
+        match self.values.iter().find(|(name, _)| name == column) {
+            Some((_, value)) => Some(value.clone()),
+            None if is_rowid_alias(column) => self.rowid.map(Value::Integer),
+            None => None,
+        }
+    }
+
+    /// Returns a copy of the row with the given columns set to new values
+    fn with_values(&self, assignments: &[(String, Value)]) -> TriggerRow {
+        let mut row = self.clone();
+        for (column, value) in assignments {
+            if let Some((_, old)) = row.values.iter_mut().find(|(name, _)| name == column) {
+                *old = value.clone();
+            }
+        }
+        row
+    }
+}
+
+/// `RowSource` for the WHEN clause of a trigger, reading the `NEW` and `OLD` rows
+struct TriggerRowSource<'a> {
+    old: Option<&'a TriggerRow>,
+    new: Option<&'a TriggerRow>,
+}
+
+impl<'a> RowSource for TriggerRowSource<'a> {
+    fn get_column(&self, table: Option<&str>, column: &str) -> Result<Value> {
+        let row = match table.map(|table| table.to_lowercase()).as_deref() {
+            Some("new") => self.new,
+            Some("old") => self.old,
+            _ => None,
+        };
+        row.and_then(|row| row.get(column))
+            .ok_or_else(|| match table {
+                Some(table) => {
+                    SQLRiteError::General(format!("no such column: {}.{}", table, column))
+                }
+                None => SQLRiteError::General(format!("no such column: {}", column)),
+            })
+    }
+}
+
+/// Returns true if a change of a table fires any BEFORE or AFTER trigger
+pub fn has_triggers(db: &Database, table_name: &str, event: &TriggerEvent) -> bool {
+    db.triggers.values().any(|trigger| {
+        trigger.fires_on(table_name, TriggerTiming::Before, event)
+            || trigger.fires_on(table_name, TriggerTiming::After, event)
+    })
+}
+
+/// Runs the triggers a change of a row fires, in the order of their names. A trigger does not
+/// fire again while it runs, and only runs for the rows its WHEN clause is true for.
+/// Returns false if a trigger called `RAISE(IGNORE)`, skipping the change of the row.
+pub fn fire_triggers(
+    db: &mut Database,
+    table_name: &str,
+    timing: TriggerTiming,
+    event: &TriggerEvent,
+    old: Option<&TriggerRow>,
+    new: Option<&TriggerRow>,
+) -> Result<bool> {
+    let mut triggers: Vec<Trigger> = db
+        .triggers
+        .values()
+        .filter(|trigger| {
+            trigger.fires_on(table_name, timing, event)
+                && !db.running_triggers.contains(&trigger.name)
+        })
+        .cloned()
+        .collect();
+    triggers.sort_by(|a, b| a.name.cmp(&b.name));
+
+    for trigger in triggers {
+        if let Some(when) = &trigger.when {
+            if is_truthy(&evaluate(when, &TriggerRowSource { old, new })?) != Some(true) {
+                continue;
+            }
+        }
+        db.running_triggers.push(trigger.name.to_string());
+        let result = trigger.body.iter().try_for_each(|statement| {
+            let statement = bind_rows(statement, old, new)?;
+            execute_trigger_statement(&statement, db)
+        });
+        db.running_triggers.pop();
+        match result {
+            Err(SQLRiteError::Raise(RaiseAction::Ignore, _)) => return Ok(false),
+            result => result?,
+        }
+    }
+    Ok(true)
+}
+
+/// Replaces the `NEW.column` and `OLD.column` references of a statement of a trigger body
+/// with the values of the rows the trigger fired for
+fn bind_rows(
+    statement: &str,
+    old: Option<&TriggerRow>,
+    new: Option<&TriggerRow>,
+) -> Result<String> {
+    let dialect = SQLiteDialect {};
+    let tokens = Tokenizer::new(&dialect, statement)
+        .tokenize()
+        .map_err(ParserError::from)?;
+    let mut bound = String::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        if let (Token::Word(qualifier), Some(Token::Period), Some(Token::Word(column))) =
+            (&tokens[i], tokens.get(i + 1), tokens.get(i + 2))
+        {
+            let row = match qualifier.value.to_lowercase().as_ref() {
+                "new" => Some(new),
+                "old" => Some(old),
+                _ => None,
+            };
+            let qualified = i > 0 && tokens[i - 1] == Token::Period;
+            if let (Some(row), false) = (row, qualified) {
+                let value = row.and_then(|row| row.get(&column.value)).ok_or_else(|| {
+                    SQLRiteError::General(format!(
+                        "no such column: {}.{}",
+                        qualifier.value, column.value
+                    ))
+                })?;
+                bound.push_str(&to_literal(&value));
+                i += 3;
+                continue;
+            }
+        }
+        bound.push_str(&tokens_to_string(&tokens[i..i + 1]));
+        i += 1;
+    }
+    Ok(bound)
+}
+
+/// Writes a value as a SQL literal
+fn to_literal(value: &Value) -> String {
+    match value {
+        Value::Null => String::from("NULL"),
+        Value::Integer(i) if *i < 0 => format!("({})", i),
+        Value::Integer(i) => i.to_string(),
+        Value::Real(f) if *f < 0.0 => format!("({:?})", f),
+        Value::Real(f) => format!("{:?}", f),
+        Value::Text(s) => SqlValue::SingleQuotedString(s.to_string()).to_string(),
+        Value::Bool(b) => b.to_string(),
+        Value::Blob(bytes) => encode_blob(bytes),
+    }
+}
+
+/// Runs a statement of a trigger body, discarding the rows it returns
+fn execute_trigger_statement(statement: &str, db: &mut Database) -> Result<()> {
+    let (statement, upsert) = split_upsert(statement)?;
+    let mut ast = Parser::parse_sql(&SQLiteDialect {}, &statement).map_err(SQLRiteError::from)?;
+    match ast.pop() {
+        Some(statement @ Statement::Insert { .. }) => {
+            execute_insert(InsertQuery::new(&statement, upsert, None)?, db)?;
+        }
+        Some(statement @ Statement::Update { .. }) => {
+            execute_update(UpdateQuery::new(&statement, None)?, db, None)?;
+        }
+        Some(statement @ Statement::Delete { .. }) => {
+            execute_delete(DeleteQuery::new(&statement, None)?, db, None)?;
+        }
+        Some(statement @ Statement::Query(_)) => {
+            execute_select(&SelectQuery::new(&statement)?, db)?;
+        }
+        _ => {
+            return Err(SQLRiteError::Internal(format!(
+                "Unexpected statement in trigger: {}",
+                statement
+            )))
+        }
+    }
+    Ok(())
+}
+
+/// Applies the updates of an UPDATE statement, running the BEFORE and AFTER UPDATE triggers
+/// of the table around the update of every row. Returns the ROWIDs of the updated rows,
+/// leaving out those a trigger skipped or deleted.
+pub fn update_rows(
+    db: &mut Database,
+    table_name: &str,
+    updates: Vec<(i64, Vec<(String, Value)>)>,
+) -> Result<Vec<i64>> {
+    let columns: Vec<String> = match updates.first() {
+        Some((_, values)) => values.iter().map(|(col, _)| col.to_string()).collect(),
+        None => vec![],
+    };
+    let event = TriggerEvent::Update(columns);
+    if !has_triggers(db, table_name, &event) {
+        return db.update_rows(table_name, updates);
+    }
+
+    let mut rowids: Vec<i64> = vec![];
+    for (rowid, values) in updates {
+        let table = db.get_table(table_name.to_string())?;
+        if !table.contains_rowid(rowid) {
+            continue;
+        }
+        let old = TriggerRow::read(table, rowid);
+        let new = old.with_values(&values);
+        let before = fire_triggers(
+            db,
+            table_name,
+            TriggerTiming::Before,
+            &event,
+            Some(&old),
+            Some(&new),
+        )?;
+        if !before || !db.get_table(table_name.to_string())?.contains_rowid(rowid) {
+            continue;
+        }
+        // Updating the INTEGER PRIMARY KEY moves the row to another ROWID
+        let rowid = db.update_rows(table_name, vec![(rowid, values)])?[0];
+        let new = TriggerRow::read(db.get_table(table_name.to_string())?, rowid);
+        fire_triggers(
+            db,
+            table_name,
+            TriggerTiming::After,
+            &event,
+            Some(&old),
+            Some(&new),
+        )?;
+        rowids.push(rowid);
+    }
+    Ok(rowids)
+}
+
+/// Deletes a row, running the BEFORE and AFTER DELETE triggers of the table around it.
+/// Returns false if the row was skipped by a trigger or was already deleted.
+pub fn delete_row(db: &mut Database, table_name: &str, rowid: i64) -> Result<bool> {
+    let event = TriggerEvent::Delete;
+    if !has_triggers(db, table_name, &event) {
+        db.delete_row(table_name, rowid)?;
+        return Ok(true);
+    }
+
+    let table = db.get_table(table_name.to_string())?;
+    if !table.contains_rowid(rowid) {
+        return Ok(false);
+    }
+    let old = TriggerRow::read(table, rowid);
+    let before = fire_triggers(
+        db,
+        table_name,
+        TriggerTiming::Before,
+        &event,
+        Some(&old),
+        None,
+    )?;
+    if !before || !db.get_table(table_name.to_string())?.contains_rowid(rowid) {
+        return Ok(false);
+    }
+    db.delete_row(table_name, rowid)?;
+    fire_triggers(
+        db,
+        table_name,
+        TriggerTiming::After,
+        &event,
+        Some(&old),
+        None,
+    )?;
+    Ok(true)
+}
+
+/// Returns true if a view has INSTEAD OF triggers for a kind of statement
+pub fn has_instead_of_triggers(db: &Database, view_name: &str, event: &TriggerEvent) -> bool {
+    db.contains_view(view_name)
+        && db
+            .triggers
+            .values()
+            .any(|trigger| trigger.fires_on(view_name, TriggerTiming::InsteadOf, event))
+}
+
+/// Runs the INSTEAD OF INSERT triggers of a view for every row inserted into it, the
+/// columns of the view missing from the INSERT statement being NULL
+pub fn insert_into_view(
+    db: &mut Database,
+    view_name: &str,
+    columns: Option<Vec<String>>,
+    rows: &[Vec<Value>],
+) -> Result<()> {
+    let view_columns = db.views[view_name].execute(db)?.columns;
+    let columns = columns.unwrap_or_else(|| view_columns.to_vec());
+    if let Some(column) = columns.iter().find(|col| !view_columns.contains(col)) {
+        return Err(SQLRiteError::General(format!(
+            "table {} has no column named {}",
+            view_name, column
+        )));
+    }
+    for row in rows {
+        if row.len() != columns.len() {
+            return Err(SQLRiteError::General(format!(
+                "{} values for {} columns",
+                row.len(),
+                columns.len()
+            )));
+        }
+        let new = TriggerRow {
+            rowid: None,
+            values: view_columns
+                .iter()
+                .map(|view_column| {
+                    let value = columns
+                        .iter()
+                        .position(|col| col == view_column)
+                        .map_or(Value::Null, |i| row[i].clone());
+                    (view_column.to_string(), value)
+                })
+                .collect(),
+        };
+        fire_triggers(
+            db,
+            view_name,
+            TriggerTiming::InsteadOf,
+            &TriggerEvent::Insert,
+            None,
+            Some(&new),
+        )?;
+    }
+    Ok(())
+}
+
+/// Returns the rows of a view selected by a WHERE clause, each paired with the row as the
+/// assignments of an UPDATE statement, if any, leave it
+fn select_view_rows(
+    db: &Database,
+    view_name: &str,
+    selection: Option<&Expr>,
+    assignments: &[(String, Expr)],
+) -> Result<Vec<(TriggerRow, TriggerRow)>> {
+    let view = &db.views[view_name];
+    let table = match &view.rows {
+        Some(rows) => rows.clone(),
+        None => view.materialize(db)?,
+    };
+    for (column, _) in assignments {
+        if !table.contains_column(column.to_string()) {
+            return Err(SQLRiteError::General(format!("no such column: {}", column)));
+        }
+    }
+    let from = [FromTable {
+        name: view_name.to_string(),
+        alias: None,
+        join: JoinKind::From,
+        constraint: None,
+    }];
+    let tables = [table];
+    let scope = Scope::new(db, &from, &tables)?;
+
+    let mut rows: Vec<(TriggerRow, TriggerRow)> = vec![];
+    for rowid in tables[0].rowids() {
+        let rowids = [Some(rowid)];
+        let row = JoinedRow {
+            scope: &scope,
+            rowids: &rowids,
+        };
+        if let Some(selection) = selection {
+            if is_truthy(&evaluate(selection, &row)?) != Some(true) {
+                continue;
+            }
+        }
+        let mut values: Vec<(String, Value)> = vec![];
+        for (column, expr) in assignments {
+            values.push((column.to_string(), evaluate(expr, &row)?));
+        }
+        let mut old = TriggerRow::read(&tables[0], rowid);
+        old.rowid = None;
+        let new = old.with_values(&values);
+        rows.push((old, new));
+    }
+    Ok(rows)
+}
+
+/// Runs the INSTEAD OF UPDATE triggers of a view for every row of the view an UPDATE
+/// statement selects
+pub fn update_view(
+    db: &mut Database,
+    view_name: &str,
+    assignments: &[(String, Expr)],
+    selection: Option<&Expr>,
+) -> Result<()> {
+    let event = TriggerEvent::Update(assignments.iter().map(|(col, _)| col.to_string()).collect());
+    for (old, new) in select_view_rows(db, view_name, selection, assignments)? {
+        fire_triggers(
+            db,
+            view_name,
+            TriggerTiming::InsteadOf,
+            &event,
+            Some(&old),
+            Some(&new),
+        )?;
+    }
+    Ok(())
+}
+
+/// Runs the INSTEAD OF DELETE triggers of a view for every row of the view a DELETE
+/// statement selects
+pub fn delete_from_view(
+    db: &mut Database,
+    view_name: &str,
+    selection: Option<&Expr>,
+) -> Result<()> {
+    for (old, _) in select_view_rows(db, view_name, selection, &[])? {
+        fire_triggers(
+            db,
+            view_name,
+            TriggerTiming::InsteadOf,
+            &TriggerEvent::Delete,
+            Some(&old),
+            None,
+        )?;
+    }
+    Ok(())
+}
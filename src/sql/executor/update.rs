@@ -0,0 +1,65 @@
+use crate::error::{Result, SQLRiteError};
+use crate::sql::db::database::Database;
+use crate::sql::db::value::Value;
+use crate::sql::executor::expr::{evaluate, TableRow};
+use crate::sql::executor::planner::QueryPlan;
+use crate::sql::executor::select::{execute_returning, find_rowids};
+use crate::sql::executor::trigger;
+use crate::sql::parser::trigger::TriggerEvent;
+use crate::sql::parser::update::UpdateQuery;
+
+/// Runs an UPDATE query. Every new value is computed from the rows as they were before the
+/// statement, and the triggers of the table fire for every updated row. An UPDATE of a view
+/// runs its INSTEAD OF UPDATE triggers instead.
+/// The rows to update are found following `plan` if there is one, see `find_rowids`.
+/// Returns the ROWIDs of the rows of the table the statement updated, in order.
+pub fn execute_update(
+    query: UpdateQuery,
+    db: &mut Database,
+    plan: Option<QueryPlan>,
+) -> Result<Vec<i64>> {
+    let table_name = query.table_name;
+    let columns: Vec<String> = query
+        .assignments
+        .iter()
+        .map(|(column, _)| column.to_string())
+        .collect();
+    if trigger::has_instead_of_triggers(db, &table_name, &TriggerEvent::Update(columns)) {
+        if let Some(returning) = &query.returning {
+            execute_returning(returning, db, &table_name, &[])?;
+        }
+        let (assignments, selection) = (query.assignments, query.selection);
+        db.run_statement(false, |db| {
+            trigger::update_view(db, &table_name, &assignments, selection.as_ref())
+        })?;
+        return Ok(vec![]);
+    }
+
+    // Rows are selected before updating any, so the WHERE clause sees the table as it was
+    let rowids = find_rowids(db, &table_name, query.selection.as_ref(), plan)?;
+    // The RETURNING clause is checked before updating anything
+    if let Some(returning) = &query.returning {
+        execute_returning(returning, db, &table_name, &[])?;
+    }
+    let db_table = db.get_table(table_name.to_string())?;
+    for (column, _) in &query.assignments {
+        if !db_table.contains_column(column.to_string()) {
+            return Err(SQLRiteError::General(format!("no such column: {}", column)));
+        }
+        db_table.validate_assignment(column)?;
+    }
+
+    let mut updates: Vec<(i64, Vec<(String, Value)>)> = vec![];
+    for rowid in &rowids {
+        let row = TableRow {
+            table: db_table,
+            rowid: *rowid,
+        };
+        let mut values: Vec<(String, Value)> = vec![];
+        for (column, expr) in &query.assignments {
+            values.push((column.to_string(), evaluate(expr, &row)?));
+        }
+        updates.push((*rowid, values));
+    }
+    db.run_statement(false, |db| trigger::update_rows(db, &table_name, updates))
+}
@@ -1,17 +1,47 @@
+pub mod batch;
+pub mod cache;
 pub mod parser;
 // pub mod tokenizer;
 pub mod db;
+pub mod executor;
+pub mod prepared;
 
-use parser::create::CreateQuery;
-use parser::insert::InsertQuery;
+use parser::alter::{AlterOperation, AlterQuery};
+use parser::analyze::AnalyzeQuery;
+use parser::create::{
+    integer_columns, split_deferrable, split_generated, CreateQuery, ParsedColumn, ParsedGenerated,
+};
+use parser::delete::DeleteQuery;
+use parser::drop::{DropObject, DropQuery};
+use parser::explain::{split_explain_query_plan, ExplainQuery};
+use parser::index::{split_index_predicate, CreateIndexQuery};
+use parser::insert::{split_upsert, InsertQuery, Upsert};
+use parser::pragma::PragmaQuery;
+use parser::returning::split_returning;
+use parser::select::{split_limit, Limit, SelectQuery};
+use parser::trigger::{CreateTriggerQuery, DropTriggerQuery};
+use parser::update::UpdateQuery;
+use parser::view::{CreateViewQuery, RefreshViewQuery};
 
-use sqlparser::ast::Statement;
+use sqlparser::ast::{Expr, SelectItem, Statement};
 use sqlparser::dialect::SQLiteDialect;
 use sqlparser::parser::{Parser, ParserError};
 
-use crate::error::{Result, SQLRiteError};
+use crate::error::{RaiseAction, Result, SQLRiteError};
+use crate::sql::cache::StatementCache;
 use crate::sql::db::database::Database;
 use crate::sql::db::table::Table;
+use crate::sql::db::trigger::Trigger;
+use crate::sql::db::value::Value;
+use crate::sql::db::view::View;
+use crate::sql::executor::delete::execute_delete;
+use crate::sql::executor::explain::{explain, explain_query_plan};
+use crate::sql::executor::insert::execute_insert;
+use crate::sql::executor::planner::QueryPlan;
+use crate::sql::executor::select::{
+    execute_planned_select, execute_returning, execute_select, ResultSet,
+};
+use crate::sql::executor::update::execute_update;
 
 #[derive(Debug, PartialEq)]
 pub enum SQLCommand {
@@ -37,14 +67,181 @@ impl SQLCommand {
     }
 }
 
-/// Performs initial parsing of SQL Statement using sqlparser-rs
-pub fn process_command(query: &str, db: &mut Database) -> Result<String> {
+/// Performs initial parsing of SQL Statement using sqlparser-rs, unless `cache` kept the
+/// statement from a previous run, then executes it with its parameters bound to the values
+/// set with `.parameter set`, NULL for the others.
+/// Scripts made of several statements are run by `batch::execute_batch`.
+pub fn process_command(
+    query: &str,
+    db: &mut Database,
+    cache: &mut StatementCache,
+) -> Result<String> {
+    let mut statement = cache.prepare(query, db)?;
+    statement.bind_parameters(&db.parameters)?;
+    let output = statement.execute(db)?;
+    output.print(db)?;
+    Ok(output.message)
+}
+
+/// The outcome of a statement run by `execute_command`
+#[derive(Debug)]
+pub struct Output {
+    /// Message telling which statement was executed
+    pub message: String,
+    /// Rows returned by a SELECT, EXPLAIN or PRAGMA statement, or by a RETURNING clause
+    pub rows: Option<ResultSet>,
+    /// Number of rows inserted, updated or deleted
+    pub changes: usize,
+    /// What the REPL prints for the statement
+    pub echo: Echo,
+}
+
+/// What the REPL prints once a statement ran, see `Output::print`
+#[derive(Debug, PartialEq)]
+pub enum Echo {
+    /// The rows of the output, if any
+    Rows,
+    /// The rows of the output, one line each, for EXPLAIN statements
+    Lines,
+    /// The schema of a table after CREATE TABLE
+    Schema(String),
+    /// The rows of a table or view after an INSERT, UPDATE or DELETE statement
+    Changed(String),
+    /// The statistics of tables after ANALYZE
+    Stats(Vec<String>),
+}
+
+impl Output {
+    /// Creates the output of a statement returning no rows
+    ///
+    pub fn new(message: &str) -> Output {
+        Output {
+            message: message.to_string(),
+            rows: None,
+            changes: 0,
+            echo: Echo::Rows,
+        }
+    }
+
+    /// Prints the output of a statement the way the REPL shows it
+    ///
+    pub fn print(&self, db: &Database) -> Result<()> {
+        match &self.echo {
+            Echo::Rows => {
+                if let Some(rows) = &self.rows {
+                    rows.print();
+                }
+            }
+            Echo::Lines => {
+                for row in self.rows.iter().flat_map(|rows| &rows.rows) {
+                    if let Some(Value::Text(line)) = row.first() {
+                        println!("{}", line);
+                    }
+                }
+            }
+            Echo::Schema(table_name) => {
+                db.get_table(table_name.to_string())?.print_table_schema()?;
+            }
+            Echo::Changed(name) => match db.views.get(name) {
+                Some(view) => view.execute(db)?.print(),
+                None => db.get_table(name.to_string())?.print_table_data(),
+            },
+            Echo::Stats(table_names) => {
+                for table_name in table_names {
+                    db.get_table(table_name.to_string())?.print_table_stats();
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A statement parsed by `parse_command`, ready to be run by `execute_command`
+#[derive(Debug, Clone)]
+pub enum ParsedCommand {
+    Analyze(AnalyzeQuery),
+    RefreshView(RefreshViewQuery),
+    CreateTrigger(Box<CreateTriggerQuery>),
+    DropTrigger(DropTriggerQuery),
+    InsertDefaultValues(Box<InsertQuery>),
+    Pragma(PragmaQuery),
+    Statement(Box<ParsedStatement>),
+}
+
+/// A statement parsed by sqlparser, along with the clauses sqlparser does not support,
+/// parsed apart
+#[derive(Debug, Clone)]
+pub struct ParsedStatement {
+    pub statement: Statement,
+    /// RETURNING clause of an INSERT, UPDATE or DELETE statement, see `split_returning`
+    pub returning: Option<Vec<SelectItem>>,
+    /// Value representing if the statement was declared EXPLAIN QUERY PLAN
+    pub query_plan: bool,
+    /// WHERE clause of a partial index, see `split_index_predicate`
+    pub index_predicate: Option<Expr>,
+    /// DEFERRABLE clauses of FOREIGN KEY constraints, see `split_deferrable`
+    pub deferred: Vec<bool>,
+    /// GENERATED ALWAYS AS clauses of columns, see `split_generated`
+    pub generated: Vec<(String, ParsedGenerated)>,
+    /// Columns of a CREATE TABLE statement declared INTEGER, see `integer_columns`
+    pub integer_columns: Vec<String>,
+    /// ON CONFLICT clause of an INSERT statement, see `split_upsert`
+    pub upsert: Option<Upsert>,
+    /// LIMIT clause of a SELECT statement, see `split_limit`
+    pub limit: Option<Limit>,
+    /// Plan of a SELECT, UPDATE or DELETE statement made when it was prepared,
+    /// see `PreparedStatement`.
+    /// Without one the statement is planned when it executes.
+    pub plan: Option<QueryPlan>,
+}
+
+/// Parses a single statement, without executing it
+pub fn parse_command(query: &str) -> Result<ParsedCommand> {
     let dialect = SQLiteDialect {};
-    let message: String;
+    // sqlparser only supports the `ANALYZE TABLE <name>` form of ANALYZE
+    if let Some(payload) = AnalyzeQuery::parse(query)? {
+        return Ok(ParsedCommand::Analyze(payload));
+    }
+    // Nor REFRESH MATERIALIZED VIEW
+    if let Some(payload) = RefreshViewQuery::parse(query)? {
+        return Ok(ParsedCommand::RefreshView(payload));
+    }
+    // Nor CREATE TRIGGER and DROP TRIGGER
+    if let Some(payload) = CreateTriggerQuery::parse(query)? {
+        return Ok(ParsedCommand::CreateTrigger(Box::new(payload)));
+    }
+    if let Some(payload) = DropTriggerQuery::parse(query)? {
+        return Ok(ParsedCommand::DropTrigger(payload));
+    }
+    // Nor RETURNING clauses of INSERT, UPDATE and DELETE statements
+    let (query, returning) = split_returning(query)?;
+    // Nor INSERT ... DEFAULT VALUES
+    if let Some(mut payload) = InsertQuery::parse_default_values(&query)? {
+        payload.returning = returning;
+        return Ok(ParsedCommand::InsertDefaultValues(Box::new(payload)));
+    }
+    // Nor PRAGMA statements
+    if let Some(payload) = PragmaQuery::parse(&query)? {
+        return Ok(ParsedCommand::Pragma(payload));
+    }
+    // sqlparser supports neither EXPLAIN QUERY PLAN nor partial indexes,
+    // so those parts of the statement are parsed apart
+    let (query, query_plan) = split_explain_query_plan(&query)?;
+    let (query, index_predicate) = split_index_predicate(&query)?;
+    // Nor DEFERRABLE clauses of FOREIGN KEY constraints
+    let (query, deferred) = split_deferrable(&query)?;
+    // Nor GENERATED ALWAYS AS clauses of columns
+    let (query, generated) = split_generated(&query)?;
+    // Nor does it tell INTEGER from INT
+    let integer_columns = integer_columns(&query)?;
+    // Nor ON CONFLICT clauses of INSERT statements
+    let (query, upsert) = split_upsert(&query)?;
+    // Nor anything but number literals in LIMIT clauses
+    let (query, limit) = split_limit(&query)?;
     let mut ast: Vec<Statement> =
         Parser::parse_sql(&dialect, &query).map_err(SQLRiteError::from)?;
 
-    if ast.len() > 1 || ast.len() == 0 {
+    if ast.len() != 1 {
         return Err(SQLRiteError::SqlError(ParserError::ParserError(format!(
             "Expected a single query statement, but there are {}",
             ast.len()
@@ -53,16 +250,84 @@ pub fn process_command(query: &str, db: &mut Database) -> Result<String> {
 
     // We can use unwrap() here because we handled None case above
     // Otherwise we would have to explicitely handle 0/None case
-    let query = ast.pop().unwrap();
+    Ok(ParsedCommand::Statement(Box::new(ParsedStatement {
+        statement: ast.pop().unwrap(),
+        returning,
+        query_plan,
+        index_predicate,
+        deferred,
+        generated,
+        integer_columns,
+        upsert,
+        limit,
+        plan: None,
+    })))
+}
+
+/// Executes a statement parsed by `parse_command`
+pub fn execute_command(command: ParsedCommand, db: &mut Database) -> Result<Output> {
+    let result = execute_parsed(command, db);
+    // RAISE(ROLLBACK, ...) in a trigger also ends the open transaction
+    if let Err(SQLRiteError::Raise(RaiseAction::Rollback, _)) = &result {
+        if db.transaction.is_some() {
+            db.rollback()?;
+        }
+    }
+    result
+}
+
+fn execute_parsed(command: ParsedCommand, db: &mut Database) -> Result<Output> {
+    let output: Output;
+    let ParsedStatement {
+        statement: mut query,
+        returning,
+        query_plan,
+        index_predicate,
+        deferred,
+        generated,
+        integer_columns,
+        upsert,
+        limit,
+        plan,
+    } = match command {
+        ParsedCommand::Analyze(payload) => return analyze(payload, db),
+        ParsedCommand::RefreshView(payload) => {
+            db.refresh_view(&payload.name)?;
+            return Ok(Output::new("REFRESH MATERIALIZED VIEW Statement executed."));
+        }
+        ParsedCommand::CreateTrigger(payload) => {
+            let if_not_exists = payload.if_not_exists;
+            db.create_trigger(Trigger::new(*payload), if_not_exists)?;
+            return Ok(Output::new("CREATE TRIGGER Statement executed."));
+        }
+        ParsedCommand::DropTrigger(payload) => {
+            if db.triggers.contains_key(&payload.name) || !payload.if_exists {
+                db.drop_trigger(&payload.name)?;
+            }
+            return Ok(Output::new("DROP TRIGGER Statement executed."));
+        }
+        ParsedCommand::InsertDefaultValues(payload) => return insert(*payload, db),
+        ParsedCommand::Pragma(payload) => return pragma(payload, db),
+        ParsedCommand::Statement(statement) => *statement,
+    };
+    if let Some(limit) = limit {
+        limit.apply(&mut query);
+    }
 
     // Initialy only implementing some basic SQL Statements
     match query {
         Statement::CreateTable { .. } => {
-            let create_query = CreateQuery::new(&query);
+            let create_query = CreateQuery::new(&query, &deferred, &generated, &integer_columns);
             match create_query {
-                Ok(payload) => {
+                Ok(mut payload) => {
                     let table_name = payload.table_name.clone();
 
+                    if db.contains_view(&table_name) {
+                        return Err(SQLRiteError::General(format!(
+                            "view {} already exists",
+                            table_name
+                        )));
+                    }
                     // Checking if table already exists, after parsing CREATE TABLE query
                     match db.contains_table(table_name.to_string()) {
                         true => {
@@ -71,15 +336,38 @@ pub fn process_command(query: &str, db: &mut Database) -> Result<String> {
                             ));
                         }
                         false => {
+                            // CREATE TABLE ... AS SELECT takes its columns and rows from the query
+                            let rows = match payload.select.take() {
+                                Some(select) => {
+                                    let result = execute_select(&select, db)?;
+                                    payload.columns = ParsedColumn::from_result_set(&result);
+                                    result.rows
+                                }
+                                None => vec![],
+                            };
+                            let columns: Vec<String> = payload
+                                .columns
+                                .iter()
+                                .map(|col| col.name.to_string())
+                                .collect();
                             let table = Table::new(payload);
 
-                            let _ = table.print_table_schema();
                             db.tables.insert(table_name.to_string(), table);
+                            db.schema_changed(&table_name);
+                            for row in &rows {
+                                if let Err(err) = db.insert_row(&table_name, &columns, row) {
+                                    db.tables.remove(&table_name);
+                                    return Err(err);
+                                }
+                            }
                             // Iterate over everything.
                             // for (table_name, _) in &db.tables {
                             //     println!("{}" , table_name);
                             // }
-                            message = String::from("CREATE TABLE Statement executed.");
+                            output = Output {
+                                echo: Echo::Schema(table_name),
+                                ..Output::new("CREATE TABLE Statement executed.")
+                            };
                         }
                     }
                 }
@@ -87,69 +375,260 @@ pub fn process_command(query: &str, db: &mut Database) -> Result<String> {
             }
         }
         Statement::Insert { .. } => {
-            let insert_query = InsertQuery::new(&query);
+            let insert_query = InsertQuery::new(&query, upsert, returning);
             match insert_query {
+                Ok(payload) => return insert(payload, db),
+                Err(err) => return Err(err),
+            }
+        }
+        Statement::CreateView { .. } => {
+            let create_view_query = CreateViewQuery::new(&query);
+            match create_view_query {
+                Ok(payload) => {
+                    let or_replace = payload.or_replace;
+                    output = Output::new(match payload.materialized {
+                        true => "CREATE MATERIALIZED VIEW Statement executed.",
+                        false => "CREATE VIEW Statement executed.",
+                    });
+                    db.create_view(View::new(payload), or_replace)?;
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        Statement::CreateIndex { .. } => {
+            let create_index_query = CreateIndexQuery::new(&query, index_predicate);
+            match create_index_query {
+                Ok(payload) => {
+                    // CREATE INDEX IF NOT EXISTS is a no-op when the index is already there
+                    if !(payload.if_not_exists && db.contains_index(payload.index_name.to_string()))
+                    {
+                        db.create_index(
+                            payload.index_name,
+                            payload.table_name,
+                            payload.expressions,
+                            payload.predicate,
+                            payload.is_unique,
+                        )?;
+                    }
+                    output = Output::new("CREATE INDEX Statement executed.");
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        Statement::AlterTable { .. } => {
+            let alter_query = AlterQuery::new(&query, &deferred, &generated);
+            match alter_query {
                 Ok(payload) => {
                     let table_name = payload.table_name;
-                    let columns = payload.columns;
-                    let values = payload.rows;
-
-                    // println!("table_name = {:?}\n cols = {:?}\n vals = {:?}", table_name, columns, values);
-                    // Checking if Table exists in Database
+                    // Views reading the table must still be valid once its columns changed,
+                    // otherwise the ALTER TABLE is undone
+                    let snapshot = match payload.operation {
+                        AlterOperation::RenameTable { .. } => None,
+                        _ if db.views.is_empty() => None,
+                        _ => Some(db.tables.clone()),
+                    };
                     match db.contains_table(table_name.to_string()) {
-                        true => {
-                            let db_table = db.get_table_mut(table_name.to_string()).unwrap();
-                            // Checking if columns on INSERT query exist on Table
-                            match columns
-                                .iter()
-                                .all(|column| db_table.contains_column(column.to_string()))
-                            {
+                        true => match payload.operation {
+                            AlterOperation::RenameTable { new_name } => {
+                                db.rename_table(table_name.to_string(), new_name)?;
+                            }
+                            AlterOperation::AddColumn { column } => {
+                                let db_table = db.get_table_mut(table_name.to_string()).unwrap();
+                                db_table.add_column(*column)?;
+                                db.schema_changed(&table_name);
+                            }
+                            AlterOperation::RenameColumn { old_name, new_name } => {
+                                db.rename_column(&table_name, old_name, new_name)?;
+                            }
+                            AlterOperation::DropColumn { name, if_exists } => {
+                                let db_table = db.get_table_mut(table_name.to_string()).unwrap();
+                                if db_table.contains_column(name.to_string()) {
+                                    db_table.drop_column(name)?;
+                                    db.schema_changed(&table_name);
+                                } else if !if_exists {
+                                    return Err(SQLRiteError::General(format!(
+                                        "no such column: \"{}\"",
+                                        name
+                                    )));
+                                }
+                            }
+                        },
+                        false => {
+                            return Err(SQLRiteError::General(format!(
+                                "no such table: {}",
+                                table_name
+                            )))
+                        }
+                    }
+                    if let Some(tables) = snapshot {
+                        if let Err(err) = db.check_views(&table_name) {
+                            db.tables = tables;
+                            return Err(err);
+                        }
+                    }
+                    output = Output::new("ALTER TABLE Statement executed.");
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        Statement::Drop { .. } => {
+            let drop_query = DropQuery::new(&query);
+            match drop_query {
+                Ok(payload) => {
+                    for name in payload.names {
+                        match payload.object {
+                            DropObject::Table => match db.contains_table(name.to_string()) {
                                 true => {
-                                    for value in &values {
-                                        // Checking if number of columns in query are the same as number of values
-                                        if columns.len() != value.len() {
-                                            return Err(SQLRiteError::Internal(format!(
-                                                "{} values for {} columns",
-                                                value.len(),
-                                                columns.len()
-                                            )));
-                                        }
-                                        match db_table.validate_unique_constraint(&columns, value) {
-                                            Ok(()) => {
-                                                // No unique constraint violation, moving forward with inserting row
-                                                db_table.insert_row(&columns, &value);
-                                            }
-                                            Err(err) => {
-                                                return Err(SQLRiteError::Internal(format!(
-                                                    "Unique key constaint violation: {}",
-                                                    err
-                                                )))
-                                            }
-                                        }
+                                    // A table still referred to by FOREIGN KEY constraints
+                                    // can only be dropped with the constraints disabled
+                                    if db.foreign_keys && db.is_referenced(&name) {
+                                        return Err(SQLRiteError::General(String::from(
+                                            "FOREIGN KEY constraint failed",
+                                        )));
                                     }
+                                    db.drop_table(name)?;
+                                }
+                                false if db.contains_view(&name) => {
+                                    return Err(SQLRiteError::General(format!(
+                                        "use DROP VIEW to delete view {}",
+                                        name
+                                    )));
                                 }
                                 false => {
-                                    return Err(SQLRiteError::Internal(
-                                        "Cannot insert, some of the columns do not exist"
-                                            .to_string(),
-                                    ));
+                                    if !payload.if_exists {
+                                        return Err(SQLRiteError::General(format!(
+                                            "no such table: {}",
+                                            name
+                                        )));
+                                    }
                                 }
-                            }
-                            db_table.print_table_data();
-                        }
-                        false => {
-                            return Err(SQLRiteError::Internal("Table doesn't exist".to_string()))
+                            },
+                            DropObject::Index => match db.contains_index(name.to_string()) {
+                                true => db.drop_index(name)?,
+                                false => {
+                                    if !payload.if_exists {
+                                        return Err(SQLRiteError::General(format!(
+                                            "no such index: {}",
+                                            name
+                                        )));
+                                    }
+                                }
+                            },
+                            DropObject::View => match db.contains_view(&name) {
+                                true => {
+                                    db.drop_view(&name)?;
+                                }
+                                false if db.contains_table(name.to_string()) => {
+                                    return Err(SQLRiteError::General(format!(
+                                        "use DROP TABLE to delete table {}",
+                                        name
+                                    )));
+                                }
+                                false => {
+                                    if !payload.if_exists {
+                                        return Err(SQLRiteError::General(format!(
+                                            "no such view: {}",
+                                            name
+                                        )));
+                                    }
+                                }
+                            },
                         }
                     }
+                    output = Output::new(match payload.object {
+                        DropObject::Table => "DROP TABLE Statement executed.",
+                        DropObject::Index => "DROP INDEX Statement executed.",
+                        DropObject::View => "DROP VIEW Statement executed.",
+                    });
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        Statement::Update { .. } => {
+            let update_query = UpdateQuery::new(&query, returning);
+            match update_query {
+                Ok(payload) => {
+                    let table_name = payload.table_name.to_string();
+                    let returning = payload.returning.clone();
+                    let rowids = execute_update(payload, db, plan)?;
+                    // RETURNING reads the rows as they are after the UPDATE
+                    let rows = match returning {
+                        Some(returning) => {
+                            Some(execute_returning(&returning, db, &table_name, &rowids)?)
+                        }
+                        None => None,
+                    };
+                    output = changed("UPDATE Statement executed.", table_name, rowids, rows);
                 }
                 Err(err) => return Err(err),
             }
-
-            message = String::from("INSERT Statement executed.")
         }
-        Statement::Query(_query) => message = String::from("SELECT Statement executed."),
-        // Statement::Insert { .. } => message = String::from("INSERT Statement executed."),
-        Statement::Delete { .. } => message = String::from("DELETE Statement executed."),
+        Statement::Delete { .. } => {
+            let delete_query = DeleteQuery::new(&query, returning);
+            match delete_query {
+                Ok(payload) => {
+                    let table_name = payload.table_name.to_string();
+                    let (rowids, rows) = execute_delete(payload, db, plan)?;
+                    output = changed("DELETE Statement executed.", table_name, rowids, rows);
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        Statement::Query(_) => {
+            let select_query = SelectQuery::new(&query);
+            match select_query {
+                Ok(payload) => {
+                    output = Output {
+                        rows: Some(execute_planned_select(&payload, db, plan)?),
+                        ..Output::new("SELECT Statement executed.")
+                    };
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        Statement::Analyze { .. } => {
+            let analyze_query = AnalyzeQuery::new(&query);
+            match analyze_query {
+                Ok(payload) => return analyze(payload, db),
+                Err(err) => return Err(err),
+            }
+        }
+        Statement::Explain { .. } => {
+            let explain_query = ExplainQuery::new(&query, query_plan);
+            match explain_query {
+                Ok(payload) => {
+                    let lines = match payload.query_plan {
+                        true => explain_query_plan(&payload.statement, db)?,
+                        false => explain(&payload.statement, db)?,
+                    };
+                    let rows = ResultSet {
+                        columns: vec![String::from("plan")],
+                        rows: lines
+                            .into_iter()
+                            .map(|line| vec![Value::Text(line)])
+                            .collect(),
+                    };
+                    output = Output {
+                        rows: Some(rows),
+                        echo: Echo::Lines,
+                        ..Output::new("EXPLAIN Statement executed.")
+                    };
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        Statement::StartTransaction { .. } => {
+            db.begin()?;
+            output = Output::new("BEGIN Statement executed.");
+        }
+        Statement::Commit { .. } => {
+            db.commit()?;
+            output = Output::new("COMMIT Statement executed.");
+        }
+        Statement::Rollback { .. } => {
+            db.rollback()?;
+            output = Output::new("ROLLBACK Statement executed.");
+        }
         _ => {
             return Err(SQLRiteError::NotImplemented(
                 "SQL Statement not supported yet.".to_string(),
@@ -157,5 +636,80 @@ pub fn process_command(query: &str, db: &mut Database) -> Result<String> {
         }
     };
 
-    Ok(message)
+    Ok(output)
+}
+
+/// Collects the statistics of the tables named by an ANALYZE query, printed by the REPL
+fn analyze(query: AnalyzeQuery, db: &mut Database) -> Result<Output> {
+    db.analyze(query.table_name.clone())?;
+    let mut table_names: Vec<String> = match query.table_name {
+        Some(table_name) => vec![table_name],
+        None => db.tables.keys().cloned().collect(),
+    };
+    table_names.sort();
+    Ok(Output {
+        echo: Echo::Stats(table_names),
+        ..Output::new("ANALYZE Statement executed.")
+    })
+}
+
+/// Inserts the rows of an INSERT query into its table, returning the result of the
+/// RETURNING clause of the query when it has one
+fn insert(query: InsertQuery, db: &mut Database) -> Result<Output> {
+    let table_name = query.table_name.to_string();
+    let returning = query.returning.clone();
+    let rowids = execute_insert(query, db)?;
+    let rows = match returning {
+        Some(returning) => Some(execute_returning(&returning, db, &table_name, &rowids)?),
+        None => None,
+    };
+    Ok(changed(
+        "INSERT Statement executed.",
+        table_name,
+        rowids,
+        rows,
+    ))
+}
+
+/// Returns the output of an INSERT, UPDATE or DELETE statement that changed the rows with
+/// `rowids`. Without RETURNING rows, the REPL prints the table or view the statement changed.
+fn changed(message: &str, name: String, rowids: Vec<i64>, rows: Option<ResultSet>) -> Output {
+    let echo = match rows {
+        Some(_) => Echo::Rows,
+        None => Echo::Changed(name),
+    };
+    Output {
+        message: message.to_string(),
+        rows,
+        changes: rowids.len(),
+        echo,
+    }
+}
+
+/// Reads or sets a PRAGMA. Only `foreign_keys` is supported.
+fn pragma(query: PragmaQuery, db: &mut Database) -> Result<Output> {
+    let mut output = Output::new("PRAGMA Statement executed.");
+    match query.name.as_ref() {
+        "foreign_keys" => match query.bool_value()? {
+            Some(enabled) => {
+                // Like in SQLite, the pragma is a no-op within a transaction
+                if db.transaction.is_none() {
+                    db.foreign_keys = enabled;
+                }
+            }
+            None => {
+                output.rows = Some(ResultSet {
+                    columns: vec![String::from("foreign_keys")],
+                    rows: vec![vec![Value::Integer(db.foreign_keys as i64)]],
+                });
+            }
+        },
+        _ => {
+            return Err(SQLRiteError::NotImplemented(format!(
+                "PRAGMA {} is not supported yet.",
+                query.name
+            )))
+        }
+    }
+    Ok(output)
 }
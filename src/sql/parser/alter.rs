@@ -0,0 +1,99 @@
+use sqlparser::ast::{AlterTableOperation, Statement};
+
+use crate::error::{Result, SQLRiteError};
+use crate::sql::parser::create::{ParsedColumn, ParsedGenerated};
+
+/// The schema changes supported by ALTER TABLE
+#[derive(Debug)]
+pub enum AlterOperation {
+    /// `ADD COLUMN`, existing rows being back-filled with the DEFAULT of the column
+    AddColumn { column: Box<ParsedColumn> },
+    /// `RENAME COLUMN old TO new`
+    RenameColumn { old_name: String, new_name: String },
+    /// `DROP COLUMN`
+    DropColumn { name: String, if_exists: bool },
+    /// `RENAME TO new_table_name`
+    RenameTable { new_name: String },
+}
+
+/// The following structure represents an ALTER TABLE query already parsed
+/// and broken down into `table_name` and the `AlterOperation` to apply to it
+#[derive(Debug)]
+pub struct AlterQuery {
+    pub table_name: String,
+    pub operation: AlterOperation,
+}
+
+impl AlterQuery {
+    /// Builds the query from a parsed ALTER TABLE statement. `deferred` tells if the
+    /// REFERENCES clause of an added column was DEFERRABLE INITIALLY DEFERRED,
+    /// see `split_deferrable`. `generated` gives its GENERATED ALWAYS AS clause, if any,
+    /// see `split_generated`.
+    pub fn new(
+        statement: &Statement,
+        deferred: &[bool],
+        generated: &[(String, ParsedGenerated)],
+    ) -> Result<AlterQuery> {
+        match statement {
+            Statement::AlterTable { name, operation } => {
+                let operation = match operation {
+                    AlterTableOperation::AddColumn { column_def } => {
+                        let mut column = ParsedColumn::new(column_def)?;
+                        if let Some((_, clause)) = generated.first() {
+                            // Like SQLite, existing rows can not be back-filled with stored values
+                            if clause.stored {
+                                return Err(SQLRiteError::General(String::from(
+                                    "cannot add a STORED column",
+                                )));
+                            }
+                            column.set_generated(clause.clone())?;
+                        }
+                        column.validate_default()?;
+                        column.validate_autoincrement()?;
+                        if let Some(foreign_key) = column.foreign_key.as_mut() {
+                            foreign_key.validate()?;
+                            foreign_key.deferred = deferred.first().copied().unwrap_or(false);
+                        }
+                        AlterOperation::AddColumn {
+                            column: Box::new(column),
+                        }
+                    }
+                    AlterTableOperation::RenameColumn {
+                        old_column_name,
+                        new_column_name,
+                    } => AlterOperation::RenameColumn {
+                        old_name: old_column_name.value.to_string(),
+                        new_name: new_column_name.value.to_string(),
+                    },
+                    AlterTableOperation::DropColumn {
+                        column_name,
+                        if_exists,
+                        ..
+                    } => AlterOperation::DropColumn {
+                        name: column_name.value.to_string(),
+                        if_exists: *if_exists,
+                    },
+                    AlterTableOperation::RenameTable { table_name } => {
+                        AlterOperation::RenameTable {
+                            new_name: table_name.to_string(),
+                        }
+                    }
+                    _ => {
+                        return Err(SQLRiteError::NotImplemented(format!(
+                            "ALTER TABLE {} is not supported yet.",
+                            operation
+                        )))
+                    }
+                };
+
+                Ok(AlterQuery {
+                    table_name: name.to_string(),
+                    operation,
+                })
+            }
+            _ => Err(SQLRiteError::Internal(
+                "Error parsing alter query".to_string(),
+            )),
+        }
+    }
+}
@@ -0,0 +1,68 @@
+use sqlparser::ast::Statement;
+use sqlparser::dialect::SQLiteDialect;
+use sqlparser::parser::ParserError;
+use sqlparser::tokenizer::{Token, Tokenizer};
+
+use crate::error::{Result, SQLRiteError};
+use crate::sql::parser::trigger::is_keyword;
+
+/// The following structure represents an ANALYZE query already parsed
+#[derive(Debug, Clone)]
+pub struct AnalyzeQuery {
+    /// Name of the table to analyze, `None` analyzing every table of the database
+    pub table_name: Option<String>,
+}
+
+impl AnalyzeQuery {
+    /// Builds the query from a parsed `ANALYZE TABLE <name>` statement
+    pub fn new(statement: &Statement) -> Result<AnalyzeQuery> {
+        match statement {
+            Statement::Analyze {
+                table_name,
+                partitions,
+                for_columns,
+                columns,
+                ..
+            } => {
+                if partitions.is_some() || *for_columns || !columns.is_empty() {
+                    return Err(SQLRiteError::NotImplemented(
+                        "ANALYZE of partitions or columns is not supported.".to_string(),
+                    ));
+                }
+                Ok(AnalyzeQuery {
+                    table_name: Some(table_name.to_string()),
+                })
+            }
+            _ => Err(SQLRiteError::Internal(
+                "Error parsing analyze query".to_string(),
+            )),
+        }
+    }
+
+    /// Parses the SQLite forms `ANALYZE` and `ANALYZE <name>`, which sqlparser does not
+    /// support. Returns `None` when the query is not one of them.
+    pub fn parse(query: &str) -> Result<Option<AnalyzeQuery>> {
+        let dialect = SQLiteDialect {};
+        let tokens = Tokenizer::new(&dialect, query)
+            .tokenize()
+            .map_err(ParserError::from)?;
+        let words: Vec<&Token> = tokens
+            .iter()
+            .filter(|token| !matches!(token, Token::Whitespace(_) | Token::SemiColon))
+            .collect();
+
+        match words.as_slice() {
+            [keyword] if is_keyword(keyword, "ANALYZE") => {
+                Ok(Some(AnalyzeQuery { table_name: None }))
+            }
+            [keyword, Token::Word(name)]
+                if is_keyword(keyword, "ANALYZE") && !is_keyword(words[1], "TABLE") =>
+            {
+                Ok(Some(AnalyzeQuery {
+                    table_name: Some(name.value.to_string()),
+                }))
+            }
+            _ => Ok(None),
+        }
+    }
+}
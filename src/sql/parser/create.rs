@@ -1,6 +1,105 @@
-use sqlparser::ast::{ColumnOption, DataType, Statement};
+use sqlparser::ast::{
+    ColumnDef, ColumnOption, DataType, Expr, Ident, ObjectName, ReferentialAction, Statement,
+    TableConstraint,
+};
+use sqlparser::dialect::SQLiteDialect;
+use sqlparser::parser::{Parser, ParserError};
+use sqlparser::tokenizer::{Token, Tokenizer};
 
 use crate::error::{Result, SQLRiteError};
+use crate::sql::db::value::Value;
+use crate::sql::executor::expr::{contains_aggregate, is_rowid_alias, referenced_columns};
+use crate::sql::executor::select::ResultSet;
+use crate::sql::parser::select::SelectQuery;
+use crate::sql::parser::trigger::{is_keyword, tokens_to_string};
+
+/// A CHECK constraint, declared either on a column or on the whole table
+#[derive(PartialEq, Debug)]
+pub struct ParsedCheck {
+    /// Name given to the constraint with `CONSTRAINT <name>`, if any
+    pub name: Option<String>,
+    /// Expression every row must not evaluate to false
+    pub expr: Expr,
+}
+
+/// The `GENERATED ALWAYS AS (expr)` clause of a column, whose value is computed
+/// from the other columns of the row instead of being inserted
+#[derive(PartialEq, Debug, Clone)]
+pub struct ParsedGenerated {
+    /// Expression computing the value of the column
+    pub expr: Expr,
+    /// Value representing if the column was declared STORED, its value being written with the
+    /// row, rather than VIRTUAL, the default, its value being computed every time it is read
+    pub stored: bool,
+}
+
+/// A PRIMARY KEY or UNIQUE constraint declared at table level over several columns.
+/// Constraints over a single column are parsed as if they were declared on the column.
+#[derive(PartialEq, Debug)]
+pub struct ParsedKey {
+    /// Name given to the constraint with `CONSTRAINT <name>`, if any
+    pub name: Option<String>,
+    /// Columns making up the key, in key order
+    pub columns: Vec<String>,
+    /// Value representing if the key is the PRIMARY KEY of the table
+    pub is_primary: bool,
+}
+
+/// A FOREIGN KEY constraint, declared either with a REFERENCES clause on a column
+/// or with FOREIGN KEY on the whole table
+#[derive(PartialEq, Debug)]
+pub struct ParsedForeignKey {
+    /// Name given to the constraint with `CONSTRAINT <name>`, if any
+    pub name: Option<String>,
+    /// Columns of the child table holding the key
+    pub columns: Vec<String>,
+    /// Name of the parent table
+    pub parent_table: String,
+    /// Columns of the parent table the key refers to, empty for its PRIMARY KEY
+    pub parent_columns: Vec<String>,
+    /// Action taken on the child rows when their parent row is deleted
+    pub on_delete: ReferentialAction,
+    /// Action taken on the child rows when the key of their parent row changes
+    pub on_update: ReferentialAction,
+    /// Value representing if the constraint was declared DEFERRABLE INITIALLY DEFERRED,
+    /// being checked at COMMIT rather than at the end of every statement
+    pub deferred: bool,
+}
+
+impl ParsedForeignKey {
+    fn new(
+        name: &Option<Ident>,
+        columns: Vec<String>,
+        foreign_table: &ObjectName,
+        referred_columns: &[Ident],
+        on_delete: &Option<ReferentialAction>,
+        on_update: &Option<ReferentialAction>,
+    ) -> ParsedForeignKey {
+        ParsedForeignKey {
+            name: name.as_ref().map(|name| name.value.to_string()),
+            columns,
+            parent_table: foreign_table.to_string(),
+            parent_columns: referred_columns
+                .iter()
+                .map(|col| col.value.to_string())
+                .collect(),
+            on_delete: on_delete.clone().unwrap_or(ReferentialAction::NoAction),
+            on_update: on_update.clone().unwrap_or(ReferentialAction::NoAction),
+            deferred: false,
+        }
+    }
+
+    /// Checks that the key refers to as many parent columns as it has
+    pub fn validate(&self) -> Result<()> {
+        if !self.parent_columns.is_empty() && self.parent_columns.len() != self.columns.len() {
+            return Err(SQLRiteError::General(format!(
+                "number of columns in foreign key does not match the number of columns in the referenced table {}",
+                self.parent_table
+            )));
+        }
+        Ok(())
+    }
+}
 
 /// The schema for each SQL column in every table is represented by
 /// the following structure after parsed and tokenized
@@ -16,6 +115,243 @@ pub struct ParsedColumn {
     pub not_null: bool,
     /// Value representing if column was declared with the UNIQUE Constraint
     pub is_unique: bool,
+    /// CHECK constraints declared on the column
+    pub checks: Vec<ParsedCheck>,
+    /// Expression giving the value of the column when an INSERT omits it
+    pub default: Option<Expr>,
+    /// FOREIGN KEY declared on the column with a REFERENCES clause
+    pub foreign_key: Option<ParsedForeignKey>,
+    /// Value representing if the column was declared AUTOINCREMENT, so the ROWIDs
+    /// it is assigned are never reused
+    pub autoincrement: bool,
+    /// GENERATED ALWAYS AS clause of the column, `None` for ordinary columns
+    pub generated: Option<ParsedGenerated>,
+    /// Value representing if the type of the column was declared with the exact name INTEGER,
+    /// see `integer_columns`
+    pub declared_integer: bool,
+}
+
+impl ParsedColumn {
+    /// Parses a single column definition, as found in CREATE TABLE and
+    /// ALTER TABLE ... ADD COLUMN statements.
+    /// Types are given the affinity SQLite gives them, see `type_affinity`, and types
+    /// with none of the affinities supported are rejected.
+    pub fn new(col: &ColumnDef) -> Result<ParsedColumn> {
+        let name = col.name.value.to_string();
+
+        // Parsing each column for it data type
+        let datatype = match &col.data_type {
+            DataType::TinyInt(_) => "Integer",
+            DataType::SmallInt(_) => "Integer",
+            DataType::Int(_) => "Integer",
+            DataType::BigInt(_) => "Integer",
+            DataType::UnsignedTinyInt(_)
+            | DataType::UnsignedSmallInt(_)
+            | DataType::UnsignedInt(_)
+            | DataType::UnsignedBigInt(_) => "Integer",
+            DataType::Boolean => "Bool",
+            DataType::Custom(name) if name.to_string().to_uppercase() == "BOOL" => "Bool",
+            DataType::Text => "Text",
+            DataType::String => "Text",
+            DataType::Char(_) => "Text",
+            DataType::Varchar(_bytes) => "Text",
+            DataType::Nvarchar(_bytes) => "Text",
+            DataType::Clob(_bytes) => "Text",
+            DataType::Real => "Real",
+            DataType::Float(_precision) => "Real",
+            DataType::Double => "Real",
+            DataType::Decimal(_precision1, _precision2) => "Real",
+            DataType::Blob(_) => "Blob",
+            DataType::Binary(_) => "Blob",
+            DataType::Varbinary(_) => "Blob",
+            DataType::Bytea => "Blob",
+            data_type => match type_affinity(&data_type.to_string()) {
+                Some(datatype) => datatype,
+                None => {
+                    return Err(SQLRiteError::NotImplemented(format!(
+                        "Type {} of column {} is not supported.",
+                        data_type, name
+                    )))
+                }
+            },
+        };
+
+        // checking if column is PRIMARY KEY
+        let mut is_pk: bool = false;
+        // chekcing if column is UNIQUE
+        let mut is_unique: bool = false;
+        // chekcing if column is NULLABLE
+        let mut not_null: bool = false;
+        let mut checks: Vec<ParsedCheck> = vec![];
+        let mut default: Option<Expr> = None;
+        let mut foreign_key: Option<ParsedForeignKey> = None;
+        let mut autoincrement: bool = false;
+        for column_option in &col.options {
+            match &column_option.option {
+                // PRIMARY KEY and UNIQUE columns of any type are Indexed
+                ColumnOption::Unique { is_primary } => {
+                    is_pk = *is_primary;
+                    if *is_primary {
+                        not_null = true;
+                    }
+                    is_unique = true;
+                }
+                ColumnOption::DialectSpecific(tokens)
+                    if tokens
+                        .iter()
+                        .any(|token| is_keyword(token, "AUTOINCREMENT")) =>
+                {
+                    autoincrement = true;
+                }
+                ColumnOption::NotNull => {
+                    not_null = true;
+                }
+                ColumnOption::Default(expr) => {
+                    default = Some(expr.clone());
+                }
+                ColumnOption::ForeignKey {
+                    foreign_table,
+                    referred_columns,
+                    on_delete,
+                    on_update,
+                } => {
+                    foreign_key = Some(ParsedForeignKey::new(
+                        &column_option.name,
+                        vec![name.to_string()],
+                        foreign_table,
+                        referred_columns,
+                        on_delete,
+                        on_update,
+                    ));
+                }
+                ColumnOption::Check(expr) => {
+                    checks.push(ParsedCheck {
+                        name: column_option
+                            .name
+                            .as_ref()
+                            .map(|name| name.value.to_string()),
+                        expr: expr.clone(),
+                    });
+                }
+                _ => (),
+            };
+        }
+
+        Ok(ParsedColumn {
+            name,
+            datatype: datatype.to_string(),
+            is_pk,
+            not_null,
+            is_unique,
+            checks,
+            default,
+            foreign_key,
+            autoincrement,
+            generated: None,
+            declared_integer: false,
+        })
+    }
+
+    /// Builds a column of a table created with `CREATE TABLE ... AS SELECT`, its type
+    /// being inferred from the values of the result set: numbers of mixed types make a
+    /// REAL column, other mixes of types and columns holding only NULLs a TEXT column.
+    pub fn from_values(name: &str, values: &[&Value]) -> ParsedColumn {
+        let mut datatype: Option<&str> = None;
+        for value in values {
+            let value_type = match value {
+                Value::Null => continue,
+                Value::Integer(_) => "Integer",
+                Value::Real(_) => "Real",
+                Value::Text(_) => "Text",
+                Value::Bool(_) => "Bool",
+                Value::Blob(_) => "Blob",
+            };
+            datatype = Some(match (datatype, value_type) {
+                (None, value_type) => value_type,
+                (Some(current), value_type) if current == value_type => current,
+                (Some("Real"), "Integer" | "Bool") | (Some("Integer" | "Bool"), "Real") => "Real",
+                (Some("Integer"), "Bool") | (Some("Bool"), "Integer") => "Integer",
+                _ => "Text",
+            });
+        }
+
+        ParsedColumn {
+            name: name.to_string(),
+            datatype: datatype.unwrap_or("Text").to_string(),
+            is_pk: false,
+            not_null: false,
+            is_unique: false,
+            checks: vec![],
+            default: None,
+            foreign_key: None,
+            autoincrement: false,
+            generated: None,
+            declared_integer: false,
+        }
+    }
+
+    /// Builds the columns of a table holding a result set, like the tables created with
+    /// `CREATE TABLE ... AS SELECT`. Like SQLite, repeated column names are made unique
+    /// with a `:N` suffix.
+    pub fn from_result_set(result: &ResultSet) -> Vec<ParsedColumn> {
+        let mut columns: Vec<ParsedColumn> = vec![];
+        for (i, name) in result.columns.iter().enumerate() {
+            let mut column_name = name.to_string();
+            let mut suffix = 0;
+            while columns.iter().any(|col| col.name == column_name) {
+                suffix += 1;
+                column_name = format!("{}:{}", name, suffix);
+            }
+            let values: Vec<&Value> = result.rows.iter().map(|row| &row[i]).collect();
+            columns.push(ParsedColumn::from_values(&column_name, &values));
+        }
+        columns
+    }
+
+    /// Checks that only an INTEGER PRIMARY KEY column is declared AUTOINCREMENT
+    pub fn validate_autoincrement(&self) -> Result<()> {
+        if self.autoincrement && !(self.is_pk && self.declared_integer) {
+            return Err(SQLRiteError::General(String::from(
+                "AUTOINCREMENT is only allowed on an INTEGER PRIMARY KEY",
+            )));
+        }
+        Ok(())
+    }
+
+    /// Sets the GENERATED ALWAYS AS clause of the column, see `split_generated`, checking
+    /// that it comes with neither a DEFAULT nor a PRIMARY KEY and does not call aggregates
+    pub fn set_generated(&mut self, generated: ParsedGenerated) -> Result<()> {
+        if self.default.is_some() {
+            return Err(SQLRiteError::General(format!(
+                "cannot use DEFAULT on a generated column: {}",
+                self.name
+            )));
+        }
+        if self.is_pk {
+            return Err(SQLRiteError::General(String::from(
+                "generated columns cannot be part of the PRIMARY KEY",
+            )));
+        }
+        if contains_aggregate(&generated.expr) {
+            return Err(SQLRiteError::General(format!(
+                "misuse of aggregate function in generated column: {}",
+                self.name
+            )));
+        }
+        self.generated = Some(generated);
+        Ok(())
+    }
+
+    /// Checks that the DEFAULT of the column does not read any column,
+    /// since it is evaluated before the row exists
+    pub fn validate_default(&self) -> Result<()> {
+        match &self.default {
+            Some(default) if !referenced_columns(default).is_empty() => Err(SQLRiteError::General(
+                format!("default value of column [{}] is not constant", self.name),
+            )),
+            _ => Ok(()),
+        }
+    }
 }
 
 /// The following structure represents a CREATE TABLE query already parsed
@@ -27,10 +363,28 @@ pub struct CreateQuery {
     pub table_name: String,
     /// Vector of `ParsedColumn` type with column metadata information
     pub columns: Vec<ParsedColumn>,
+    /// CHECK constraints declared at table level
+    pub checks: Vec<ParsedCheck>,
+    /// PRIMARY KEY and UNIQUE constraints declared at table level over several columns
+    pub keys: Vec<ParsedKey>,
+    /// FOREIGN KEY constraints declared at table level
+    pub foreign_keys: Vec<ParsedForeignKey>,
+    /// Query of a `CREATE TABLE ... AS SELECT` statement, giving the columns and rows of the table
+    pub select: Option<SelectQuery>,
 }
 
 impl CreateQuery {
-    pub fn new(statement: &Statement) -> Result<CreateQuery> {
+    /// Builds the query from a parsed CREATE TABLE statement. sqlparser does not parse
+    /// DEFERRABLE clauses, `deferred` tells which foreign keys had one, see `split_deferrable`.
+    /// Nor GENERATED ALWAYS AS clauses, given by `generated` with the column they belong to,
+    /// see `split_generated`. Nor does it tell INT from INTEGER, `integer_columns` lists the
+    /// columns declared INTEGER, see `integer_columns`.
+    pub fn new(
+        statement: &Statement,
+        deferred: &[bool],
+        generated: &[(String, ParsedGenerated)],
+        integer_columns: &[String],
+    ) -> Result<CreateQuery> {
         match statement {
             // Confirming the Statement is sqlparser::ast:Statement::CreateTable
             Statement::CreateTable {
@@ -41,15 +395,28 @@ impl CreateQuery {
                 external: _external,
                 file_format: _file_format,
                 location: _location,
+                query,
                 ..
             } => {
                 let table_name = name;
                 let mut parsed_columns: Vec<ParsedColumn> = vec![];
 
+                // With `AS SELECT ...` the columns come from the result of the query
+                let select = match query {
+                    Some(_) if !columns.is_empty() || !_constraints.is_empty() => {
+                        return Err(SQLRiteError::NotImplemented(
+                            "CREATE TABLE AS SELECT with column definitions is not supported."
+                                .to_string(),
+                        ))
+                    }
+                    Some(query) => Some(SelectQuery::from_query(query)?),
+                    None => None,
+                };
+
                 // Iterating over the columns returned form the Parser::parse:sql
                 // in the mod sql
                 for col in columns {
-                    let name = col.name.to_string();
+                    let name = col.name.value.to_string();
 
                     // Checks if columm already added to parsed_columns, if so, returns an error
                     if parsed_columns.iter().any(|col| col.name == name) {
@@ -59,76 +426,185 @@ impl CreateQuery {
                         )));
                     }
 
-                    // Parsing each column for it data type
-                    // For now only accepting basic data types
-                    let datatype = match &col.data_type {
-                        DataType::SmallInt(_) => "Integer",
-                        DataType::Int(_) => "Integer",
-                        DataType::BigInt(_) => "Integer",
-                        DataType::Boolean => "Bool",
-                        DataType::Text => "Text",
-                        DataType::Varchar(_bytes) => "Text",
-                        DataType::Real => "Real",
-                        DataType::Float(_precision) => "Real",
-                        DataType::Double => "Real",
-                        DataType::Decimal(_precision1, _precision2) => "Real",
-                        _ => {
-                            eprintln!("not matched on custom type");
-                            "Invalid"
-                        }
-                    };
-
-                    // checking if column is PRIMARY KEY
-                    let mut is_pk: bool = false;
-                    // chekcing if column is UNIQUE
-                    let mut is_unique: bool = false;
-                    // chekcing if column is NULLABLE
-                    let mut not_null: bool = false;
-                    for column_option in &col.options {
-                        match column_option.option {
-                            ColumnOption::Unique { is_primary } => {
-                                // For now, only Integer and Text types can be PRIMERY KEY and Unique
-                                // Therefore Indexed.
-                                if datatype != "Real" && datatype != "Bool" {
-                                    is_pk = is_primary;
-                                    if is_primary {
-                                        // Checks if table being created already has a PRIMARY KEY, if so, returns an error
-                                        if parsed_columns.iter().any(|col| col.is_pk == true) {
-                                            return Err(SQLRiteError::Internal(format!(
-                                                "Table '{}' has more than one primary key",
-                                                &table_name
-                                            )));
-                                        }
-                                        not_null = true;
-                                    }
-                                    is_unique = true;
+                    let mut parsed_column = ParsedColumn::new(col)?;
+                    parsed_column.declared_integer = integer_columns.contains(&name);
+                    if let Some((_, clause)) = generated.iter().find(|(col, _)| *col == name) {
+                        parsed_column.set_generated(clause.clone())?;
+                    }
+                    parsed_column.validate_default()?;
+                    parsed_column.validate_autoincrement()?;
+
+                    // Checks if table being created already has a PRIMARY KEY, if so, returns an error
+                    if parsed_column.is_pk && parsed_columns.iter().any(|col| col.is_pk) {
+                        return Err(SQLRiteError::Internal(format!(
+                            "Table '{}' has more than one primary key",
+                            &table_name
+                        )));
+                    }
+
+                    parsed_columns.push(parsed_column);
+                }
+
+                let mut checks: Vec<ParsedCheck> = vec![];
+                let mut keys: Vec<ParsedKey> = vec![];
+                let mut foreign_keys: Vec<ParsedForeignKey> = vec![];
+                for constraint in _constraints {
+                    match constraint {
+                        TableConstraint::Check { name, expr } => checks.push(ParsedCheck {
+                            name: name.as_ref().map(|name| name.value.to_string()),
+                            expr: (**expr).clone(),
+                        }),
+                        TableConstraint::Unique {
+                            name,
+                            columns,
+                            is_primary,
+                        } => {
+                            let columns: Vec<String> =
+                                columns.iter().map(|col| col.value.to_string()).collect();
+                            for col_name in &columns {
+                                if !parsed_columns.iter().any(|col| col.name == *col_name) {
+                                    return Err(SQLRiteError::General(format!(
+                                        "no such column: {}",
+                                        col_name
+                                    )));
                                 }
                             }
-                            ColumnOption::NotNull => {
-                                not_null = true;
+                            if *is_primary
+                                && (parsed_columns.iter().any(|col| col.is_pk)
+                                    || keys.iter().any(|key| key.is_primary))
+                            {
+                                return Err(SQLRiteError::Internal(format!(
+                                    "Table '{}' has more than one primary key",
+                                    &table_name
+                                )));
+                            }
+                            // PRIMARY KEY columns are NOT NULL, like the ones declared on a column
+                            if *is_primary {
+                                for col in parsed_columns
+                                    .iter_mut()
+                                    .filter(|col| columns.contains(&col.name))
+                                {
+                                    col.is_pk = true;
+                                    col.not_null = true;
+                                }
+                            }
+                            if columns.len() == 1 {
+                                if let Some(col) =
+                                    parsed_columns.iter_mut().find(|col| col.name == columns[0])
+                                {
+                                    col.is_unique = true;
+                                }
+                            } else {
+                                keys.push(ParsedKey {
+                                    name: name.as_ref().map(|name| name.value.to_string()),
+                                    columns,
+                                    is_primary: *is_primary,
+                                });
                             }
-                            _ => (),
-                        };
+                        }
+                        TableConstraint::ForeignKey {
+                            name,
+                            columns,
+                            foreign_table,
+                            referred_columns,
+                            on_delete,
+                            on_update,
+                        } => foreign_keys.push(ParsedForeignKey::new(
+                            name,
+                            columns.iter().map(|col| col.value.to_string()).collect(),
+                            foreign_table,
+                            referred_columns,
+                            on_delete,
+                            on_update,
+                        )),
                     }
+                }
 
-                    parsed_columns.push(ParsedColumn {
-                        name,
-                        datatype: datatype.to_string(),
-                        is_pk,
-                        not_null,
-                        is_unique,
-                    });
+                // CHECK constraints can read any column of the table, whatever they are declared on
+                for check in parsed_columns
+                    .iter()
+                    .flat_map(|col| col.checks.iter())
+                    .chain(checks.iter())
+                {
+                    for col_name in referenced_columns(&check.expr) {
+                        if !is_rowid_alias(&col_name)
+                            && !parsed_columns.iter().any(|col| col.name == col_name)
+                        {
+                            return Err(SQLRiteError::General(format!(
+                                "no such column: {}",
+                                col_name
+                            )));
+                        }
+                    }
                 }
 
-                // TODO: Handle constraints,
-                // Default value and others.
-                for constraint in _constraints {
-                    println!("{:?}", constraint);
+                // Generated columns can read any other column, but not in a loop
+                for col_name in parsed_columns
+                    .iter()
+                    .filter_map(|col| col.generated.as_ref())
+                    .flat_map(|generated| referenced_columns(&generated.expr))
+                {
+                    if !is_rowid_alias(&col_name)
+                        && !parsed_columns.iter().any(|col| col.name == col_name)
+                    {
+                        return Err(SQLRiteError::General(format!(
+                            "no such column: {}",
+                            col_name
+                        )));
+                    }
+                }
+                let columns: Vec<(&str, Option<&Expr>)> = parsed_columns
+                    .iter()
+                    .map(|col| {
+                        (
+                            col.name.as_str(),
+                            col.generated.as_ref().map(|generated| &generated.expr),
+                        )
+                    })
+                    .collect();
+                generated_order(&columns)?;
+                if keys.iter().any(|key| {
+                    key.is_primary
+                        && parsed_columns
+                            .iter()
+                            .any(|col| col.generated.is_some() && key.columns.contains(&col.name))
+                }) {
+                    return Err(SQLRiteError::General(String::from(
+                        "generated columns cannot be part of the PRIMARY KEY",
+                    )));
+                }
+
+                // Column constraints come before table constraints in a CREATE TABLE statement,
+                // which is the order `deferred` lists the foreign keys in
+                let mut deferred = deferred.iter();
+                let column_names: Vec<String> = parsed_columns
+                    .iter()
+                    .map(|col| col.name.to_string())
+                    .collect();
+                for foreign_key in parsed_columns
+                    .iter_mut()
+                    .filter_map(|col| col.foreign_key.as_mut())
+                    .chain(foreign_keys.iter_mut())
+                {
+                    foreign_key.validate()?;
+                    for col_name in &foreign_key.columns {
+                        if !column_names.contains(col_name) {
+                            return Err(SQLRiteError::General(format!(
+                                "unknown column \"{}\" in foreign key definition",
+                                col_name
+                            )));
+                        }
+                    }
+                    foreign_key.deferred = deferred.next().copied().unwrap_or(false);
                 }
 
                 return Ok(CreateQuery {
                     table_name: table_name.to_string(),
                     columns: parsed_columns,
+                    checks,
+                    keys,
+                    foreign_keys,
+                    select,
                 });
             }
 
@@ -136,3 +612,315 @@ impl CreateQuery {
         }
     }
 }
+
+/// Removes the `[NOT] DEFERRABLE [INITIALLY DEFERRED | INITIALLY IMMEDIATE]` clauses of the
+/// foreign keys of a statement, since sqlparser does not support them.
+/// Returns the statement without them and, for every REFERENCES clause in the order they
+/// appear, whether the foreign key is DEFERRABLE INITIALLY DEFERRED.
+pub fn split_deferrable(query: &str) -> Result<(String, Vec<bool>)> {
+    let dialect = SQLiteDialect {};
+    let tokens = Tokenizer::new(&dialect, query)
+        .tokenize()
+        .map_err(ParserError::from)?;
+    // Positions of the tokens that are not whitespace
+    let words: Vec<usize> = (0..tokens.len())
+        .filter(|i| !matches!(tokens[*i], Token::Whitespace(_)))
+        .collect();
+
+    let mut deferred: Vec<bool> = vec![];
+    let mut removed: Vec<usize> = vec![];
+    let mut w = 0;
+    while w < words.len() {
+        let token = &tokens[words[w]];
+        if is_keyword(token, "REFERENCES") {
+            deferred.push(false);
+        }
+        let negated = is_keyword(token, "NOT")
+            && words
+                .get(w + 1)
+                .is_some_and(|i| is_keyword(&tokens[*i], "DEFERRABLE"));
+        if !negated && !is_keyword(token, "DEFERRABLE") {
+            w += 1;
+            continue;
+        }
+
+        let mut end = if negated { w + 2 } else { w + 1 };
+        let mut initially_deferred = false;
+        if words
+            .get(end)
+            .is_some_and(|i| is_keyword(&tokens[*i], "INITIALLY"))
+        {
+            match words.get(end + 1).map(|i| &tokens[*i]) {
+                Some(token) if is_keyword(token, "DEFERRED") => initially_deferred = true,
+                Some(token) if is_keyword(token, "IMMEDIATE") => (),
+                _ => {
+                    return Err(SQLRiteError::SqlError(ParserError::ParserError(
+                        "Expected DEFERRED or IMMEDIATE after INITIALLY".to_string(),
+                    )))
+                }
+            }
+            end += 2;
+        }
+        match deferred.last_mut() {
+            Some(last) => *last = !negated && initially_deferred,
+            None => {
+                return Err(SQLRiteError::SqlError(ParserError::ParserError(
+                    "DEFERRABLE must follow a REFERENCES clause".to_string(),
+                )))
+            }
+        }
+        removed.extend(words[w]..words.get(end).copied().unwrap_or(tokens.len()));
+        w = end;
+    }
+
+    if removed.is_empty() {
+        return Ok((query.to_string(), deferred));
+    }
+    let kept: Vec<Token> = tokens
+        .into_iter()
+        .enumerate()
+        .filter(|(i, _)| !removed.contains(i))
+        .map(|(_, token)| token)
+        .collect();
+    Ok((tokens_to_string(&kept), deferred))
+}
+
+/// Returns the positions of the generated columns of a table, ordered so every one comes after
+/// the generated columns its expression reads, which is the order they are computed in.
+/// `columns` gives the name of every column of the table and its expression if it is generated.
+/// Fails if generated columns read each other in a loop.
+pub fn generated_order(columns: &[(&str, Option<&Expr>)]) -> Result<Vec<usize>> {
+    let reads: Vec<Vec<usize>> = columns
+        .iter()
+        .map(|(_, expr)| match expr {
+            Some(expr) => referenced_columns(expr)
+                .iter()
+                .filter_map(|col_name| columns.iter().position(|(name, _)| name == col_name))
+                .filter(|i| columns[*i].1.is_some())
+                .collect(),
+            None => vec![],
+        })
+        .collect();
+    let mut order: Vec<usize> = vec![];
+    let mut pending: Vec<usize> = (0..columns.len())
+        .filter(|i| columns[*i].1.is_some())
+        .collect();
+    while !pending.is_empty() {
+        let ready: Vec<usize> = pending
+            .iter()
+            .copied()
+            .filter(|i| reads[*i].iter().all(|read| order.contains(read)))
+            .collect();
+        if ready.is_empty() {
+            return Err(SQLRiteError::General(format!(
+                "generated column loop on \"{}\"",
+                columns[pending[0]].0
+            )));
+        }
+        pending.retain(|i| !ready.contains(i));
+        order.extend(ready);
+    }
+    Ok(order)
+}
+
+/// Returns the type of the columns declared with a type name sqlparser does not know,
+/// following the rules SQLite gives them an affinity by, in order:
+/// (Determination Of Column Affinity)[https://www.sqlite.org/datatype3.html#determination_of_column_affinity].
+/// Returns `None` for the NUMERIC affinity, DATETIME for instance, which has no column type here.
+fn type_affinity(type_name: &str) -> Option<&'static str> {
+    let type_name = type_name.to_uppercase();
+    let contains = |parts: &[&str]| parts.iter().any(|part| type_name.contains(part));
+    if contains(&["INT"]) {
+        Some("Integer")
+    } else if contains(&["CHAR", "CLOB", "TEXT"]) {
+        Some("Text")
+    } else if contains(&["BLOB"]) {
+        Some("Blob")
+    } else if contains(&["REAL", "FLOA", "DOUB"]) {
+        Some("Real")
+    } else {
+        None
+    }
+}
+
+/// Returns the columns of a CREATE TABLE statement declared with the type name INTEGER.
+/// sqlparser parses INT, INTEGER and BIGINT alike, while like in SQLite only a PRIMARY KEY
+/// declared INTEGER is an alias of the ROWID.
+pub fn integer_columns(query: &str) -> Result<Vec<String>> {
+    let dialect = SQLiteDialect {};
+    let tokens = Tokenizer::new(&dialect, query)
+        .tokenize()
+        .map_err(ParserError::from)?;
+    let words: Vec<&Token> = tokens
+        .iter()
+        .filter(|token| !matches!(token, Token::Whitespace(_)))
+        .collect();
+    match words.as_slice() {
+        [create, table, ..] if is_keyword(create, "CREATE") && is_keyword(table, "TABLE") => (),
+        _ => return Ok(vec![]),
+    }
+
+    // Column definitions start after the opening parenthesis and every comma at depth 1
+    let mut columns: Vec<String> = vec![];
+    let mut depth = 0;
+    for (w, token) in words.iter().enumerate() {
+        match token {
+            Token::LParen => depth += 1,
+            Token::RParen => depth -= 1,
+            _ => (),
+        }
+        if depth != 1 || !matches!(token, Token::LParen | Token::Comma) {
+            continue;
+        }
+        if let (Some(Token::Word(name)), Some(datatype)) = (words.get(w + 1), words.get(w + 2)) {
+            if is_keyword(datatype, "INTEGER") {
+                columns.push(name.value.to_string());
+            }
+        }
+    }
+    Ok(columns)
+}
+
+/// Removes the `[GENERATED ALWAYS] AS (expr) [VIRTUAL | STORED]` clauses of the columns of a
+/// CREATE TABLE or ALTER TABLE ... ADD COLUMN statement, since sqlparser does not support them.
+/// Returns the statement without them and every clause with the name of its column.
+pub fn split_generated(query: &str) -> Result<(String, Vec<(String, ParsedGenerated)>)> {
+    let dialect = SQLiteDialect {};
+    let tokens = Tokenizer::new(&dialect, query)
+        .tokenize()
+        .map_err(ParserError::from)?;
+    // Positions of the tokens that are not whitespace
+    let words: Vec<usize> = (0..tokens.len())
+        .filter(|i| !matches!(tokens[*i], Token::Whitespace(_)))
+        .collect();
+    let word = |w: usize| words.get(w).map(|i| &tokens[*i]);
+
+    // Column definitions are found within the parentheses of CREATE TABLE,
+    // and after ADD [COLUMN] in ALTER TABLE
+    let is_table = word(1).is_some_and(|token| is_keyword(token, "TABLE"));
+    let definitions_depth = match word(0) {
+        Some(token) if is_table && is_keyword(token, "CREATE") => 1,
+        Some(token) if is_table && is_keyword(token, "ALTER") => 0,
+        _ => return Ok((query.to_string(), vec![])),
+    };
+
+    let mut generated: Vec<(String, ParsedGenerated)> = vec![];
+    let mut removed: Vec<usize> = vec![];
+    let mut column: Option<String> = None;
+    let mut expect_column = false;
+    let mut depth = 0;
+    let mut w = 0;
+    while w < words.len() {
+        let token = &tokens[words[w]];
+        match token {
+            Token::LParen => depth += 1,
+            Token::RParen => depth -= 1,
+            _ => (),
+        }
+        if depth != definitions_depth {
+            w += 1;
+            continue;
+        }
+        match token {
+            Token::LParen | Token::Comma => expect_column = definitions_depth == 1,
+            Token::Word(_) if definitions_depth == 0 && is_keyword(token, "ADD") => {
+                expect_column = true;
+                // ADD COLUMN <name>, where COLUMN is optional
+                if word(w + 1).is_some_and(|token| is_keyword(token, "COLUMN")) {
+                    w += 1;
+                }
+            }
+            Token::Word(word) if expect_column => {
+                column = Some(word.value.to_string());
+                expect_column = false;
+                // Like in SQLite, GENERATED can name a column
+                w += 1;
+                continue;
+            }
+            _ => (),
+        }
+
+        let start = w;
+        let mut open = w;
+        if is_keyword(token, "GENERATED") {
+            if !word(w + 1).is_some_and(|token| is_keyword(token, "ALWAYS"))
+                || !word(w + 2).is_some_and(|token| is_keyword(token, "AS"))
+            {
+                return Err(SQLRiteError::SqlError(ParserError::ParserError(
+                    "Expected ALWAYS AS after GENERATED".to_string(),
+                )));
+            }
+            open = w + 3;
+        } else if is_keyword(token, "AS") && column.is_some() && word(w + 1) == Some(&Token::LParen)
+        {
+            open = w + 1;
+        }
+        if open == start {
+            w += 1;
+            continue;
+        }
+        if word(open) != Some(&Token::LParen) {
+            return Err(SQLRiteError::SqlError(ParserError::ParserError(
+                "Expected ( after GENERATED ALWAYS AS".to_string(),
+            )));
+        }
+
+        // The expression runs up to the matching closing parenthesis
+        let mut close = open + 1;
+        let mut nesting = 1;
+        while nesting > 0 {
+            match word(close) {
+                Some(Token::LParen) => nesting += 1,
+                Some(Token::RParen) => nesting -= 1,
+                Some(_) => (),
+                None => {
+                    return Err(SQLRiteError::SqlError(ParserError::ParserError(
+                        "Expected ) after the expression of a generated column".to_string(),
+                    )))
+                }
+            }
+            close += 1;
+        }
+        let expr_tokens: Vec<Token> = tokens[words[open] + 1..words[close - 1]].to_vec();
+        let mut parser = Parser::new(expr_tokens, &dialect);
+        let expr = parser.parse_expr()?;
+        if parser.peek_token() != Token::EOF {
+            return Err(SQLRiteError::SqlError(ParserError::ParserError(format!(
+                "Expected ) after the expression of a generated column, found: {}",
+                parser.peek_token()
+            ))));
+        }
+
+        let mut end = close;
+        let stored = match word(end) {
+            Some(token) if is_keyword(token, "STORED") => {
+                end += 1;
+                true
+            }
+            Some(token) if is_keyword(token, "VIRTUAL") => {
+                end += 1;
+                false
+            }
+            _ => false,
+        };
+        let column_name = column.clone().ok_or_else(|| {
+            SQLRiteError::SqlError(ParserError::ParserError(
+                "GENERATED ALWAYS AS must follow a column name".to_string(),
+            ))
+        })?;
+        generated.push((column_name, ParsedGenerated { expr, stored }));
+        removed.extend(words[start]..words.get(end).copied().unwrap_or(tokens.len()));
+        w = end;
+    }
+
+    if generated.is_empty() {
+        return Ok((query.to_string(), generated));
+    }
+    let kept: Vec<Token> = tokens
+        .into_iter()
+        .enumerate()
+        .filter(|(i, _)| !removed.contains(i))
+        .map(|(_, token)| token)
+        .collect();
+    Ok((tokens_to_string(&kept), generated))
+}
@@ -0,0 +1,35 @@
+use sqlparser::ast::{Expr, SelectItem, Statement};
+
+use crate::error::{Result, SQLRiteError};
+
+/// The following structure represents a DELETE query already parsed
+/// and broken down into the table name and the optional WHERE clause
+#[derive(Debug)]
+pub struct DeleteQuery {
+    /// Name of the table rows are deleted from
+    pub table_name: String,
+    /// WHERE clause, every row is deleted when there is none
+    pub selection: Option<Expr>,
+    /// RETURNING clause, computed over the deleted rows as they were before the DELETE
+    pub returning: Option<Vec<SelectItem>>,
+}
+
+impl DeleteQuery {
+    /// Builds the query from a parsed DELETE statement. sqlparser does not parse RETURNING
+    /// clauses, `returning` is the one removed from the statement, see `split_returning`.
+    pub fn new(statement: &Statement, returning: Option<Vec<SelectItem>>) -> Result<DeleteQuery> {
+        match statement {
+            Statement::Delete {
+                table_name,
+                selection,
+            } => Ok(DeleteQuery {
+                table_name: table_name.to_string(),
+                selection: selection.clone(),
+                returning,
+            }),
+            _ => Err(SQLRiteError::Internal(
+                "Error parsing delete query".to_string(),
+            )),
+        }
+    }
+}
@@ -0,0 +1,57 @@
+use sqlparser::ast::{ObjectType, Statement};
+
+use crate::error::{Result, SQLRiteError};
+
+/// The kind of schema object a DROP statement removes
+#[derive(Debug, PartialEq)]
+pub enum DropObject {
+    Table,
+    Index,
+    View,
+}
+
+/// The following structure represents a DROP TABLE, DROP INDEX or DROP VIEW query already parsed
+/// and broken down into the kind of object and the `Vec<String>` of names to drop
+#[derive(Debug)]
+pub struct DropQuery {
+    /// Kind of object being dropped
+    pub object: DropObject,
+    /// Value representing if the statement was declared with IF EXISTS
+    pub if_exists: bool,
+    /// Names of the objects to drop
+    pub names: Vec<String>,
+}
+
+impl DropQuery {
+    pub fn new(statement: &Statement) -> Result<DropQuery> {
+        match statement {
+            Statement::Drop {
+                object_type,
+                if_exists,
+                names,
+                ..
+            } => {
+                let object = match object_type {
+                    ObjectType::Table => DropObject::Table,
+                    ObjectType::Index => DropObject::Index,
+                    ObjectType::View => DropObject::View,
+                    _ => {
+                        return Err(SQLRiteError::NotImplemented(format!(
+                            "DROP {} is not supported yet.",
+                            object_type
+                        )))
+                    }
+                };
+
+                Ok(DropQuery {
+                    object,
+                    if_exists: *if_exists,
+                    names: names.iter().map(|name| name.to_string()).collect(),
+                })
+            }
+            _ => Err(SQLRiteError::Internal(
+                "Error parsing drop query".to_string(),
+            )),
+        }
+    }
+}
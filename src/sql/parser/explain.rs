@@ -0,0 +1,76 @@
+use sqlparser::ast::Statement;
+use sqlparser::dialect::SQLiteDialect;
+use sqlparser::parser::ParserError;
+use sqlparser::tokenizer::{Token, Tokenizer};
+
+use crate::error::{Result, SQLRiteError};
+use crate::sql::parser::trigger::{is_keyword, tokens_to_string};
+
+/// The following structure represents an EXPLAIN or EXPLAIN QUERY PLAN query already
+/// parsed and broken down into the explained statement
+#[derive(Debug)]
+pub struct ExplainQuery {
+    /// Value representing if the query was declared EXPLAIN QUERY PLAN
+    pub query_plan: bool,
+    /// Statement being explained
+    pub statement: Statement,
+}
+
+impl ExplainQuery {
+    /// Builds the query from a parsed EXPLAIN statement. sqlparser does not parse
+    /// EXPLAIN QUERY PLAN, see `split_explain_query_plan` for that.
+    pub fn new(statement: &Statement, query_plan: bool) -> Result<ExplainQuery> {
+        match statement {
+            Statement::Explain {
+                analyze,
+                verbose,
+                statement,
+                ..
+            } => {
+                if *analyze || *verbose {
+                    return Err(SQLRiteError::NotImplemented(
+                        "EXPLAIN ANALYZE and EXPLAIN VERBOSE are not supported.".to_string(),
+                    ));
+                }
+                Ok(ExplainQuery {
+                    query_plan,
+                    statement: (**statement).clone(),
+                })
+            }
+            _ => Err(SQLRiteError::Internal(
+                "Error parsing explain query".to_string(),
+            )),
+        }
+    }
+}
+
+/// Removes the `QUERY PLAN` words of an `EXPLAIN QUERY PLAN <stmt>` statement,
+/// since sqlparser only supports plain `EXPLAIN <stmt>`.
+/// Returns the statement without them and whether they were there.
+pub fn split_explain_query_plan(query: &str) -> Result<(String, bool)> {
+    let dialect = SQLiteDialect {};
+    let tokens = Tokenizer::new(&dialect, query)
+        .tokenize()
+        .map_err(ParserError::from)?;
+
+    // Positions of the first three tokens that are not whitespace
+    let words: Vec<(usize, &Token)> = tokens
+        .iter()
+        .enumerate()
+        .filter(|(_, token)| !matches!(token, Token::Whitespace(_)))
+        .take(3)
+        .collect();
+    let is_word =
+        |i: usize, keyword: &str| words.get(i).is_some_and(|(_, t)| is_keyword(t, keyword));
+    if !(is_word(0, "EXPLAIN") && is_word(1, "QUERY") && is_word(2, "PLAN")) {
+        return Ok((query.to_string(), false));
+    }
+    let (query_position, plan_position) = (words[1].0, words[2].0);
+    let kept: Vec<Token> = tokens
+        .into_iter()
+        .enumerate()
+        .filter(|(i, _)| *i != query_position && *i != plan_position)
+        .map(|(_, token)| token)
+        .collect();
+    Ok((tokens_to_string(&kept), true))
+}
@@ -0,0 +1,129 @@
+use sqlparser::ast::{Expr, Statement};
+use sqlparser::dialect::SQLiteDialect;
+use sqlparser::keywords::Keyword;
+use sqlparser::parser::{Parser, ParserError};
+use sqlparser::tokenizer::{Token, Tokenizer};
+
+use crate::error::{Result, SQLRiteError};
+use crate::sql::parser::trigger::tokens_to_string;
+
+/// The following structure represents a CREATE INDEX query already parsed
+/// and broken down into the index and table names and the indexed expressions
+#[derive(Debug)]
+pub struct CreateIndexQuery {
+    /// Name of the index
+    pub index_name: String,
+    /// Name of the table being indexed
+    pub table_name: String,
+    /// Indexed columns or expressions, in key order. A plain column is an `Expr::Identifier`
+    pub expressions: Vec<Expr>,
+    /// WHERE clause of a partial index
+    pub predicate: Option<Expr>,
+    /// Value representing if the index was declared UNIQUE
+    pub is_unique: bool,
+    /// Value representing if the statement was declared with IF NOT EXISTS
+    pub if_not_exists: bool,
+}
+
+impl CreateIndexQuery {
+    /// Builds the query from a parsed CREATE INDEX statement. sqlparser does not parse
+    /// the WHERE clause of partial indexes, see `split_index_predicate` for that.
+    pub fn new(statement: &Statement, predicate: Option<Expr>) -> Result<CreateIndexQuery> {
+        match statement {
+            Statement::CreateIndex {
+                name,
+                table_name,
+                columns,
+                unique,
+                if_not_exists,
+            } => {
+                let mut expressions: Vec<Expr> = vec![];
+                for column in columns {
+                    if let Expr::Identifier(ident) = &column.expr {
+                        if expressions.contains(&column.expr) {
+                            return Err(SQLRiteError::General(format!(
+                                "Column {} appears more than once in index {}",
+                                ident.value, name
+                            )));
+                        }
+                    }
+                    expressions.push(column.expr.clone());
+                }
+
+                Ok(CreateIndexQuery {
+                    index_name: name.to_string(),
+                    table_name: table_name.to_string(),
+                    expressions,
+                    predicate,
+                    is_unique: *unique,
+                    if_not_exists: *if_not_exists,
+                })
+            }
+            _ => Err(SQLRiteError::Internal(
+                "Error parsing create index query".to_string(),
+            )),
+        }
+    }
+}
+
+/// Splits the WHERE clause off a `CREATE [UNIQUE] INDEX ... WHERE <expr>` statement,
+/// since sqlparser does not support partial indexes.
+/// Returns the statement without the clause and the parsed predicate, or the query
+/// unchanged and `None` if it is not a CREATE INDEX statement with a WHERE clause.
+pub fn split_index_predicate(query: &str) -> Result<(String, Option<Expr>)> {
+    let dialect = SQLiteDialect {};
+    let tokens = Tokenizer::new(&dialect, query)
+        .tokenize()
+        .map_err(ParserError::from)?;
+
+    let keywords: Vec<Keyword> = tokens
+        .iter()
+        .filter_map(|token| match token {
+            Token::Word(word) => Some(word.keyword),
+            Token::Whitespace(_) => None,
+            _ => Some(Keyword::NoKeyword),
+        })
+        .take(3)
+        .collect();
+    let is_create_index = matches!(
+        keywords.as_slice(),
+        [Keyword::CREATE, Keyword::INDEX, ..] | [Keyword::CREATE, Keyword::UNIQUE, Keyword::INDEX]
+    );
+    if !is_create_index {
+        return Ok((query.to_string(), None));
+    }
+
+    // The WHERE keyword of a partial index can only appear after the column list,
+    // outside of any parenthesis
+    let mut depth = 0;
+    let mut where_position = None;
+    for (i, token) in tokens.iter().enumerate() {
+        match token {
+            Token::LParen => depth += 1,
+            Token::RParen => depth -= 1,
+            Token::Word(word) if word.keyword == Keyword::WHERE && depth == 0 => {
+                where_position = Some(i);
+                break;
+            }
+            _ => (),
+        }
+    }
+    let where_position = match where_position {
+        Some(i) => i,
+        None => return Ok((query.to_string(), None)),
+    };
+
+    let statement = tokens_to_string(&tokens[..where_position]);
+    let mut parser = Parser::new(tokens[where_position + 1..].to_vec(), &dialect);
+    let predicate = parser.parse_expr()?;
+    // Allowing a trailing semicolon, like the rest of the statement
+    while parser.consume_token(&Token::SemiColon) {}
+    let token = parser.peek_token();
+    if token != Token::EOF {
+        return Err(SQLRiteError::SqlError(ParserError::ParserError(format!(
+            "Expected end of statement, found: {}",
+            token
+        ))));
+    }
+    Ok((statement, Some(predicate)))
+}
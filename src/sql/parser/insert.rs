@@ -1,34 +1,88 @@
-use sqlparser::ast::{Expr, Query, SetExpr, Statement, Value, Values};
+use sqlparser::ast::{Expr, Query, SelectItem, SetExpr, SqliteOnConflict, Statement, Values};
+use sqlparser::dialect::SQLiteDialect;
+use sqlparser::keywords::Keyword;
+use sqlparser::parser::{Parser, ParserError};
+use sqlparser::tokenizer::{Token, Tokenizer};
 
 use crate::error::{Result, SQLRiteError};
+use crate::sql::db::value::Value;
+use crate::sql::executor::expr::{evaluate, NoRow};
+use crate::sql::parser::select::SelectQuery;
+use crate::sql::parser::trigger::{is_keyword, tokens_to_string};
 
 /// The following structure represents a INSERT query already parsed
 /// and broken down into `table_name` a `Vec<String>` representing the `Columns`
-/// and `Vec<Vec<String>>` representing the list of `Rows` to be inserted
-#[derive(Debug)]
+/// and `Vec<Vec<Value>>` representing the list of `Rows` to be inserted.
+/// `columns` is `None` when the query has no column list, the values then being
+/// for every column of the table in declaration order.
+/// With `INSERT ... SELECT` the rows are the result of `select`, run when the query executes.
+#[derive(Debug, Clone)]
 pub struct InsertQuery {
     pub table_name: String,
-    pub columns: Vec<String>,
-    pub rows: Vec<Vec<String>>,
+    pub columns: Option<Vec<String>>,
+    pub rows: Vec<Vec<Value>>,
+    pub select: Option<SelectQuery>,
+    /// Conflict resolution given with `INSERT OR ...` or `REPLACE`, ABORT by default
+    pub on_conflict: SqliteOnConflict,
+    /// `ON CONFLICT` clause, which takes precedence over `on_conflict` for the rows it matches
+    pub upsert: Option<Upsert>,
+    /// RETURNING clause, computed over the inserted or updated rows
+    pub returning: Option<Vec<SelectItem>>,
+}
+
+/// What the `ON CONFLICT` clause of an INSERT statement does with an existing row
+/// the row being inserted conflicts with
+#[derive(Debug, Clone)]
+pub enum UpsertAction {
+    /// `DO NOTHING`, the row being inserted is skipped
+    Nothing,
+    /// `DO UPDATE SET ... [WHERE ...]`, the existing row is updated instead. The expressions
+    /// read the row being inserted through the `excluded` table name.
+    Update {
+        assignments: Vec<(String, Expr)>,
+        selection: Option<Expr>,
+    },
+}
+
+/// The `ON CONFLICT` clause of an INSERT statement, making it an UPSERT
+#[derive(Debug, Clone)]
+pub struct Upsert {
+    /// Columns of the PRIMARY KEY or UNIQUE constraint the clause applies to, empty for any
+    pub target: Vec<String>,
+    /// What is done with the conflicting row
+    pub action: UpsertAction,
 }
 
 impl InsertQuery {
-    pub fn new(statement: &Statement) -> Result<InsertQuery> {
+    /// Builds the query from a parsed INSERT statement. sqlparser does not parse ON CONFLICT
+    /// nor RETURNING clauses, `upsert` and `returning` are the ones removed from the statement,
+    /// see `split_upsert` and `split_returning`.
+    pub fn new(
+        statement: &Statement,
+        upsert: Option<Upsert>,
+        returning: Option<Vec<SelectItem>>,
+    ) -> Result<InsertQuery> {
         #[allow(unused_assignments)]
         let mut tname: Option<String> = None;
-        let mut columns: Vec<String> = vec![];
-        let mut all_values: Vec<Vec<String>> = vec![];
+        let mut columns: Option<Vec<String>> = None;
+        let mut all_values: Vec<Vec<Value>> = vec![];
+        let mut select: Option<SelectQuery> = None;
+        let mut on_conflict = SqliteOnConflict::Abort;
 
         match statement {
             Statement::Insert {
                 table_name,
                 columns: cols,
                 source,
+                or,
                 ..
             } => {
                 tname = Some(table_name.to_string());
-                for col in cols {
-                    columns.push(col.to_string());
+                if let Some(or) = or {
+                    on_conflict = or.clone();
+                }
+                if !cols.is_empty() {
+                    columns = Some(cols.iter().map(|col| col.value.to_string()).collect());
                 }
 
                 match &**source {
@@ -44,34 +98,15 @@ impl InsertQuery {
                             #[allow(irrefutable_let_patterns)]
                             if let Values(expressions) = values {
                                 for i in expressions {
-                                    let mut value_set: Vec<String> = vec![];
+                                    let mut value_set: Vec<Value> = vec![];
                                     for e in i {
-                                        match e {
-                                            Expr::Value(v) => match v {
-                                                Value::Number(n, _) => {
-                                                    value_set.push(n.to_string());
-                                                }
-                                                Value::Boolean(b) => match *b {
-                                                    true => value_set.push("true".to_string()),
-                                                    false => value_set.push("false".to_string()),
-                                                },
-                                                Value::SingleQuotedString(sqs) => {
-                                                    value_set.push(sqs.to_string());
-                                                }
-                                                Value::Null => {
-                                                    value_set.push("Null".to_string());
-                                                }
-                                                _ => {}
-                                            },
-                                            Expr::Identifier(i) => {
-                                                value_set.push(i.to_string());
-                                            }
-                                            _ => {}
-                                        }
+                                        value_set.push(evaluate_value_expr(e)?);
                                     }
                                     all_values.push(value_set);
                                 }
                             }
+                        } else {
+                            select = Some(SelectQuery::from_query(source)?);
                         }
                     }
                 }
@@ -88,10 +123,167 @@ impl InsertQuery {
                 table_name: t,
                 columns,
                 rows: all_values,
+                select,
+                on_conflict,
+                upsert,
+                returning,
             }),
             None => Err(SQLRiteError::Internal(
                 "Error parsing insert query".to_string(),
             )),
         }
     }
+
+    /// Parses `INSERT INTO <name> DEFAULT VALUES`, which sqlparser does not support,
+    /// into a single row where every column takes its DEFAULT.
+    /// Returns `None` when the query is not of that form.
+    pub fn parse_default_values(query: &str) -> Result<Option<InsertQuery>> {
+        let dialect = SQLiteDialect {};
+        let tokens = Tokenizer::new(&dialect, query)
+            .tokenize()
+            .map_err(ParserError::from)?;
+        let words: Vec<&Token> = tokens
+            .iter()
+            .filter(|token| !matches!(token, Token::Whitespace(_) | Token::SemiColon))
+            .collect();
+
+        match words.as_slice() {
+            [insert, into, Token::Word(name), default, values]
+                if is_keyword(insert, "INSERT")
+                    && is_keyword(into, "INTO")
+                    && is_keyword(default, "DEFAULT")
+                    && is_keyword(values, "VALUES") =>
+            {
+                Ok(Some(InsertQuery {
+                    table_name: name.value.to_string(),
+                    columns: Some(vec![]),
+                    rows: vec![vec![]],
+                    select: None,
+                    on_conflict: SqliteOnConflict::Abort,
+                    upsert: None,
+                    returning: None,
+                }))
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+/// Removes the `ON CONFLICT` clause of an INSERT statement, since sqlparser does not support it.
+/// Returns the statement without it and the parsed clause, if there was one.
+///
+/// The clause has the form `ON CONFLICT [(column, ...)] DO NOTHING` or
+/// `ON CONFLICT [(column, ...)] DO UPDATE SET column = expr, ... [WHERE expr]`.
+pub fn split_upsert(query: &str) -> Result<(String, Option<Upsert>)> {
+    let dialect = SQLiteDialect {};
+    let tokens = Tokenizer::new(&dialect, query)
+        .tokenize()
+        .map_err(ParserError::from)?;
+    // Positions of the tokens that are not whitespace
+    let words: Vec<usize> = (0..tokens.len())
+        .filter(|i| !matches!(tokens[*i], Token::Whitespace(_)))
+        .collect();
+    let is_insert = words
+        .first()
+        .is_some_and(|i| is_keyword(&tokens[*i], "INSERT") || is_keyword(&tokens[*i], "REPLACE"));
+    if !is_insert {
+        return Ok((query.to_string(), None));
+    }
+
+    // ON CONFLICT can only appear after the rows, outside of any parenthesis
+    let mut depth = 0;
+    let mut on_position = None;
+    for (w, i) in words.iter().enumerate() {
+        match &tokens[*i] {
+            Token::LParen => depth += 1,
+            Token::RParen => depth -= 1,
+            token
+                if depth == 0
+                    && is_keyword(token, "ON")
+                    && words
+                        .get(w + 1)
+                        .is_some_and(|next| is_keyword(&tokens[*next], "CONFLICT")) =>
+            {
+                on_position = Some(w);
+                break;
+            }
+            _ => (),
+        }
+    }
+    let on_position = match on_position {
+        Some(w) => w,
+        None => return Ok((query.to_string(), None)),
+    };
+
+    let statement = tokens_to_string(&tokens[..words[on_position]]);
+    let mut parser = Parser::new(tokens[words[on_position + 1] + 1..].to_vec(), &dialect);
+    let mut target: Vec<String> = vec![];
+    if parser.consume_token(&Token::LParen) {
+        for ident in parser.parse_comma_separated(Parser::parse_identifier)? {
+            target.push(ident.value.to_string());
+        }
+        parser.expect_token(&Token::RParen)?;
+    }
+    if !is_keyword(&parser.next_token(), "DO") {
+        return Err(SQLRiteError::SqlError(ParserError::ParserError(
+            "Expected DO after ON CONFLICT".to_string(),
+        )));
+    }
+    let action = match parser.next_token() {
+        token if is_keyword(&token, "NOTHING") => UpsertAction::Nothing,
+        token if is_keyword(&token, "UPDATE") => {
+            parser.expect_keyword(Keyword::SET)?;
+            let mut assignments: Vec<(String, Expr)> = vec![];
+            for assignment in parser.parse_comma_separated(Parser::parse_assignment)? {
+                // Assignments can be qualified, the column is the last part
+                let column = match assignment.id.last() {
+                    Some(ident) => ident.value.to_string(),
+                    None => {
+                        return Err(SQLRiteError::Internal(
+                            "Error parsing insert query".to_string(),
+                        ))
+                    }
+                };
+                if assignments.iter().any(|(col, _)| *col == column) {
+                    return Err(SQLRiteError::General(format!(
+                        "Column {} is assigned more than once",
+                        column
+                    )));
+                }
+                assignments.push((column, assignment.value));
+            }
+            let selection = match parser.parse_keyword(Keyword::WHERE) {
+                true => Some(parser.parse_expr()?),
+                false => None,
+            };
+            UpsertAction::Update {
+                assignments,
+                selection,
+            }
+        }
+        token => {
+            return Err(SQLRiteError::SqlError(ParserError::ParserError(format!(
+                "Expected NOTHING or UPDATE after DO, found: {}",
+                token
+            ))))
+        }
+    };
+    // Allowing a trailing semicolon, like the rest of the statement
+    while parser.consume_token(&Token::SemiColon) {}
+    let token = parser.peek_token();
+    if token != Token::EOF {
+        return Err(SQLRiteError::SqlError(ParserError::ParserError(format!(
+            "Expected end of statement, found: {}",
+            token
+        ))));
+    }
+    Ok((statement, Some(Upsert { target, action })))
+}
+
+/// Evaluates a single expression from the VALUES list. Bare identifiers are taken as text.
+pub fn evaluate_value_expr(expr: &Expr) -> Result<Value> {
+    match expr {
+        Expr::Identifier(i) => Ok(Value::Text(i.to_string())),
+        _ => evaluate(expr, &NoRow),
+    }
 }
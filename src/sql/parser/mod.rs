@@ -1,2 +1,15 @@
+pub mod alter;
+pub mod analyze;
 pub mod create;
+pub mod delete;
+pub mod drop;
+pub mod explain;
+pub mod index;
 pub mod insert;
+pub mod parameter;
+pub mod pragma;
+pub mod returning;
+pub mod select;
+pub mod trigger;
+pub mod update;
+pub mod view;
@@ -0,0 +1,87 @@
+use sqlparser::ast::Expr;
+use sqlparser::dialect::SQLiteDialect;
+use sqlparser::parser::ParserError;
+use sqlparser::tokenizer::{Token, Tokenizer};
+
+use crate::error::{Result, SQLRiteError};
+use crate::sql::parser::trigger::tokens_to_string;
+
+/// Largest index a parameter can have, as in SQLite
+pub const MAX_PARAMETER_INDEX: usize = 32766;
+
+/// Replaces the parameters of a statement, `?`, `?NNN`, `:name`, `@name` and `$name`, which
+/// sqlparser does not support, with identifiers naming them by index: `$1`, `$2` and so on.
+/// Returns the statement and the name of every parameter by index, `None` for those given
+/// as `?` or `?NNN`.
+///
+/// Like in SQLite, `?NNN` has index NNN, `?` the index after the largest one so far and a
+/// name used more than once keeps the index it was first given.
+pub fn split_parameters(query: &str) -> Result<(String, Vec<Option<String>>)> {
+    let dialect = SQLiteDialect {};
+    let tokens = Tokenizer::new(&dialect, query)
+        .tokenize()
+        .map_err(ParserError::from)?;
+
+    let mut names: Vec<Option<String>> = vec![];
+    let mut rewritten: Vec<Token> = vec![];
+    let mut i = 0;
+    while i < tokens.len() {
+        let (name, length) = match (&tokens[i], tokens.get(i + 1)) {
+            (Token::Placeholder(mark), Some(Token::Number(number, _))) if mark == "?" => {
+                (format!("?{}", number), 2)
+            }
+            (Token::Placeholder(mark), _) if mark == "?" => (String::from("?"), 1),
+            (Token::Colon, Some(Token::Word(word))) if word.quote_style.is_none() => {
+                (format!(":{}", word.value), 2)
+            }
+            (Token::AtSign, Some(Token::Word(word))) if word.quote_style.is_none() => {
+                (format!("@{}", word.value), 2)
+            }
+            // `$` starts identifiers in the SQLite dialect of sqlparser
+            (Token::Word(word), _) if word.quote_style.is_none() && word.value.starts_with('$') => {
+                (word.value.to_string(), 1)
+            }
+            (token, _) => {
+                rewritten.push(token.clone());
+                i += 1;
+                continue;
+            }
+        };
+
+        let index = match name.strip_prefix('?') {
+            Some("") => names.len() + 1,
+            Some(number) => match number.parse::<usize>() {
+                Ok(index) if (1..=MAX_PARAMETER_INDEX).contains(&index) => index,
+                _ => {
+                    return Err(SQLRiteError::General(format!(
+                        "variable number must be between ?1 and ?{}",
+                        MAX_PARAMETER_INDEX
+                    )))
+                }
+            },
+            None => match names.iter().position(|n| n.as_deref() == Some(&name)) {
+                Some(position) => position + 1,
+                None => names.len() + 1,
+            },
+        };
+        if index > names.len() {
+            names.resize(index, None);
+            if !name.starts_with('?') {
+                names[index - 1] = Some(name);
+            }
+        }
+        rewritten.push(Token::make_word(&format!("${}", index), None));
+        i += length;
+    }
+    Ok((tokens_to_string(&rewritten), names))
+}
+
+/// Returns the index of the parameter an expression stands for, see `split_parameters`
+pub fn parameter_index(expr: &Expr) -> Option<usize> {
+    match expr {
+        Expr::Identifier(ident) if ident.quote_style.is_none() => {
+            ident.value.strip_prefix('$')?.parse::<usize>().ok()
+        }
+        _ => None,
+    }
+}
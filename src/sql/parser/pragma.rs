@@ -0,0 +1,72 @@
+use sqlparser::dialect::SQLiteDialect;
+use sqlparser::parser::ParserError;
+use sqlparser::tokenizer::{Token, Tokenizer};
+
+use crate::error::{Result, SQLRiteError};
+use crate::sql::parser::trigger::is_keyword;
+
+/// The following structure represents a PRAGMA query already parsed,
+/// which sqlparser does not support
+#[derive(Debug, Clone)]
+pub struct PragmaQuery {
+    /// Name of the pragma, in lowercase
+    pub name: String,
+    /// Value the pragma is set to, `None` when the query reads its current value
+    pub value: Option<String>,
+}
+
+impl PragmaQuery {
+    /// Parses `PRAGMA <name>`, `PRAGMA <name> = <value>` and `PRAGMA <name>(<value>)`.
+    /// Returns `None` when the query is not a PRAGMA.
+    pub fn parse(query: &str) -> Result<Option<PragmaQuery>> {
+        let dialect = SQLiteDialect {};
+        let tokens = Tokenizer::new(&dialect, query)
+            .tokenize()
+            .map_err(ParserError::from)?;
+        let words: Vec<&Token> = tokens
+            .iter()
+            .filter(|token| !matches!(token, Token::Whitespace(_) | Token::SemiColon))
+            .collect();
+        match words.first() {
+            Some(word) if is_keyword(word, "PRAGMA") => (),
+            _ => return Ok(None),
+        }
+
+        let name = match words.get(1) {
+            Some(Token::Word(word)) => word.value.to_lowercase(),
+            _ => return Err(invalid_pragma()),
+        };
+        let value = match &words[2..] {
+            [] => None,
+            [Token::Eq, value] | [Token::LParen, value, Token::RParen] => Some(match value {
+                Token::Word(word) => word.value.to_string(),
+                Token::Number(number, _) => number.to_string(),
+                Token::SingleQuotedString(string) => string.to_string(),
+                _ => return Err(invalid_pragma()),
+            }),
+            _ => return Err(invalid_pragma()),
+        };
+        Ok(Some(PragmaQuery { name, value }))
+    }
+
+    /// Reads the value of the query as a boolean, the way SQLite does
+    pub fn bool_value(&self) -> Result<Option<bool>> {
+        match &self.value {
+            Some(value) => match value.to_lowercase().as_ref() {
+                "1" | "on" | "true" | "yes" => Ok(Some(true)),
+                "0" | "off" | "false" | "no" => Ok(Some(false)),
+                _ => Err(SQLRiteError::General(format!(
+                    "invalid value for PRAGMA {}: {}",
+                    self.name, value
+                ))),
+            },
+            None => Ok(None),
+        }
+    }
+}
+
+fn invalid_pragma() -> SQLRiteError {
+    SQLRiteError::SqlError(ParserError::ParserError(
+        "Expected PRAGMA <name> [= <value>]".to_string(),
+    ))
+}
@@ -0,0 +1,61 @@
+use sqlparser::ast::SelectItem;
+use sqlparser::dialect::SQLiteDialect;
+use sqlparser::parser::{Parser, ParserError};
+use sqlparser::tokenizer::{Token, Tokenizer};
+
+use crate::error::{Result, SQLRiteError};
+use crate::sql::parser::trigger::{is_keyword, tokens_to_string};
+
+/// Removes the `RETURNING` clause of an INSERT, UPDATE or DELETE statement, since sqlparser
+/// does not support it. Returns the statement without it and the items of the clause, parsed
+/// like a SELECT list, if there was one.
+pub fn split_returning(query: &str) -> Result<(String, Option<Vec<SelectItem>>)> {
+    let dialect = SQLiteDialect {};
+    let tokens = Tokenizer::new(&dialect, query)
+        .tokenize()
+        .map_err(ParserError::from)?;
+    let is_dml = tokens
+        .iter()
+        .find(|token| !matches!(token, Token::Whitespace(_)))
+        .is_some_and(|token| {
+            ["INSERT", "REPLACE", "UPDATE", "DELETE"]
+                .iter()
+                .any(|keyword| is_keyword(token, keyword))
+        });
+    if !is_dml {
+        return Ok((query.to_string(), None));
+    }
+
+    // RETURNING ends the statement, outside of any parenthesis
+    let mut depth = 0;
+    let mut position = None;
+    for (i, token) in tokens.iter().enumerate() {
+        match token {
+            Token::LParen => depth += 1,
+            Token::RParen => depth -= 1,
+            token if depth == 0 && is_keyword(token, "RETURNING") => {
+                position = Some(i);
+                break;
+            }
+            _ => (),
+        }
+    }
+    let position = match position {
+        Some(i) => i,
+        None => return Ok((query.to_string(), None)),
+    };
+
+    let statement = tokens_to_string(&tokens[..position]);
+    let mut parser = Parser::new(tokens[position + 1..].to_vec(), &dialect);
+    let items = parser.parse_comma_separated(Parser::parse_select_item)?;
+    // Allowing a trailing semicolon, like the rest of the statement
+    while parser.consume_token(&Token::SemiColon) {}
+    let token = parser.peek_token();
+    if token != Token::EOF {
+        return Err(SQLRiteError::SqlError(ParserError::ParserError(format!(
+            "Expected end of statement, found: {}",
+            token
+        ))));
+    }
+    Ok((statement, Some(items)))
+}
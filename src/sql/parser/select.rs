@@ -0,0 +1,377 @@
+use serde::{Deserialize, Serialize};
+use sqlparser::ast::{
+    BinaryOperator, Expr, Ident, JoinConstraint, JoinOperator, ObjectName, Offset, OffsetRows,
+    OrderByExpr, Query, SelectItem, SetExpr, Statement, TableFactor,
+};
+use sqlparser::dialect::SQLiteDialect;
+use sqlparser::parser::{Parser, ParserError};
+use sqlparser::tokenizer::{Token, Tokenizer};
+
+use crate::error::{Result, SQLRiteError};
+use crate::sql::executor::expr::{contains_aggregate, walk_expr_mut};
+use crate::sql::parser::trigger::{is_keyword, tokens_to_string};
+
+/// How a table in the FROM clause is joined to the tables before it
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum JoinKind {
+    /// First table of the FROM clause
+    From,
+    /// `JOIN`, `INNER JOIN` or a comma separated table
+    Inner,
+    /// `LEFT [OUTER] JOIN`
+    Left,
+    /// `CROSS JOIN`
+    Cross,
+}
+
+/// A table in the FROM clause of a SELECT query
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FromTable {
+    /// Name of the table
+    pub name: String,
+    /// Alias given with `AS`, if any
+    pub alias: Option<String>,
+    /// How the table is joined to the tables before it
+    pub join: JoinKind,
+    /// ON clause of the join. `USING (...)` is turned into the equivalent ON clause
+    pub constraint: Option<Expr>,
+}
+
+impl FromTable {
+    /// Name the table is referenced by in the rest of the query
+    pub fn reference_name(&self) -> &str {
+        self.alias.as_deref().unwrap_or(&self.name)
+    }
+}
+
+/// The following structure represents a SELECT query already parsed
+/// and broken down into its projection, FROM tables and clauses
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SelectQuery {
+    /// Value representing if the query was declared SELECT DISTINCT
+    pub distinct: bool,
+    /// Selected expressions, aliases and wildcards
+    pub projection: Vec<SelectItem>,
+    /// Tables of the FROM clause, in join order
+    pub from: Vec<FromTable>,
+    /// WHERE clause
+    pub selection: Option<Expr>,
+    /// GROUP BY terms
+    pub group_by: Vec<Expr>,
+    /// HAVING clause
+    pub having: Option<Expr>,
+    /// ORDER BY terms
+    pub order_by: Vec<OrderByExpr>,
+    /// LIMIT clause
+    pub limit: Option<Expr>,
+    /// OFFSET clause
+    pub offset: Option<Expr>,
+}
+
+impl SelectQuery {
+    pub fn new(statement: &Statement) -> Result<SelectQuery> {
+        match statement {
+            Statement::Query(query) => SelectQuery::from_query(query),
+            _ => Err(SQLRiteError::Internal(
+                "Error parsing select query".to_string(),
+            )),
+        }
+    }
+
+    /// Builds the query from the body of a `Statement::Query`
+    pub fn from_query(query: &Query) -> Result<SelectQuery> {
+        let Query {
+            with,
+            body,
+            order_by,
+            limit,
+            offset,
+            fetch,
+            ..
+        } = query;
+        if with.is_some() || fetch.is_some() {
+            return Err(SQLRiteError::NotImplemented(
+                "WITH and FETCH clauses are not supported yet.".to_string(),
+            ));
+        }
+        let select = match body {
+            SetExpr::Select(select) => select,
+            _ => {
+                return Err(SQLRiteError::NotImplemented(
+                    "Only simple SELECT queries are supported yet.".to_string(),
+                ))
+            }
+        };
+        let mut from: Vec<FromTable> = vec![];
+        for table_with_joins in &select.from {
+            let join = match from.is_empty() {
+                true => JoinKind::From,
+                false => JoinKind::Inner,
+            };
+            let (name, alias) = parse_table_factor(&table_with_joins.relation)?;
+            from.push(FromTable {
+                name,
+                alias,
+                join,
+                constraint: None,
+            });
+
+            for join in &table_with_joins.joins {
+                let (name, alias) = parse_table_factor(&join.relation)?;
+                let (join, constraint) = match &join.join_operator {
+                    JoinOperator::Inner(constraint) => (JoinKind::Inner, constraint),
+                    JoinOperator::LeftOuter(constraint) => (JoinKind::Left, constraint),
+                    JoinOperator::CrossJoin => (JoinKind::Cross, &JoinConstraint::None),
+                    operator => {
+                        return Err(SQLRiteError::NotImplemented(format!(
+                            "{:?} joins are not supported yet.",
+                            operator
+                        )))
+                    }
+                };
+                let reference_name = alias.as_deref().unwrap_or(&name).to_string();
+                let constraint = match constraint {
+                    JoinConstraint::On(expr) => Some(expr.clone()),
+                    // `USING (a, b)` joins on the columns of the same name in the previous table
+                    JoinConstraint::Using(columns) => {
+                        let previous = from.last().unwrap().reference_name().to_string();
+                        columns
+                            .iter()
+                            .map(|col| Expr::BinaryOp {
+                                left: Box::new(Expr::CompoundIdentifier(vec![
+                                    Ident::new(&previous),
+                                    col.clone(),
+                                ])),
+                                op: BinaryOperator::Eq,
+                                right: Box::new(Expr::CompoundIdentifier(vec![
+                                    Ident::new(&reference_name),
+                                    col.clone(),
+                                ])),
+                            })
+                            .reduce(|left, right| Expr::BinaryOp {
+                                left: Box::new(left),
+                                op: BinaryOperator::And,
+                                right: Box::new(right),
+                            })
+                    }
+                    JoinConstraint::Natural => {
+                        return Err(SQLRiteError::NotImplemented(
+                            "NATURAL joins are not supported yet.".to_string(),
+                        ))
+                    }
+                    JoinConstraint::None => None,
+                };
+                from.push(FromTable {
+                    name,
+                    alias,
+                    join,
+                    constraint,
+                });
+            }
+        }
+
+        for (i, table) in from.iter().enumerate() {
+            if from[..i]
+                .iter()
+                .any(|other| other.reference_name() == table.reference_name())
+            {
+                return Err(SQLRiteError::General(format!(
+                    "ambiguous table name: {}",
+                    table.reference_name()
+                )));
+            }
+        }
+
+        Ok(SelectQuery {
+            distinct: select.distinct,
+            projection: select.projection.clone(),
+            from,
+            selection: select.selection.clone(),
+            group_by: select.group_by.clone(),
+            having: select.having.clone(),
+            order_by: order_by.clone(),
+            limit: limit.clone(),
+            offset: offset.as_ref().map(|offset| offset.value.clone()),
+        })
+    }
+
+    /// Returns true if the query computes aggregates, having a GROUP BY or HAVING clause
+    /// or calling an aggregate function in its SELECT list or ORDER BY terms
+    pub fn is_aggregate(&self) -> bool {
+        let mut exprs = self.projection.iter().filter_map(|item| match item {
+            SelectItem::UnnamedExpr(expr) | SelectItem::ExprWithAlias { expr, .. } => Some(expr),
+            _ => None,
+        });
+        !self.group_by.is_empty()
+            || self.having.is_some()
+            || exprs.any(contains_aggregate)
+            || self
+                .order_by
+                .iter()
+                .any(|term| contains_aggregate(&term.expr))
+    }
+
+    /// Makes the query read from a table that was renamed, along with the
+    /// column references qualified by the old name of the table
+    pub fn rename_table(&mut self, old_name: &str, new_name: &str) {
+        let mut qualified = false;
+        for table in self.from.iter_mut() {
+            if table.name == old_name {
+                qualified |= table.alias.is_none();
+                table.name = new_name.to_string();
+            }
+        }
+        if !qualified {
+            return;
+        }
+
+        let mut rename = |expr: &mut Expr| {
+            walk_expr_mut(expr, &mut |e| {
+                if let Expr::CompoundIdentifier(idents) = e {
+                    if idents.len() == 2 && idents[0].value == old_name {
+                        idents[0] = Ident::new(new_name);
+                    }
+                }
+            })
+        };
+        for item in self.projection.iter_mut() {
+            match item {
+                SelectItem::UnnamedExpr(expr) | SelectItem::ExprWithAlias { expr, .. } => {
+                    rename(expr)
+                }
+                SelectItem::QualifiedWildcard(name) if name.to_string() == old_name => {
+                    *name = ObjectName(vec![Ident::new(new_name)]);
+                }
+                _ => (),
+            }
+        }
+        self.from
+            .iter_mut()
+            .filter_map(|table| table.constraint.as_mut())
+            .for_each(&mut rename);
+        self.selection.iter_mut().for_each(&mut rename);
+        self.group_by.iter_mut().for_each(&mut rename);
+        self.having.iter_mut().for_each(&mut rename);
+        self.order_by
+            .iter_mut()
+            .for_each(|term| rename(&mut term.expr));
+    }
+}
+
+/// Returns the name and alias of a table in the FROM clause
+fn parse_table_factor(relation: &TableFactor) -> Result<(String, Option<String>)> {
+    match relation {
+        TableFactor::Table { name, alias, .. } => Ok((
+            name.to_string(),
+            alias.as_ref().map(|alias| alias.name.value.to_string()),
+        )),
+        relation => Err(SQLRiteError::NotImplemented(format!(
+            "Selecting from {} is not supported yet.",
+            relation
+        ))),
+    }
+}
+
+/// The LIMIT clause of a SELECT statement, parsed apart, see `split_limit`
+#[derive(Debug, Clone)]
+pub struct Limit {
+    /// Maximum number of rows
+    pub limit: Expr,
+    /// Number of rows skipped, given with `OFFSET` or as `LIMIT <offset>, <limit>`
+    pub offset: Option<Expr>,
+}
+
+impl Limit {
+    /// Puts the clause back into a parsed SELECT or EXPLAIN SELECT statement
+    pub fn apply(self, statement: &mut Statement) {
+        match statement {
+            Statement::Query(query) => {
+                query.limit = Some(self.limit);
+                query.offset = self.offset.map(|value| Offset {
+                    value,
+                    rows: OffsetRows::None,
+                });
+            }
+            Statement::Explain { statement, .. } => self.apply(statement),
+            _ => (),
+        }
+    }
+}
+
+/// Removes the LIMIT clause of a SELECT or EXPLAIN SELECT statement, since sqlparser only
+/// supports number literals in LIMIT and OFFSET, leaving out expressions and parameters.
+/// Returns the statement without it and the parsed clause, if there was one.
+///
+/// The clause has the form `LIMIT <expr> [OFFSET <expr>]` or `LIMIT <offset>, <limit>`.
+pub fn split_limit(query: &str) -> Result<(String, Option<Limit>)> {
+    let dialect = SQLiteDialect {};
+    let tokens = Tokenizer::new(&dialect, query)
+        .tokenize()
+        .map_err(ParserError::from)?;
+    let mut words = tokens
+        .iter()
+        .filter(|token| !matches!(token, Token::Whitespace(_)));
+    let is_select = match words.next() {
+        Some(token) if is_keyword(token, "EXPLAIN") => words
+            .next()
+            .is_some_and(|token| is_keyword(token, "SELECT")),
+        Some(token) => is_keyword(token, "SELECT"),
+        None => false,
+    };
+    if !is_select {
+        return Ok((query.to_string(), None));
+    }
+
+    // LIMIT ends the statement, outside of any parenthesis
+    let mut depth = 0;
+    let mut position = None;
+    for (i, token) in tokens.iter().enumerate() {
+        match token {
+            Token::LParen => depth += 1,
+            Token::RParen => depth -= 1,
+            token if depth == 0 && is_keyword(token, "LIMIT") => {
+                position = Some(i);
+                break;
+            }
+            _ => (),
+        }
+    }
+    let position = match position {
+        Some(i) => i,
+        None => return Ok((query.to_string(), None)),
+    };
+
+    let mut parser = Parser::new(tokens[position + 1..].to_vec(), &dialect);
+    // `LIMIT ALL` is the same as no LIMIT clause, which sqlparser supports
+    if is_keyword(&parser.peek_token(), "ALL") {
+        return Ok((query.to_string(), None));
+    }
+    let first = parser.parse_expr()?;
+    let limit = if parser.consume_token(&Token::Comma) {
+        Limit {
+            limit: parser.parse_expr()?,
+            offset: Some(first),
+        }
+    } else if is_keyword(&parser.peek_token(), "OFFSET") {
+        parser.next_token();
+        Limit {
+            limit: first,
+            offset: Some(parser.parse_expr()?),
+        }
+    } else {
+        Limit {
+            limit: first,
+            offset: None,
+        }
+    };
+    // Allowing a trailing semicolon, like the rest of the statement
+    while parser.consume_token(&Token::SemiColon) {}
+    let token = parser.peek_token();
+    if token != Token::EOF {
+        return Err(SQLRiteError::SqlError(ParserError::ParserError(format!(
+            "Expected end of statement, found: {}",
+            token
+        ))));
+    }
+    Ok((tokens_to_string(&tokens[..position]), Some(limit)))
+}
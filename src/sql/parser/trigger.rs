@@ -0,0 +1,291 @@
+use serde::{Deserialize, Serialize};
+use sqlparser::ast::{Expr, Statement};
+use sqlparser::dialect::SQLiteDialect;
+use sqlparser::parser::{Parser, ParserError};
+use sqlparser::tokenizer::{Token, Tokenizer};
+
+use crate::error::{Result, SQLRiteError};
+use crate::sql::parser::insert::split_upsert;
+
+/// When a trigger runs, relative to the change of the row it fires for
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum TriggerTiming {
+    Before,
+    After,
+    /// In place of the change, which a view can not make itself
+    InsteadOf,
+}
+
+/// The kind of statement a trigger fires on
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum TriggerEvent {
+    Insert,
+    /// `UPDATE [OF column, ...]`, the columns being empty when the trigger fires on any column
+    Update(Vec<String>),
+    Delete,
+}
+
+/// The following structure represents a CREATE TRIGGER query already parsed
+/// and broken down into the trigger name, its table, when it fires and its body
+#[derive(Debug, Clone)]
+pub struct CreateTriggerQuery {
+    /// Name of the trigger
+    pub name: String,
+    /// Value representing if the statement was declared with IF NOT EXISTS
+    pub if_not_exists: bool,
+    /// When the trigger runs, BEFORE when not specified
+    pub timing: TriggerTiming,
+    /// The kind of statement the trigger fires on
+    pub event: TriggerEvent,
+    /// Table or view the trigger is attached to
+    pub table_name: String,
+    /// WHEN clause, the trigger only running for the rows it is true for
+    pub when: Option<Expr>,
+    /// Statements of the trigger body, between BEGIN and END
+    pub body: Vec<String>,
+}
+
+impl CreateTriggerQuery {
+    /// Parses a `CREATE TRIGGER` statement, which sqlparser does not support:
+    ///
+    /// ```text
+    /// CREATE TRIGGER [IF NOT EXISTS] <name> [BEFORE | AFTER | INSTEAD OF]
+    ///     {INSERT | UPDATE [OF column, ...] | DELETE} ON <table>
+    ///     [FOR EACH ROW] [WHEN <expr>] BEGIN <statement>; ... END
+    /// ```
+    ///
+    /// Returns `None` when the query is not a CREATE TRIGGER statement.
+    pub fn parse(query: &str) -> Result<Option<CreateTriggerQuery>> {
+        let dialect = SQLiteDialect {};
+        let tokens = Tokenizer::new(&dialect, query)
+            .tokenize()
+            .map_err(ParserError::from)?;
+        let words: Vec<&Token> = tokens
+            .iter()
+            .filter(|token| !matches!(token, Token::Whitespace(_)))
+            .take(3)
+            .collect();
+        let is_create_trigger = match words.as_slice() {
+            [create, trigger, ..]
+                if is_keyword(create, "CREATE") && is_keyword(trigger, "TRIGGER") =>
+            {
+                true
+            }
+            [create, temp, trigger]
+                if is_keyword(create, "CREATE")
+                    && (is_keyword(temp, "TEMP") || is_keyword(temp, "TEMPORARY"))
+                    && is_keyword(trigger, "TRIGGER") =>
+            {
+                return Err(SQLRiteError::NotImplemented(
+                    "Temporary triggers are not supported.".to_string(),
+                ))
+            }
+            _ => false,
+        };
+        if !is_create_trigger {
+            return Ok(None);
+        }
+
+        let mut parser = Parser::new(tokens, &dialect);
+        parser.next_token();
+        parser.next_token();
+        let if_not_exists = parse_words(&mut parser, &["IF", "NOT", "EXISTS"]);
+        let name = parser.parse_object_name()?.to_string();
+
+        let timing = if parse_words(&mut parser, &["BEFORE"]) {
+            TriggerTiming::Before
+        } else if parse_words(&mut parser, &["AFTER"]) {
+            TriggerTiming::After
+        } else if parse_words(&mut parser, &["INSTEAD", "OF"]) {
+            TriggerTiming::InsteadOf
+        } else {
+            TriggerTiming::Before
+        };
+        let event = if parse_words(&mut parser, &["INSERT"]) {
+            TriggerEvent::Insert
+        } else if parse_words(&mut parser, &["DELETE"]) {
+            TriggerEvent::Delete
+        } else if parse_words(&mut parser, &["UPDATE"]) {
+            let mut columns: Vec<String> = vec![];
+            if parse_words(&mut parser, &["OF"]) {
+                for ident in parser.parse_comma_separated(Parser::parse_identifier)? {
+                    columns.push(ident.value.to_string());
+                }
+            }
+            TriggerEvent::Update(columns)
+        } else {
+            return Err(expected("INSERT, UPDATE or DELETE", parser.peek_token()));
+        };
+        if !parse_words(&mut parser, &["ON"]) {
+            return Err(expected("ON", parser.peek_token()));
+        }
+        let table_name = parser.parse_object_name()?.to_string();
+        parse_words(&mut parser, &["FOR", "EACH", "ROW"]);
+        let when = match parse_words(&mut parser, &["WHEN"]) {
+            true => Some(parser.parse_expr()?),
+            false => None,
+        };
+        if !parse_words(&mut parser, &["BEGIN"]) {
+            return Err(expected("BEGIN", parser.peek_token()));
+        }
+
+        // The body runs up to the last END, since END also closes CASE expressions
+        let mut rest: Vec<Token> = vec![];
+        while let Some(token) = parser.next_token_no_skip() {
+            rest.push(token.clone());
+        }
+        while matches!(rest.last(), Some(Token::SemiColon | Token::Whitespace(_))) {
+            rest.pop();
+        }
+        if !rest.last().is_some_and(|token| is_keyword(token, "END")) {
+            return Err(expected("END", Token::EOF));
+        }
+        rest.pop();
+
+        let mut body: Vec<String> = vec![];
+        for statement in rest.split(|token| *token == Token::SemiColon) {
+            let statement = tokens_to_string(statement);
+            if !statement.trim().is_empty() {
+                body.push(statement.trim().to_string());
+            }
+        }
+        if body.is_empty() {
+            return Err(SQLRiteError::General(String::from(
+                "a trigger body needs at least one statement",
+            )));
+        }
+        for statement in &body {
+            validate_body_statement(statement)?;
+        }
+
+        Ok(Some(CreateTriggerQuery {
+            name,
+            if_not_exists,
+            timing,
+            event,
+            table_name,
+            when,
+            body,
+        }))
+    }
+}
+
+/// The following structure represents a DROP TRIGGER query already parsed
+#[derive(Debug, Clone)]
+pub struct DropTriggerQuery {
+    /// Name of the trigger to drop
+    pub name: String,
+    /// Value representing if the statement was declared with IF EXISTS
+    pub if_exists: bool,
+}
+
+impl DropTriggerQuery {
+    /// Parses `DROP TRIGGER [IF EXISTS] <name>`, which sqlparser does not support.
+    /// Returns `None` when the query is not a DROP TRIGGER statement.
+    pub fn parse(query: &str) -> Result<Option<DropTriggerQuery>> {
+        let dialect = SQLiteDialect {};
+        let tokens = Tokenizer::new(&dialect, query)
+            .tokenize()
+            .map_err(ParserError::from)?;
+        let words: Vec<&Token> = tokens
+            .iter()
+            .filter(|token| !matches!(token, Token::Whitespace(_) | Token::SemiColon))
+            .collect();
+
+        match words.as_slice() {
+            [drop, trigger, Token::Word(name)]
+                if is_keyword(drop, "DROP") && is_keyword(trigger, "TRIGGER") =>
+            {
+                Ok(Some(DropTriggerQuery {
+                    name: name.value.to_string(),
+                    if_exists: false,
+                }))
+            }
+            [drop, trigger, if_, exists, Token::Word(name)]
+                if is_keyword(drop, "DROP")
+                    && is_keyword(trigger, "TRIGGER")
+                    && is_keyword(if_, "IF")
+                    && is_keyword(exists, "EXISTS") =>
+            {
+                Ok(Some(DropTriggerQuery {
+                    name: name.value.to_string(),
+                    if_exists: true,
+                }))
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+/// Returns true if a token is the given keyword. Quoted words are identifiers, never keywords.
+pub fn is_keyword(token: &Token, keyword: &str) -> bool {
+    match token {
+        Token::Word(word) => word.quote_style.is_none() && word.value.eq_ignore_ascii_case(keyword),
+        _ => false,
+    }
+}
+
+/// Consumes the given sequence of words if the parser is positioned on it
+fn parse_words(parser: &mut Parser, words: &[&str]) -> bool {
+    let mut consumed = 0;
+    for word in words {
+        if !is_keyword(&parser.peek_token(), word) {
+            break;
+        }
+        parser.next_token();
+        consumed += 1;
+    }
+    if consumed < words.len() {
+        for _ in 0..consumed {
+            parser.prev_token();
+        }
+        return false;
+    }
+    true
+}
+
+fn expected(what: &str, found: Token) -> SQLRiteError {
+    SQLRiteError::SqlError(ParserError::ParserError(format!(
+        "Expected {}, found: {}",
+        what, found
+    )))
+}
+
+/// Turns tokens back into SQL text, quoting string literals and quoted identifiers again.
+/// The tokenizer unescapes the quotes doubled inside of them, which are doubled back.
+pub fn tokens_to_string(tokens: &[Token]) -> String {
+    tokens
+        .iter()
+        .map(|token| match token {
+            Token::SingleQuotedString(s) => format!("'{}'", s.replace('\'', "''")),
+            Token::Word(word) => match word.quote_style {
+                Some(start) => {
+                    let end = match start {
+                        '[' => ']',
+                        quote => quote,
+                    };
+                    let value = word.value.replace(end, &end.to_string().repeat(2));
+                    format!("{}{}{}", start, value, end)
+                }
+                None => word.to_string(),
+            },
+            token => token.to_string(),
+        })
+        .collect()
+}
+
+/// Checks that a statement of a trigger body parses and is an INSERT, UPDATE, DELETE or SELECT
+fn validate_body_statement(statement: &str) -> Result<()> {
+    let (statement, _) = split_upsert(statement)?;
+    let ast = Parser::parse_sql(&SQLiteDialect {}, &statement).map_err(SQLRiteError::from)?;
+    match ast.as_slice() {
+        [Statement::Insert { .. }]
+        | [Statement::Update { .. }]
+        | [Statement::Delete { .. }]
+        | [Statement::Query(_)] => Ok(()),
+        _ => Err(SQLRiteError::General(format!(
+            "only INSERT, UPDATE, DELETE and SELECT statements are allowed in triggers: {}",
+            statement
+        ))),
+    }
+}
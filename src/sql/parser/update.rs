@@ -0,0 +1,78 @@
+use sqlparser::ast::{Expr, SelectItem, Statement, TableFactor};
+
+use crate::error::{Result, SQLRiteError};
+
+/// The following structure represents an UPDATE query already parsed
+/// and broken down into the table name, the `(column, expression)` assignments
+/// and the optional WHERE clause selecting the rows to update
+#[derive(Debug)]
+pub struct UpdateQuery {
+    /// Name of the table being updated
+    pub table_name: String,
+    /// Columns being assigned, each with the expression computing its new value
+    pub assignments: Vec<(String, Expr)>,
+    /// WHERE clause, every row is updated when there is none
+    pub selection: Option<Expr>,
+    /// RETURNING clause, computed over the updated rows
+    pub returning: Option<Vec<SelectItem>>,
+}
+
+impl UpdateQuery {
+    /// Builds the query from a parsed UPDATE statement. sqlparser does not parse RETURNING
+    /// clauses, `returning` is the one removed from the statement, see `split_returning`.
+    pub fn new(statement: &Statement, returning: Option<Vec<SelectItem>>) -> Result<UpdateQuery> {
+        match statement {
+            Statement::Update {
+                table,
+                assignments,
+                from,
+                selection,
+            } => {
+                if from.is_some() || !table.joins.is_empty() {
+                    return Err(SQLRiteError::NotImplemented(
+                        "UPDATE with FROM or joins is not supported yet.".to_string(),
+                    ));
+                }
+                let table_name = match &table.relation {
+                    TableFactor::Table { name, .. } => name.to_string(),
+                    relation => {
+                        return Err(SQLRiteError::NotImplemented(format!(
+                            "UPDATE of {} is not supported.",
+                            relation
+                        )))
+                    }
+                };
+
+                let mut parsed_assignments: Vec<(String, Expr)> = vec![];
+                for assignment in assignments {
+                    // Assignments can be qualified, as in `SET t.a = 1`, the column is the last part
+                    let column = match assignment.id.last() {
+                        Some(ident) => ident.value.to_string(),
+                        None => {
+                            return Err(SQLRiteError::Internal(
+                                "Error parsing update query".to_string(),
+                            ))
+                        }
+                    };
+                    if parsed_assignments.iter().any(|(col, _)| *col == column) {
+                        return Err(SQLRiteError::General(format!(
+                            "Column {} is assigned more than once",
+                            column
+                        )));
+                    }
+                    parsed_assignments.push((column, assignment.value.clone()));
+                }
+
+                Ok(UpdateQuery {
+                    table_name,
+                    assignments: parsed_assignments,
+                    selection: selection.clone(),
+                    returning,
+                })
+            }
+            _ => Err(SQLRiteError::Internal(
+                "Error parsing update query".to_string(),
+            )),
+        }
+    }
+}
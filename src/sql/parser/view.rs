@@ -0,0 +1,93 @@
+use sqlparser::ast::Statement;
+use sqlparser::dialect::SQLiteDialect;
+use sqlparser::parser::ParserError;
+use sqlparser::tokenizer::{Token, Tokenizer};
+
+use crate::error::{Result, SQLRiteError};
+use crate::sql::parser::select::SelectQuery;
+use crate::sql::parser::trigger::is_keyword;
+
+/// The following structure represents a CREATE VIEW query already parsed
+/// and broken down into the view name, its column names and its SELECT query
+#[derive(Debug)]
+pub struct CreateViewQuery {
+    /// Name of the view
+    pub name: String,
+    /// Names given to the columns of the view with `CREATE VIEW <name>(<columns>)`,
+    /// empty when the columns are named after the result of the query
+    pub columns: Vec<String>,
+    /// Query giving the rows of the view
+    pub query: SelectQuery,
+    /// Value representing if the statement was declared CREATE OR REPLACE VIEW
+    pub or_replace: bool,
+    /// Value representing if the statement was declared CREATE MATERIALIZED VIEW
+    pub materialized: bool,
+}
+
+impl CreateViewQuery {
+    pub fn new(statement: &Statement) -> Result<CreateViewQuery> {
+        match statement {
+            Statement::CreateView {
+                or_replace,
+                materialized,
+                name,
+                columns,
+                query,
+                with_options,
+            } => {
+                if !with_options.is_empty() {
+                    return Err(SQLRiteError::NotImplemented(
+                        "CREATE VIEW ... WITH options are not supported.".to_string(),
+                    ));
+                }
+                Ok(CreateViewQuery {
+                    name: name.to_string(),
+                    columns: columns.iter().map(|col| col.value.to_string()).collect(),
+                    query: SelectQuery::from_query(query)?,
+                    or_replace: *or_replace,
+                    materialized: *materialized,
+                })
+            }
+            _ => Err(SQLRiteError::Internal(
+                "Error parsing create view query".to_string(),
+            )),
+        }
+    }
+}
+
+/// The following structure represents a REFRESH MATERIALIZED VIEW query already parsed
+#[derive(Debug, Clone)]
+pub struct RefreshViewQuery {
+    /// Name of the materialized view to refresh
+    pub name: String,
+}
+
+impl RefreshViewQuery {
+    /// Parses `REFRESH MATERIALIZED VIEW <name>`, which sqlparser does not support.
+    /// Returns `None` when the query is not a REFRESH statement.
+    pub fn parse(query: &str) -> Result<Option<RefreshViewQuery>> {
+        let dialect = SQLiteDialect {};
+        let tokens = Tokenizer::new(&dialect, query)
+            .tokenize()
+            .map_err(ParserError::from)?;
+        let words: Vec<&Token> = tokens
+            .iter()
+            .filter(|token| !matches!(token, Token::Whitespace(_) | Token::SemiColon))
+            .collect();
+
+        match words.as_slice() {
+            [keyword, ..] if !is_keyword(keyword, "REFRESH") => Ok(None),
+            [_, materialized, view, Token::Word(name)]
+                if is_keyword(materialized, "MATERIALIZED") && is_keyword(view, "VIEW") =>
+            {
+                Ok(Some(RefreshViewQuery {
+                    name: name.value.to_string(),
+                }))
+            }
+            [Token::Word(_), ..] => Err(SQLRiteError::General(
+                "expected REFRESH MATERIALIZED VIEW <name>".to_string(),
+            )),
+            _ => Ok(None),
+        }
+    }
+}
@@ -0,0 +1,470 @@
+use sqlparser::ast::{
+    Expr, Ident, JoinConstraint, JoinOperator, Query, SelectItem, SetExpr, Statement, TableFactor,
+    Value as SqlValue,
+};
+use std::collections::BTreeMap;
+
+use crate::error::{Result, SQLRiteError};
+use crate::sql::db::database::Database;
+use crate::sql::db::value::Value;
+use crate::sql::executor::expr::walk_expr_mut;
+use crate::sql::executor::planner::{AccessPath, QueryPlan};
+use crate::sql::executor::select::{plan_select, plan_table_rows};
+use crate::sql::parser::delete::DeleteQuery;
+use crate::sql::parser::insert::UpsertAction;
+use crate::sql::parser::parameter::{parameter_index, split_parameters};
+use crate::sql::parser::select::SelectQuery;
+use crate::sql::parser::update::UpdateQuery;
+use crate::sql::{execute_command, parse_command, Output, ParsedCommand};
+
+/// A statement parsed and planned once and executed any number of times, with the values
+/// bound to its parameters, `?`, `?NNN`, `:name`, `@name` or `$name`, at the time it executes.
+/// Values are bound as they are, never through the text of the statement: they take the
+/// place of the parameters in the parsed statement and in its plan.
+///
+/// SELECT, UPDATE and DELETE statements keep the plan made when they were prepared for as
+/// long as the schema and the statistics of the tables and views they refer to stay the same,
+/// and are planned again when they execute otherwise.
+#[derive(Debug, Clone)]
+pub struct PreparedStatement {
+    /// The statement, its parameters replaced by identifiers naming them, see `split_parameters`,
+    /// along with its plan
+    command: ParsedCommand,
+    /// Name of every parameter by index, `None` for those given as `?` or `?NNN`
+    names: Vec<Option<String>>,
+    /// Values bound to the parameters by index, NULL until bound
+    values: Vec<Value>,
+    /// Tables and views the statement refers to, with the version of their schema when it
+    /// was planned, see `Database::schema_version`
+    schema: Vec<(String, Option<u64>)>,
+}
+
+impl PreparedStatement {
+    /// Parses and plans a statement, ready to be executed with `execute`. Like in SQLite,
+    /// parameters are only allowed in SELECT, INSERT, UPDATE and DELETE statements.
+    ///
+    pub fn prepare(query: &str, db: &Database) -> Result<PreparedStatement> {
+        let (query, names) = split_parameters(query)?;
+        let mut command = parse_command(&query)?;
+        if !names.is_empty() && !accepts_parameters(&command) {
+            return Err(SQLRiteError::General(
+                "parameters are only allowed in SELECT, INSERT, UPDATE and DELETE statements"
+                    .to_string(),
+            ));
+        }
+        name_parameter_columns(&mut command, &names);
+
+        let schema = command_tables(&command)
+            .into_iter()
+            .map(|name| {
+                let version = db.schema_version(&name);
+                (name, version)
+            })
+            .collect();
+        if let ParsedCommand::Statement(parsed) = &mut command {
+            parsed.plan = plan_statement(&parsed.statement, db)?;
+        }
+        Ok(PreparedStatement {
+            command,
+            values: vec![Value::Null; names.len()],
+            names,
+            schema,
+        })
+    }
+
+    /// Returns the number of parameters, the largest index of a parameter
+    ///
+    pub fn parameter_count(&self) -> usize {
+        self.names.len()
+    }
+
+    /// Returns the name of the parameter at an index starting at 1, prefix included,
+    /// `None` for the parameters given as `?` or `?NNN`
+    ///
+    pub fn parameter_name(&self, index: usize) -> Option<&str> {
+        self.names.get(index.checked_sub(1)?)?.as_deref()
+    }
+
+    /// Binds a value to the parameter at an index starting at 1
+    ///
+    pub fn bind(&mut self, index: usize, value: Value) -> Result<()> {
+        match index.checked_sub(1).and_then(|i| self.values.get_mut(i)) {
+            Some(bound) => {
+                *bound = value;
+                Ok(())
+            }
+            None => Err(SQLRiteError::General(format!(
+                "parameter index {} out of range, the statement has {} parameters",
+                index,
+                self.parameter_count()
+            ))),
+        }
+    }
+
+    /// Binds values by parameter name, prefix included, `?NNN` standing for the parameter at
+    /// index NNN when it has no name. Parameters missing from `parameters` keep their value.
+    ///
+    pub fn bind_parameters(&mut self, parameters: &BTreeMap<String, Value>) -> Result<()> {
+        for index in 1..=self.parameter_count() {
+            let name = match self.parameter_name(index) {
+                Some(name) => name.to_string(),
+                None => format!("?{}", index),
+            };
+            if let Some(value) = parameters.get(&name) {
+                self.bind(index, value.clone())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns true for SELECT, INSERT, UPDATE and DELETE statements, the only ones
+    /// parameters are allowed in
+    ///
+    pub fn accepts_parameters(&self) -> bool {
+        accepts_parameters(&self.command)
+    }
+
+    /// Returns true if the schema of the tables and views the statement refers to did not
+    /// change since it was planned, for its plan to still be followed
+    ///
+    pub fn is_current(&self, db: &Database) -> bool {
+        self.schema
+            .iter()
+            .all(|(name, version)| db.schema_version(name) == *version)
+    }
+
+    /// Executes the statement with the values bound to its parameters. The statement is
+    /// not parsed again, nor planned again unless `is_current` tells its plan is out of date.
+    ///
+    pub fn execute(&self, db: &mut Database) -> Result<Output> {
+        let mut command = self.command.clone();
+        if !self.is_current(db) {
+            if let ParsedCommand::Statement(parsed) = &mut command {
+                parsed.plan = None;
+            }
+        }
+        if !self.values.is_empty() {
+            let values = &self.values;
+            visit_expressions(&mut command, &mut |expr| {
+                if let Some(value) = parameter_index(expr).and_then(|i| values.get(i - 1)) {
+                    *expr = literal(value);
+                }
+            });
+        }
+        execute_command(command, db)
+    }
+}
+
+/// Returns the SQL literal of a value
+pub fn literal(value: &Value) -> Expr {
+    Expr::Value(match value {
+        Value::Null => SqlValue::Null,
+        Value::Integer(i) => SqlValue::Number(i.to_string(), false),
+        // Debug formatting keeps the decimal point of whole numbers, for them to stay REAL
+        Value::Real(r) => SqlValue::Number(format!("{:?}", r), false),
+        Value::Text(s) => SqlValue::SingleQuotedString(s.to_string()),
+        Value::Bool(b) => SqlValue::Boolean(*b),
+        Value::Blob(bytes) => {
+            SqlValue::HexStringLiteral(bytes.iter().map(|b| format!("{:02X}", b)).collect())
+        }
+    })
+}
+
+/// Returns true if values can be bound to the parameters of a statement
+fn accepts_parameters(command: &ParsedCommand) -> bool {
+    match command {
+        ParsedCommand::InsertDefaultValues(_) => true,
+        ParsedCommand::Statement(parsed) => match &parsed.statement {
+            Statement::Explain { statement, .. } => matches!(
+                **statement,
+                Statement::Query(_)
+                    | Statement::Insert { .. }
+                    | Statement::Update { .. }
+                    | Statement::Delete { .. }
+            ),
+            Statement::Query(_)
+            | Statement::Insert { .. }
+            | Statement::Update { .. }
+            | Statement::Delete { .. } => true,
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// Plans a SELECT, UPDATE or DELETE statement, returning `None` for other statements.
+/// Views changed through their INSTEAD OF triggers are not planned.
+fn plan_statement(statement: &Statement, db: &Database) -> Result<Option<QueryPlan>> {
+    let plan = match statement {
+        Statement::Query(_) => plan_select(&SelectQuery::new(statement)?, db)?,
+        Statement::Update { .. } => {
+            let query = UpdateQuery::new(statement, None)?;
+            match db.contains_view(&query.table_name) {
+                true => return Ok(None),
+                false => plan_table_rows(db, &query.table_name, query.selection.as_ref(), None)?.1,
+            }
+        }
+        Statement::Delete { .. } => {
+            let query = DeleteQuery::new(statement, None)?;
+            match db.contains_view(&query.table_name) {
+                true => return Ok(None),
+                false => plan_table_rows(db, &query.table_name, query.selection.as_ref(), None)?.1,
+            }
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some(plan))
+}
+
+/// Names the output columns computed from parameters after the parameters as they were
+/// written, `:name` for instance, instead of the identifiers they were replaced by.
+/// Only the columns of a SELECT statement and of a RETURNING clause are named this way.
+fn name_parameter_columns(command: &mut ParsedCommand, names: &[Option<String>]) {
+    let items: Vec<&mut SelectItem> = match command {
+        ParsedCommand::InsertDefaultValues(query) => query.returning.iter_mut().flatten().collect(),
+        ParsedCommand::Statement(parsed) => {
+            let mut items: Vec<&mut SelectItem> = parsed.returning.iter_mut().flatten().collect();
+            if let Statement::Query(query) = &mut parsed.statement {
+                if let SetExpr::Select(select) = &mut query.body {
+                    items.extend(select.projection.iter_mut());
+                }
+            }
+            items
+        }
+        _ => return,
+    };
+    for item in items {
+        if let SelectItem::UnnamedExpr(expr) = item {
+            let mut named = expr.clone();
+            let mut has_parameters = false;
+            walk_expr_mut(&mut named, &mut |e| {
+                if let Some(index) = parameter_index(e) {
+                    let name = names.get(index - 1).cloned().flatten();
+                    *e = Expr::Identifier(Ident::new(name.unwrap_or_else(|| String::from("?"))));
+                    has_parameters = true;
+                }
+            });
+            if has_parameters {
+                *item = SelectItem::ExprWithAlias {
+                    expr: expr.clone(),
+                    alias: Ident::new(named.to_string()),
+                };
+            }
+        }
+    }
+}
+
+/// Returns the names of the tables and views a statement refers to
+fn command_tables(command: &ParsedCommand) -> Vec<String> {
+    let mut tables: Vec<String> = vec![];
+    match command {
+        ParsedCommand::InsertDefaultValues(query) => tables.push(query.table_name.to_string()),
+        ParsedCommand::Statement(parsed) => statement_tables(&parsed.statement, &mut tables),
+        _ => (),
+    }
+    tables.sort();
+    tables.dedup();
+    tables
+}
+
+fn statement_tables(statement: &Statement, tables: &mut Vec<String>) {
+    match statement {
+        Statement::Query(query) => query_tables(query, tables),
+        Statement::Insert {
+            table_name, source, ..
+        } => {
+            tables.push(table_name.to_string());
+            query_tables(source, tables);
+        }
+        Statement::Update { table, .. } => {
+            let relations = std::iter::once(&table.relation)
+                .chain(table.joins.iter().map(|join| &join.relation));
+            relations.for_each(|relation| relation_tables(relation, tables));
+        }
+        Statement::Delete { table_name, .. } => tables.push(table_name.to_string()),
+        Statement::Explain { statement, .. } => statement_tables(statement, tables),
+        _ => (),
+    }
+}
+
+fn query_tables(query: &Query, tables: &mut Vec<String>) {
+    set_expr_tables(&query.body, tables);
+}
+
+fn set_expr_tables(body: &SetExpr, tables: &mut Vec<String>) {
+    match body {
+        SetExpr::Select(select) => {
+            for table in &select.from {
+                relation_tables(&table.relation, tables);
+                for join in &table.joins {
+                    relation_tables(&join.relation, tables);
+                }
+            }
+        }
+        SetExpr::Query(query) => query_tables(query, tables),
+        SetExpr::SetOperation { left, right, .. } => {
+            set_expr_tables(left, tables);
+            set_expr_tables(right, tables);
+        }
+        SetExpr::Insert(statement) => statement_tables(statement, tables),
+        SetExpr::Values(_) => (),
+    }
+}
+
+fn relation_tables(relation: &TableFactor, tables: &mut Vec<String>) {
+    match relation {
+        TableFactor::Table { name, .. } => tables.push(name.to_string()),
+        TableFactor::Derived { subquery, .. } => query_tables(subquery, tables),
+        TableFactor::NestedJoin(table) => {
+            relation_tables(&table.relation, tables);
+            for join in &table.joins {
+                relation_tables(&join.relation, tables);
+            }
+        }
+        _ => (),
+    }
+}
+
+/// Calls `visit` on every expression of a SELECT, INSERT, UPDATE or DELETE statement
+/// and the expressions nested in them, allowing them to be rewritten in place
+fn visit_expressions(command: &mut ParsedCommand, visit: &mut dyn FnMut(&mut Expr)) {
+    let parsed = match command {
+        ParsedCommand::InsertDefaultValues(query) => {
+            visit_select_items(query.returning.iter_mut().flatten(), visit);
+            return;
+        }
+        ParsedCommand::Statement(parsed) => parsed,
+        _ => return,
+    };
+    visit_select_items(parsed.returning.iter_mut().flatten(), visit);
+    if let Some(limit) = &mut parsed.limit {
+        let exprs = std::iter::once(&mut limit.limit).chain(&mut limit.offset);
+        exprs.for_each(|e| walk_expr_mut(e, visit));
+    }
+    if let Some(upsert) = &mut parsed.upsert {
+        if let UpsertAction::Update {
+            assignments,
+            selection,
+        } = &mut upsert.action
+        {
+            let exprs = assignments.iter_mut().map(|(_, e)| e).chain(selection);
+            exprs.for_each(|e| walk_expr_mut(e, visit));
+        }
+    }
+    if let Some(plan) = &mut parsed.plan {
+        visit_plan(plan, visit);
+    }
+    visit_statement(&mut parsed.statement, visit);
+}
+
+/// Calls `visit` on every expression of a plan, see `visit_expressions`
+fn visit_plan(plan: &mut QueryPlan, visit: &mut dyn FnMut(&mut Expr)) {
+    for table in &mut plan.tables {
+        match &mut table.access {
+            AccessPath::FullScan => (),
+            AccessPath::RowidLookup(e) => walk_expr_mut(e, visit),
+            AccessPath::RowidRange { lower, upper } => {
+                let bounds = lower.iter_mut().chain(upper).map(|bound| &mut bound.expr);
+                bounds.for_each(|e| walk_expr_mut(e, visit));
+            }
+            AccessPath::IndexScan {
+                prefix,
+                lower,
+                upper,
+                ..
+            } => {
+                let bounds = lower.iter_mut().chain(upper).map(|bound| &mut bound.expr);
+                prefix
+                    .iter_mut()
+                    .chain(bounds)
+                    .for_each(|e| walk_expr_mut(e, visit));
+            }
+        }
+        table
+            .filter
+            .iter_mut()
+            .for_each(|e| walk_expr_mut(e, visit));
+    }
+    plan.residual
+        .iter_mut()
+        .for_each(|e| walk_expr_mut(e, visit));
+}
+
+fn visit_statement(statement: &mut Statement, visit: &mut dyn FnMut(&mut Expr)) {
+    match statement {
+        Statement::Query(query) | Statement::Insert { source: query, .. } => {
+            visit_query(query, visit)
+        }
+        Statement::Update {
+            assignments,
+            selection,
+            ..
+        } => {
+            let exprs = assignments
+                .iter_mut()
+                .map(|a| &mut a.value)
+                .chain(selection);
+            exprs.for_each(|e| walk_expr_mut(e, visit));
+        }
+        Statement::Delete { selection, .. } => {
+            selection.iter_mut().for_each(|e| walk_expr_mut(e, visit))
+        }
+        Statement::Explain { statement, .. } => visit_statement(statement, visit),
+        _ => (),
+    }
+}
+
+fn visit_query(query: &mut Query, visit: &mut dyn FnMut(&mut Expr)) {
+    visit_set_expr(&mut query.body, visit);
+    let order_by = query.order_by.iter_mut().map(|term| &mut term.expr);
+    let offset = query.offset.iter_mut().map(|offset| &mut offset.value);
+    let exprs = order_by.chain(&mut query.limit).chain(offset);
+    exprs.for_each(|e| walk_expr_mut(e, visit));
+}
+
+fn visit_set_expr(body: &mut SetExpr, visit: &mut dyn FnMut(&mut Expr)) {
+    match body {
+        SetExpr::Select(select) => {
+            visit_select_items(select.projection.iter_mut(), visit);
+            for table in &mut select.from {
+                for join in &mut table.joins {
+                    if let JoinOperator::Inner(JoinConstraint::On(e))
+                    | JoinOperator::LeftOuter(JoinConstraint::On(e))
+                    | JoinOperator::RightOuter(JoinConstraint::On(e))
+                    | JoinOperator::FullOuter(JoinConstraint::On(e)) = &mut join.join_operator
+                    {
+                        walk_expr_mut(e, visit);
+                    }
+                }
+            }
+            let exprs = select
+                .selection
+                .iter_mut()
+                .chain(&mut select.group_by)
+                .chain(&mut select.having);
+            exprs.for_each(|e| walk_expr_mut(e, visit));
+        }
+        SetExpr::Values(values) => values
+            .0
+            .iter_mut()
+            .flatten()
+            .for_each(|e| walk_expr_mut(e, visit)),
+        SetExpr::Query(query) => visit_query(query, visit),
+        SetExpr::SetOperation { left, right, .. } => {
+            visit_set_expr(left, visit);
+            visit_set_expr(right, visit);
+        }
+        SetExpr::Insert(statement) => visit_statement(statement, visit),
+    }
+}
+
+fn visit_select_items<'a>(
+    items: impl Iterator<Item = &'a mut SelectItem>,
+    visit: &mut dyn FnMut(&mut Expr),
+) {
+    for item in items {
+        if let SelectItem::UnnamedExpr(e) | SelectItem::ExprWithAlias { expr: e, .. } = item {
+            walk_expr_mut(e, visit);
+        }
+    }
+}
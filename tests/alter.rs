@@ -0,0 +1,93 @@
+mod common;
+
+use common::query;
+use rustdb::{Connection, Value};
+
+fn open() -> Connection {
+    let mut conn = Connection::open(":memory:").unwrap();
+    conn.execute("CREATE TABLE t (id INTEGER PRIMARY KEY, name TEXT);", &[])
+        .unwrap();
+    conn.execute("INSERT INTO t (id, name) VALUES (1, 'a'), (2, 'b');", &[])
+        .unwrap();
+    conn
+}
+
+#[test]
+fn added_column_is_back_filled_with_its_default() {
+    let mut conn = open();
+    conn.execute("ALTER TABLE t ADD COLUMN score INTEGER DEFAULT 5;", &[])
+        .unwrap();
+    conn.execute("ALTER TABLE t ADD COLUMN note TEXT;", &[])
+        .unwrap();
+    conn.execute("INSERT INTO t (id, name, score) VALUES (3, 'c', 9);", &[])
+        .unwrap();
+    assert_eq!(
+        query(&mut conn, "SELECT id, score, note FROM t;"),
+        vec![
+            vec![Value::Integer(1), Value::Integer(5), Value::Null],
+            vec![Value::Integer(2), Value::Integer(5), Value::Null],
+            vec![Value::Integer(3), Value::Integer(9), Value::Null],
+        ]
+    );
+
+    // Existing rows could not be given a value
+    assert!(conn
+        .execute("ALTER TABLE t ADD COLUMN code TEXT NOT NULL;", &[])
+        .is_err());
+    assert!(conn
+        .execute("ALTER TABLE t ADD COLUMN name TEXT;", &[])
+        .is_err());
+}
+
+#[test]
+fn renamed_column_keeps_its_values() {
+    let mut conn = open();
+    conn.execute("ALTER TABLE t RENAME COLUMN name TO label;", &[])
+        .unwrap();
+    assert_eq!(
+        query(&mut conn, "SELECT label FROM t WHERE id = 2;"),
+        vec![vec![Value::Text("b".into())]]
+    );
+    assert!(conn.query("SELECT name FROM t;", &[]).is_err());
+
+    // The PRIMARY KEY follows its column
+    conn.execute("ALTER TABLE t RENAME COLUMN id TO key;", &[])
+        .unwrap();
+    assert!(conn
+        .execute("INSERT INTO t (key, label) VALUES (1, 'z');", &[])
+        .is_err());
+    assert_eq!(
+        query(&mut conn, "SELECT key FROM t WHERE label = 'a';"),
+        vec![vec![Value::Integer(1)]]
+    );
+}
+
+#[test]
+fn dropped_column_is_gone() {
+    let mut conn = open();
+    conn.execute("ALTER TABLE t DROP COLUMN name;", &[])
+        .unwrap();
+    let rows = conn.query("SELECT * FROM t;", &[]).unwrap();
+    assert_eq!(rows.columns(), ["id"]);
+    assert_eq!(
+        rows.collect::<Vec<_>>(),
+        vec![vec![Value::Integer(1)], vec![Value::Integer(2)]]
+    );
+    assert!(conn
+        .execute("ALTER TABLE t DROP COLUMN name;", &[])
+        .is_err());
+}
+
+#[test]
+fn renamed_table_moves_its_rows() {
+    let mut conn = open();
+    conn.execute("ALTER TABLE t RENAME TO u;", &[]).unwrap();
+    assert!(conn.query("SELECT id FROM t;", &[]).is_err());
+    assert_eq!(
+        query(&mut conn, "SELECT name FROM u;"),
+        vec![vec![Value::Text("a".into())], vec![Value::Text("b".into())]]
+    );
+    conn.execute("CREATE TABLE t (id INTEGER PRIMARY KEY);", &[])
+        .unwrap();
+    assert!(conn.execute("ALTER TABLE u RENAME TO t;", &[]).is_err());
+}
@@ -0,0 +1,74 @@
+mod common;
+
+use common::query;
+use rustdb::{Connection, Value};
+
+fn open() -> Connection {
+    let mut conn = Connection::open(":memory:").unwrap();
+    conn.execute(
+        "CREATE TABLE t (id INTEGER PRIMARY KEY, flag INTEGER, name TEXT);",
+        &[],
+    )
+    .unwrap();
+    conn.execute("CREATE INDEX ix_flag ON t (flag);", &[])
+        .unwrap();
+    for flag in [1, 1, 1, 1, 1, 1, 2] {
+        conn.execute("INSERT INTO t (flag) VALUES (?);", &[flag.into()])
+            .unwrap();
+    }
+    conn
+}
+
+/// Returns the lines of the plan of a query, header excluded
+fn plan(conn: &mut Connection, sql: &str) -> Vec<Value> {
+    let mut lines: Vec<Value> = query(conn, &format!("EXPLAIN QUERY PLAN {}", sql))
+        .into_iter()
+        .flatten()
+        .collect();
+    lines.remove(0);
+    lines
+}
+
+#[test]
+fn histograms_tell_index_searches_from_full_scans() {
+    let mut conn = open();
+    let common = "SELECT name FROM t WHERE flag = 1;";
+    let rare = "SELECT name FROM t WHERE flag = 2;";
+    let search = vec![Value::Text(
+        "`--SEARCH t USING INDEX ix_flag (flag=?)".into(),
+    )];
+    // Without statistics every value is assumed to be rare
+    assert_eq!(plan(&mut conn, common), search);
+
+    conn.execute("ANALYZE t;", &[]).unwrap();
+    assert_eq!(plan(&mut conn, common), [Value::Text("`--SCAN t".into())]);
+    assert_eq!(plan(&mut conn, rare), search);
+    assert_eq!(query(&mut conn, common).len(), 6);
+}
+
+#[test]
+fn row_counts_order_the_joins() {
+    let mut conn = open();
+    conn.execute("CREATE TABLE big (id INTEGER PRIMARY KEY);", &[])
+        .unwrap();
+    for _ in 0..12 {
+        conn.execute("INSERT INTO big DEFAULT VALUES;", &[])
+            .unwrap();
+    }
+    conn.execute("ANALYZE;", &[]).unwrap();
+    // The smaller table is read once, by the outer loop
+    assert_eq!(
+        plan(&mut conn, "SELECT * FROM big, t;"),
+        [
+            Value::Text("|--SCAN t".into()),
+            Value::Text("`--SCAN big (INNER JOIN, NESTED LOOP)".into())
+        ]
+    );
+    assert_eq!(query(&mut conn, "SELECT * FROM big, t;").len(), 12 * 7);
+}
+
+#[test]
+fn analyze_of_a_missing_table_fails() {
+    let mut conn = open();
+    assert!(conn.execute("ANALYZE missing;", &[]).is_err());
+}
@@ -0,0 +1,63 @@
+use rustdb::sql::batch::{execute_batch, split_statements, BatchResult, OnError};
+use rustdb::sql::cache::{StatementCache, STATEMENT_CACHE_CAPACITY};
+use rustdb::sql::db::database::Database;
+
+const SCRIPT: &str = "CREATE TABLE t (id INTEGER PRIMARY KEY, n INTEGER);
+INSERT INTO t (n) VALUES (1);
+INSERT INTO missing (n) VALUES (2);
+INSERT INTO t (n) VALUES (3);
+SELECT n FROM t;";
+
+/// Runs a script on a new database, returning the outcome and the positions reported
+fn run(script: &str, on_error: OnError) -> (BatchResult, Vec<(usize, usize)>, Database) {
+    let mut db = Database::new("tempdb".to_string());
+    let mut cache = StatementCache::new(STATEMENT_CACHE_CAPACITY);
+    let mut reported = vec![];
+    let result = execute_batch(script, &mut db, &mut cache, on_error, &mut |i, count, _| {
+        reported.push((i, count))
+    })
+    .unwrap();
+    (result, reported, db)
+}
+
+fn rows(db: &Database) -> usize {
+    db.get_table("t".to_string()).unwrap().rowids().len()
+}
+
+#[test]
+fn every_statement_runs_without_bail() {
+    let (result, reported, db) = run(SCRIPT, OnError::Continue);
+    assert_eq!(result.results.len(), 5);
+    assert_eq!((result.failed(), result.skipped), (1, 0));
+    assert_eq!(reported, (0..5).map(|i| (i, 5)).collect::<Vec<_>>());
+    assert_eq!(result.results[1].statement, "INSERT INTO t (n) VALUES (1);");
+    assert!(result.results[1].result.is_ok());
+    assert!(result.results[2].result.is_err());
+    assert!(result.results[3].result.is_ok());
+    assert_eq!(rows(&db), 2);
+}
+
+#[test]
+fn bail_skips_the_rest_of_the_batch() {
+    let (result, reported, db) = run(SCRIPT, OnError::Stop);
+    assert_eq!(result.results.len(), 3);
+    assert_eq!((result.failed(), result.skipped), (1, 2));
+    assert_eq!(reported.len(), 3);
+    assert_eq!(rows(&db), 1);
+}
+
+#[test]
+fn scripts_are_split_on_semicolons() {
+    let statements = split_statements(
+        "SELECT ';'; -- a comment; still a comment\n\
+         CREATE TRIGGER tr AFTER INSERT ON t BEGIN DELETE FROM t; DELETE FROM t; END;\n\
+         SELECT 1",
+    )
+    .unwrap();
+    assert_eq!(statements.len(), 3);
+    // Comments stay with the statement after them
+    assert!(statements[1].starts_with("-- a comment"));
+    assert!(statements[1].contains("\nCREATE TRIGGER tr"));
+    assert!(statements[1].ends_with("END;"));
+    assert!(split_statements("  -- nothing\n").unwrap().is_empty());
+}
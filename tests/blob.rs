@@ -0,0 +1,26 @@
+use rustdb::{Connection, SQLRiteError, Value};
+
+#[test]
+fn zeroblob_returns_zeroed_bytes() {
+    let mut conn = Connection::open(":memory:").unwrap();
+    let rows: Vec<Vec<Value>> = conn
+        .query("SELECT zeroblob(3), length(zeroblob(3));", &[])
+        .unwrap()
+        .collect();
+    assert_eq!(
+        rows,
+        vec![vec![Value::Blob(vec![0, 0, 0]), Value::Integer(3)]]
+    );
+}
+
+#[test]
+fn zeroblob_rejects_sizes_above_max_length() {
+    let mut conn = Connection::open(":memory:").unwrap();
+    let err = conn
+        .query("SELECT length(zeroblob(1000000000000));", &[])
+        .unwrap_err();
+    assert_eq!(
+        err,
+        SQLRiteError::General(String::from("string or blob too big"))
+    );
+}
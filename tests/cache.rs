@@ -0,0 +1,121 @@
+use rustdb::sql::cache::{StatementCache, STATEMENT_CACHE_CAPACITY};
+use rustdb::sql::db::database::Database;
+use rustdb::{Connection, Value};
+
+#[test]
+fn cached_statement_sees_schema_changes() {
+    let mut conn = Connection::open(":memory:").unwrap();
+    conn.execute("CREATE TABLE t (id INTEGER PRIMARY KEY);", &[])
+        .unwrap();
+    conn.execute("INSERT INTO t (id) VALUES (1);", &[]).unwrap();
+    assert_eq!(
+        conn.query("SELECT * FROM t;", &[]).unwrap().columns(),
+        ["id"]
+    );
+
+    conn.execute("ALTER TABLE t ADD COLUMN name TEXT;", &[])
+        .unwrap();
+    conn.execute("CREATE INDEX ix ON t (name);", &[]).unwrap();
+    let rows = conn.query("SELECT * FROM t;", &[]).unwrap();
+    assert_eq!(rows.columns(), ["id", "name"]);
+    assert_eq!(
+        rows.collect::<Vec<_>>(),
+        vec![vec![Value::Integer(1), Value::Null]]
+    );
+
+    conn.execute("DROP TABLE t;", &[]).unwrap();
+    assert!(conn.query("SELECT * FROM t;", &[]).is_err());
+}
+
+/// Runs a statement through the cache, as the REPL does
+fn run(cache: &mut StatementCache, db: &mut Database, sql: &str) {
+    cache.prepare(sql, db).unwrap().execute(db).unwrap();
+}
+
+#[test]
+fn least_recently_used_statement_is_evicted() {
+    let db = Database::new(String::from("main"));
+    let mut cache = StatementCache::new(STATEMENT_CACHE_CAPACITY);
+    for i in 0..STATEMENT_CACHE_CAPACITY {
+        cache.prepare(&format!("SELECT {};", i), &db).unwrap();
+    }
+    assert_eq!(cache.len(), STATEMENT_CACHE_CAPACITY);
+
+    // Taking the first statement from the cache makes the second one the least recently used
+    cache.prepare("SELECT 0;", &db).unwrap();
+    assert_eq!(cache.len(), STATEMENT_CACHE_CAPACITY);
+    cache.prepare("SELECT 1000;", &db).unwrap();
+    assert_eq!(cache.len(), STATEMENT_CACHE_CAPACITY);
+    assert!(cache.contains("SELECT 0;"));
+    assert!(!cache.contains("SELECT 1;"));
+    assert!(cache.contains("SELECT 2;"));
+    assert!(cache.contains("SELECT 1000;"));
+}
+
+#[test]
+fn only_select_insert_update_and_delete_are_kept() {
+    let mut db = Database::new(String::from("main"));
+    let mut cache = StatementCache::new(STATEMENT_CACHE_CAPACITY);
+    let kept = [
+        "SELECT id FROM t;",
+        "INSERT INTO t (id) VALUES (1);",
+        "UPDATE t SET id = 2;",
+        "DELETE FROM t;",
+    ];
+    let not_kept = [
+        "CREATE TABLE t (id INTEGER PRIMARY KEY);",
+        "CREATE INDEX ix ON t (id);",
+        "ANALYZE t;",
+        "PRAGMA foreign_keys = ON;",
+        "BEGIN;",
+        "COMMIT;",
+    ];
+    run(&mut cache, &mut db, not_kept[0]);
+    for sql in kept.iter().chain(&not_kept[1..]) {
+        run(&mut cache, &mut db, sql);
+    }
+    for sql in kept {
+        assert!(cache.contains(sql), "{} is not kept", sql);
+    }
+    for sql in not_kept {
+        assert!(!cache.contains(sql), "{} is kept", sql);
+    }
+    assert_eq!(cache.len(), kept.len());
+}
+
+#[test]
+fn statement_is_prepared_again_once_its_tables_change() {
+    let mut db = Database::new(String::from("main"));
+    let mut cache = StatementCache::new(STATEMENT_CACHE_CAPACITY);
+    run(
+        &mut cache,
+        &mut db,
+        "CREATE TABLE t (id INTEGER PRIMARY KEY);",
+    );
+    run(
+        &mut cache,
+        &mut db,
+        "CREATE TABLE u (id INTEGER PRIMARY KEY);",
+    );
+    let sql = "SELECT id FROM t;";
+    assert!(cache.prepare(sql, &db).unwrap().is_current(&db));
+
+    // Other tables do not matter
+    run(&mut cache, &mut db, "CREATE INDEX iu ON u (id);");
+    assert!(cache.prepare(sql, &db).unwrap().is_current(&db));
+
+    for change in [
+        "CREATE INDEX it ON t (id);",
+        "ANALYZE t;",
+        "DROP INDEX it;",
+        "ALTER TABLE t ADD COLUMN name TEXT;",
+    ] {
+        run(&mut cache, &mut db, change);
+        assert!(
+            cache.prepare(sql, &db).unwrap().is_current(&db),
+            "{} did not prepare the statement again",
+            change
+        );
+    }
+    assert_eq!(cache.len(), 1);
+}
@@ -0,0 +1,78 @@
+mod common;
+
+use common::query;
+use rustdb::{Connection, Value};
+
+fn open() -> Connection {
+    let mut conn = Connection::open(":memory:").unwrap();
+    conn.execute(
+        "CREATE TABLE t (id INTEGER PRIMARY KEY, \
+         age INTEGER CONSTRAINT adult CHECK (age >= 18), \
+         lo INTEGER, hi INTEGER CHECK (hi < 100), \
+         CHECK (lo < hi), CONSTRAINT positive CHECK (lo > 0));",
+        &[],
+    )
+    .unwrap();
+    conn
+}
+
+/// Returns the error of a statement that must fail
+fn error(conn: &mut Connection, sql: &str) -> String {
+    conn.execute(sql, &[]).unwrap_err().to_string()
+}
+
+#[test]
+fn violations_name_the_constraint() {
+    let mut conn = open();
+    for (sql, constraint) in [
+        ("INSERT INTO t (age, lo, hi) VALUES (10, 1, 2);", "adult"),
+        (
+            "INSERT INTO t (age, lo, hi) VALUES (20, 1, 200);",
+            "hi < 100",
+        ),
+        ("INSERT INTO t (age, lo, hi) VALUES (20, 3, 2);", "lo < hi"),
+        (
+            "INSERT INTO t (age, lo, hi) VALUES (20, -1, 2);",
+            "positive",
+        ),
+    ] {
+        let error = error(&mut conn, sql);
+        assert!(
+            error.ends_with(&format!("CHECK constraint failed: {}", constraint)),
+            "{}: {}",
+            sql,
+            error
+        );
+    }
+    assert!(query(&mut conn, "SELECT id FROM t;").is_empty());
+}
+
+#[test]
+fn null_satisfies_a_check() {
+    let mut conn = open();
+    conn.execute("INSERT INTO t (age, lo, hi) VALUES (NULL, 1, 2);", &[])
+        .unwrap();
+    conn.execute("INSERT INTO t (age) VALUES (30);", &[])
+        .unwrap();
+    assert_eq!(query(&mut conn, "SELECT id FROM t;").len(), 2);
+}
+
+#[test]
+fn updates_are_checked() {
+    let mut conn = open();
+    conn.execute("INSERT INTO t (age, lo, hi) VALUES (20, 1, 2);", &[])
+        .unwrap();
+    let failed = error(&mut conn, "UPDATE t SET age = 5;");
+    assert!(failed.ends_with("CHECK constraint failed: adult"));
+    let failed = error(&mut conn, "UPDATE t SET lo = 5;");
+    assert!(failed.ends_with("CHECK constraint failed: lo < hi"));
+    conn.execute("UPDATE t SET lo = 5, hi = 6;", &[]).unwrap();
+    assert_eq!(
+        query(&mut conn, "SELECT age, lo, hi FROM t;"),
+        vec![vec![
+            Value::Integer(20),
+            Value::Integer(5),
+            Value::Integer(6)
+        ]]
+    );
+}
@@ -0,0 +1,103 @@
+mod common;
+
+use common::query;
+use rustdb::{Connection, Value};
+
+#[test]
+fn quoted_column_names_are_stored_without_quotes() {
+    let mut conn = Connection::open(":memory:").unwrap();
+    conn.execute(
+        "CREATE TABLE t (id INTEGER PRIMARY KEY, \"my col\" TEXT);",
+        &[],
+    )
+    .unwrap();
+    conn.execute("INSERT INTO t (id, \"my col\") VALUES (1, 'm');", &[])
+        .unwrap();
+
+    assert_eq!(
+        query(&mut conn, "SELECT id FROM t WHERE \"my col\" = 'm';"),
+        vec![vec![Value::Integer(1)]]
+    );
+    let rows = conn.query("SELECT \"my col\" FROM t;", &[]).unwrap();
+    assert_eq!(rows.columns(), ["my col"]);
+    assert_eq!(
+        rows.collect::<Vec<_>>(),
+        vec![vec![Value::Text(String::from("m"))]]
+    );
+
+    conn.execute("CREATE INDEX ix ON t (\"my col\");", &[])
+        .unwrap();
+    conn.execute(
+        "ALTER TABLE t RENAME COLUMN \"my col\" TO \"other col\";",
+        &[],
+    )
+    .unwrap();
+    assert_eq!(
+        query(&mut conn, "SELECT \"other col\" FROM t;"),
+        vec![vec![Value::Text(String::from("m"))]]
+    );
+}
+
+#[test]
+fn real_values_out_of_integer_range_are_rejected() {
+    let mut conn = Connection::open(":memory:").unwrap();
+    conn.execute("CREATE TABLE t (id INTEGER PRIMARY KEY, n INTEGER);", &[])
+        .unwrap();
+    conn.execute("INSERT INTO t (id, n) VALUES (1, 5);", &[])
+        .unwrap();
+
+    assert!(conn
+        .execute("UPDATE t SET n = 1000000000000000000000.0;", &[])
+        .is_err());
+    conn.execute("UPDATE t SET n = 7.0;", &[]).unwrap();
+    assert_eq!(
+        query(&mut conn, "SELECT n FROM t;"),
+        vec![vec![Value::Integer(7)]]
+    );
+}
+
+#[test]
+fn type_names_are_given_an_affinity() {
+    let mut conn = Connection::open(":memory:").unwrap();
+    conn.execute(
+        "CREATE TABLE t (a VARCHAR(10), b MEDIUMINT, c NUMERIC, d LONGTEXT, e DOUBLE);",
+        &[],
+    )
+    .unwrap();
+    conn.execute(
+        "INSERT INTO t (a, b, c, d, e) VALUES ('x', '7', 2.5, 'y', 1);",
+        &[],
+    )
+    .unwrap();
+    assert_eq!(
+        query(&mut conn, "SELECT a, b, c, d, e FROM t;"),
+        vec![vec![
+            Value::Text("x".into()),
+            Value::Integer(7),
+            Value::Real(2.5),
+            Value::Text("y".into()),
+            Value::Real(1.0),
+        ]]
+    );
+}
+
+#[test]
+fn unsupported_type_names_are_rejected() {
+    let mut conn = Connection::open(":memory:").unwrap();
+    assert!(conn
+        .execute(
+            "CREATE TABLE t (a VARCHAR(10), b DATETIME, c NUMERIC);",
+            &[]
+        )
+        .is_err());
+    assert!(conn.query("SELECT * FROM t;", &[]).is_err());
+
+    conn.execute("CREATE TABLE t (a TEXT);", &[]).unwrap();
+    assert!(conn
+        .execute("ALTER TABLE t ADD COLUMN b DATE;", &[])
+        .is_err());
+    assert_eq!(
+        conn.query("SELECT * FROM t;", &[]).unwrap().columns(),
+        ["a"]
+    );
+}
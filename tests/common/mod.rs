@@ -0,0 +1,6 @@
+use rustdb::{Connection, Value};
+
+/// Runs a query that must succeed and returns all of its rows
+pub fn query(conn: &mut Connection, sql: &str) -> Vec<Vec<Value>> {
+    conn.query(sql, &[]).unwrap().collect()
+}
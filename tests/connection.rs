@@ -0,0 +1,101 @@
+use rustdb::{Connection, Value};
+
+#[test]
+fn only_memory_databases_open() {
+    assert!(Connection::open(":memory:").is_ok());
+    let error = Connection::open("data.db").unwrap_err().to_string();
+    assert!(error.contains("cannot open data.db"), "{}", error);
+}
+
+#[test]
+fn execute_counts_the_rows_changed() {
+    let mut conn = Connection::open(":memory:").unwrap();
+    assert_eq!(
+        conn.execute("CREATE TABLE t (id INTEGER PRIMARY KEY, s TEXT);", &[])
+            .unwrap(),
+        0
+    );
+    assert_eq!(
+        conn.execute("INSERT INTO t (s) VALUES ('a'), ('b'), ('c');", &[])
+            .unwrap(),
+        3
+    );
+    assert_eq!(
+        conn.execute("UPDATE t SET s = ? WHERE id > ?;", &["x".into(), 1.into()])
+            .unwrap(),
+        2
+    );
+    assert_eq!(conn.execute("DELETE FROM t;", &[]).unwrap(), 3);
+    assert!(conn.execute("SELECT * FROM missing;", &[]).is_err());
+}
+
+#[test]
+fn query_returns_typed_values() {
+    let mut conn = Connection::open(":memory:").unwrap();
+    conn.execute(
+        "CREATE TABLE t (id INTEGER PRIMARY KEY, s TEXT, r REAL, b BOOL);",
+        &[],
+    )
+    .unwrap();
+    conn.execute(
+        "INSERT INTO t (s, r, b) VALUES (?, ?, ?), (NULL, NULL, NULL);",
+        &["a".into(), 1.5.into(), true.into()],
+    )
+    .unwrap();
+    let mut rows = conn.query("SELECT * FROM t;", &[]).unwrap();
+    assert_eq!(rows.columns(), ["id", "s", "r", "b"]);
+    assert_eq!(
+        rows.next(),
+        Some(vec![
+            Value::Integer(1),
+            Value::Text("a".into()),
+            Value::Real(1.5),
+            Value::Bool(true)
+        ])
+    );
+    assert_eq!(
+        rows.next(),
+        Some(vec![
+            Value::Integer(2),
+            Value::Null,
+            Value::Null,
+            Value::Null
+        ])
+    );
+    assert_eq!(rows.next(), None);
+
+    // Statements returning no rows return no columns either
+    let rows = conn.query("DELETE FROM t;", &[]).unwrap();
+    assert!(rows.columns().is_empty());
+    assert_eq!(rows.count(), 0);
+}
+
+#[test]
+fn prepared_statements_keep_their_bindings() {
+    let mut conn = Connection::open(":memory:").unwrap();
+    conn.execute("CREATE TABLE t (id INTEGER PRIMARY KEY, s TEXT);", &[])
+        .unwrap();
+    let mut stmt = conn
+        .prepare("INSERT INTO t (id, s) VALUES (?1, :s);")
+        .unwrap();
+    assert_eq!(stmt.parameter_count(), 2);
+    assert_eq!(stmt.parameter_index(":s"), Some(2));
+    stmt.bind_named(":s", "a").unwrap();
+    assert_eq!(stmt.execute(&[1.into()]).unwrap(), 1);
+    assert_eq!(stmt.execute(&[2.into()]).unwrap(), 1);
+    stmt.bind(2, "b").unwrap();
+    assert_eq!(stmt.execute(&[3.into()]).unwrap(), 1);
+    assert!(stmt.bind_named(":missing", 1).is_err());
+    assert!(stmt.bind(3, 1).is_err());
+
+    let rows: Vec<_> = conn.query("SELECT s FROM t;", &[]).unwrap().collect();
+    assert_eq!(
+        rows,
+        vec![
+            vec![Value::Text("a".into())],
+            vec![Value::Text("a".into())],
+            vec![Value::Text("b".into())],
+        ]
+    );
+    assert!(conn.prepare("SELECT 1; SELECT 2;").is_err());
+}
@@ -0,0 +1,74 @@
+mod common;
+
+use common::query;
+use rustdb::{Connection, Value};
+
+fn open() -> Connection {
+    let mut conn = Connection::open(":memory:").unwrap();
+    conn.execute(
+        "CREATE TABLE t (id INTEGER PRIMARY KEY, n INTEGER DEFAULT 5, s TEXT DEFAULT 'x', \
+         e INTEGER DEFAULT (2 * 3), neg INTEGER DEFAULT -1, \
+         ts TEXT DEFAULT CURRENT_TIMESTAMP, plain TEXT);",
+        &[],
+    )
+    .unwrap();
+    conn
+}
+
+#[test]
+fn omitted_columns_take_their_default() {
+    let mut conn = open();
+    conn.execute("INSERT INTO t (n, plain) VALUES (NULL, 'p');", &[])
+        .unwrap();
+    conn.execute("INSERT INTO t (s) VALUES ('y');", &[])
+        .unwrap();
+    assert_eq!(
+        query(&mut conn, "SELECT id, n, s, e, neg, plain FROM t;"),
+        vec![
+            vec![
+                Value::Integer(1),
+                Value::Null,
+                Value::Text("x".into()),
+                Value::Integer(6),
+                Value::Integer(-1),
+                Value::Text("p".into()),
+            ],
+            vec![
+                Value::Integer(2),
+                Value::Integer(5),
+                Value::Text("y".into()),
+                Value::Integer(6),
+                Value::Integer(-1),
+                Value::Null,
+            ],
+        ]
+    );
+}
+
+#[test]
+fn default_values_inserts_a_row_of_defaults() {
+    let mut conn = open();
+    conn.execute("INSERT INTO t DEFAULT VALUES;", &[]).unwrap();
+    let rows = query(&mut conn, "SELECT n, s, plain, ts FROM t;");
+    assert_eq!(rows.len(), 1);
+    assert_eq!(
+        rows[0][..3],
+        [Value::Integer(5), Value::Text("x".into()), Value::Null]
+    );
+    // CURRENT_TIMESTAMP is given as YYYY-MM-DD HH:MM:SS
+    match &rows[0][3] {
+        Value::Text(ts) => assert_eq!((ts.len(), &ts[4..5], &ts[10..11]), (19, "-", " ")),
+        value => panic!("not a timestamp: {:?}", value),
+    }
+}
+
+#[test]
+fn defaults_must_be_constant() {
+    let mut conn = open();
+    assert!(conn
+        .execute(
+            "CREATE TABLE u (a INTEGER DEFAULT (abs(b)), b INTEGER);",
+            &[]
+        )
+        .is_err());
+}
@@ -0,0 +1,43 @@
+mod common;
+
+use common::query;
+use rustdb::{Connection, Value};
+
+#[test]
+fn drop_table_refuses_a_referenced_table() {
+    let mut conn = Connection::open(":memory:").unwrap();
+    conn.execute("PRAGMA foreign_keys = ON;", &[]).unwrap();
+    conn.execute("CREATE TABLE parent (id INTEGER PRIMARY KEY);", &[])
+        .unwrap();
+    conn.execute(
+        "CREATE TABLE child (id INTEGER PRIMARY KEY, parent_id INTEGER REFERENCES parent (id));",
+        &[],
+    )
+    .unwrap();
+    conn.execute("INSERT INTO parent (id) VALUES (1);", &[])
+        .unwrap();
+
+    assert!(conn.execute("DROP TABLE parent;", &[]).is_err());
+    assert_eq!(
+        query(&mut conn, "SELECT id FROM parent;"),
+        vec![vec![Value::Integer(1)]]
+    );
+
+    conn.execute("PRAGMA foreign_keys = OFF;", &[]).unwrap();
+    conn.execute("DROP TABLE parent;", &[]).unwrap();
+    assert!(conn.query("SELECT id FROM parent;", &[]).is_err());
+}
+
+#[test]
+fn drop_table_removes_its_indexes() {
+    let mut conn = Connection::open(":memory:").unwrap();
+    conn.execute("CREATE TABLE t (id INTEGER PRIMARY KEY, name TEXT);", &[])
+        .unwrap();
+    conn.execute("CREATE INDEX ix ON t (name);", &[]).unwrap();
+    conn.execute("DROP TABLE t;", &[]).unwrap();
+    conn.execute("DROP TABLE IF EXISTS t;", &[]).unwrap();
+
+    conn.execute("CREATE TABLE t (id INTEGER PRIMARY KEY, name TEXT);", &[])
+        .unwrap();
+    conn.execute("CREATE INDEX ix ON t (name);", &[]).unwrap();
+}
@@ -0,0 +1,108 @@
+mod common;
+
+use common::query;
+use rustdb::{Connection, Value};
+
+fn open() -> Connection {
+    let mut conn = Connection::open(":memory:").unwrap();
+    conn.execute("CREATE TABLE a (id INTEGER PRIMARY KEY, x INTEGER);", &[])
+        .unwrap();
+    conn.execute(
+        "CREATE TABLE b (id INTEGER PRIMARY KEY, a_id INTEGER);",
+        &[],
+    )
+    .unwrap();
+    conn
+}
+
+/// Returns the lines of the output of an EXPLAIN statement
+fn explain(conn: &mut Connection, sql: &str) -> Vec<String> {
+    query(conn, sql)
+        .into_iter()
+        .map(|row| match &row[0] {
+            Value::Text(line) => line.to_string(),
+            value => panic!("not a line: {:?}", value),
+        })
+        .collect()
+}
+
+#[test]
+fn query_plan_shows_every_table_and_temp_b_tree() {
+    let mut conn = open();
+    assert_eq!(
+        explain(
+            &mut conn,
+            "EXPLAIN QUERY PLAN SELECT a.x FROM a JOIN b ON b.a_id = a.id ORDER BY a.x;"
+        ),
+        [
+            "QUERY PLAN",
+            "|--SCAN b",
+            "|--SEARCH a USING INTEGER PRIMARY KEY (rowid=?) (INNER JOIN, INDEX NESTED LOOP)",
+            "`--USE TEMP B-TREE FOR ORDER BY",
+        ]
+    );
+    assert_eq!(
+        explain(
+            &mut conn,
+            "EXPLAIN QUERY PLAN SELECT * FROM a LEFT JOIN b ON b.a_id = a.id;"
+        ),
+        [
+            "QUERY PLAN",
+            "|--SCAN a",
+            "`--SCAN b (LEFT JOIN, NESTED LOOP)"
+        ]
+    );
+    assert_eq!(
+        explain(
+            &mut conn,
+            "EXPLAIN QUERY PLAN SELECT x, count(*) FROM a GROUP BY x;"
+        ),
+        ["QUERY PLAN", "|--SCAN a", "`--USE TEMP B-TREE FOR GROUP BY"]
+    );
+    assert_eq!(
+        explain(&mut conn, "EXPLAIN QUERY PLAN SELECT 1;"),
+        ["QUERY PLAN", "`--SCAN CONSTANT ROW"]
+    );
+    assert_eq!(
+        explain(
+            &mut conn,
+            "EXPLAIN QUERY PLAN UPDATE a SET x = 1 WHERE id = 3;"
+        ),
+        [
+            "QUERY PLAN",
+            "`--SEARCH a USING INTEGER PRIMARY KEY (rowid=?)"
+        ]
+    );
+}
+
+#[test]
+fn explain_shows_the_operator_tree() {
+    let mut conn = open();
+    assert_eq!(
+        explain(
+            &mut conn,
+            "EXPLAIN SELECT a.x FROM a JOIN b ON b.a_id = a.id WHERE a.x > 1 ORDER BY a.x;"
+        ),
+        [
+            "Sort (a.x)",
+            "`--Project (a.x)",
+            "   `--NestedLoopJoin (INNER JOIN, INDEX NESTED LOOP)",
+            "      |--TableScan b (~1000 rows)",
+            "      `--Filter (a.x > 1 AND b.a_id = a.id)",
+            "         `--RowidLookup a (rowid=b.a_id) (~1 rows)",
+        ]
+    );
+}
+
+#[test]
+fn explain_runs_nothing() {
+    let mut conn = open();
+    conn.execute("INSERT INTO a (id, x) VALUES (1, 1);", &[])
+        .unwrap();
+    explain(&mut conn, "EXPLAIN DELETE FROM a WHERE x = 1;");
+    explain(&mut conn, "EXPLAIN QUERY PLAN DELETE FROM a;");
+    assert_eq!(
+        query(&mut conn, "SELECT id FROM a;"),
+        vec![vec![Value::Integer(1)]]
+    );
+}
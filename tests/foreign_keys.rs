@@ -0,0 +1,185 @@
+mod common;
+
+use common::query;
+use rustdb::{Connection, Value};
+
+fn open(child_actions: &str) -> Connection {
+    let mut conn = Connection::open(":memory:").unwrap();
+    conn.execute("PRAGMA foreign_keys = ON;", &[]).unwrap();
+    conn.execute("CREATE TABLE parent (id INTEGER PRIMARY KEY);", &[])
+        .unwrap();
+    conn.execute(
+        &format!(
+            "CREATE TABLE child (id INTEGER PRIMARY KEY, \
+             parent_id INTEGER REFERENCES parent (id) {});",
+            child_actions
+        ),
+        &[],
+    )
+    .unwrap();
+    conn.execute("INSERT INTO parent (id) VALUES (1);", &[])
+        .unwrap();
+    conn.execute("INSERT INTO parent (id) VALUES (2);", &[])
+        .unwrap();
+    conn.execute("INSERT INTO child (id, parent_id) VALUES (1, 1);", &[])
+        .unwrap();
+    conn.execute("INSERT INTO child (id, parent_id) VALUES (2, 2);", &[])
+        .unwrap();
+    conn
+}
+
+#[test]
+fn foreign_keys_are_off_by_default() {
+    let mut conn = Connection::open(":memory:").unwrap();
+    assert_eq!(
+        query(&mut conn, "PRAGMA foreign_keys;"),
+        vec![vec![Value::Integer(0)]]
+    );
+    conn.execute("CREATE TABLE parent (id INTEGER PRIMARY KEY);", &[])
+        .unwrap();
+    conn.execute(
+        "CREATE TABLE child (parent_id INTEGER REFERENCES parent (id));",
+        &[],
+    )
+    .unwrap();
+    conn.execute("INSERT INTO child (parent_id) VALUES (1);", &[])
+        .unwrap();
+}
+
+#[test]
+fn missing_parent_is_rejected() {
+    let mut conn = open("");
+    assert!(conn
+        .execute("INSERT INTO child (id, parent_id) VALUES (3, 3);", &[])
+        .is_err());
+    assert!(conn
+        .execute("UPDATE child SET parent_id = 3 WHERE id = 1;", &[])
+        .is_err());
+    conn.execute("INSERT INTO child (id, parent_id) VALUES (3, NULL);", &[])
+        .unwrap();
+    assert_eq!(
+        query(&mut conn, "SELECT id, parent_id FROM child;"),
+        vec![
+            vec![Value::Integer(1), Value::Integer(1)],
+            vec![Value::Integer(2), Value::Integer(2)],
+            vec![Value::Integer(3), Value::Null],
+        ]
+    );
+}
+
+#[test]
+fn on_delete_cascade_deletes_children() {
+    let mut conn = open("ON DELETE CASCADE");
+    conn.execute("DELETE FROM parent WHERE id = 1;", &[])
+        .unwrap();
+    assert_eq!(
+        query(&mut conn, "SELECT id FROM child;"),
+        vec![vec![Value::Integer(2)]]
+    );
+}
+
+#[test]
+fn on_delete_set_null_clears_children() {
+    let mut conn = open("ON DELETE SET NULL");
+    conn.execute("DELETE FROM parent WHERE id = 1;", &[])
+        .unwrap();
+    assert_eq!(
+        query(&mut conn, "SELECT id, parent_id FROM child;"),
+        vec![
+            vec![Value::Integer(1), Value::Null],
+            vec![Value::Integer(2), Value::Integer(2)],
+        ]
+    );
+}
+
+#[test]
+fn on_delete_restrict_keeps_every_row() {
+    let mut conn = open("ON DELETE RESTRICT");
+    assert!(conn.execute("DELETE FROM parent;", &[]).is_err());
+    assert_eq!(
+        query(&mut conn, "SELECT id FROM parent;"),
+        vec![vec![Value::Integer(1)], vec![Value::Integer(2)]]
+    );
+    assert_eq!(query(&mut conn, "SELECT id FROM child;").len(), 2);
+}
+
+#[test]
+fn on_update_cascade_updates_children() {
+    let mut conn = open("ON UPDATE CASCADE");
+    conn.execute("UPDATE parent SET id = 5 WHERE id = 2;", &[])
+        .unwrap();
+    assert_eq!(
+        query(&mut conn, "SELECT id, parent_id FROM child;"),
+        vec![
+            vec![Value::Integer(1), Value::Integer(1)],
+            vec![Value::Integer(2), Value::Integer(5)],
+        ]
+    );
+}
+
+#[test]
+fn on_update_restrict_keeps_the_parent() {
+    let mut conn = open("ON UPDATE RESTRICT");
+    assert!(conn
+        .execute("UPDATE parent SET id = 5 WHERE id = 2;", &[])
+        .is_err());
+    assert_eq!(
+        query(&mut conn, "SELECT id FROM parent;"),
+        vec![vec![Value::Integer(1)], vec![Value::Integer(2)]]
+    );
+}
+
+#[test]
+fn failed_cascade_is_undone_as_a_whole() {
+    let mut conn = open("ON DELETE CASCADE");
+    conn.execute(
+        "CREATE TABLE grandchild (child_id INTEGER REFERENCES child (id) ON DELETE RESTRICT);",
+        &[],
+    )
+    .unwrap();
+    conn.execute("INSERT INTO grandchild (child_id) VALUES (2);", &[])
+        .unwrap();
+
+    assert!(conn.execute("DELETE FROM parent;", &[]).is_err());
+    assert_eq!(
+        query(&mut conn, "SELECT id FROM parent;"),
+        vec![vec![Value::Integer(1)], vec![Value::Integer(2)]]
+    );
+    assert_eq!(
+        query(&mut conn, "SELECT id FROM child;"),
+        vec![vec![Value::Integer(1)], vec![Value::Integer(2)]]
+    );
+}
+
+#[test]
+fn deferred_keys_are_checked_at_commit() {
+    let mut conn = Connection::open(":memory:").unwrap();
+    conn.execute("PRAGMA foreign_keys = ON;", &[]).unwrap();
+    conn.execute("CREATE TABLE parent (id INTEGER PRIMARY KEY);", &[])
+        .unwrap();
+    conn.execute(
+        "CREATE TABLE child (parent_id INTEGER REFERENCES parent (id) \
+         DEFERRABLE INITIALLY DEFERRED);",
+        &[],
+    )
+    .unwrap();
+
+    conn.execute("BEGIN;", &[]).unwrap();
+    conn.execute("INSERT INTO child (parent_id) VALUES (1);", &[])
+        .unwrap();
+    assert!(conn.execute("COMMIT;", &[]).is_err());
+    // The transaction stays open for the missing parent to be added
+    conn.execute("INSERT INTO parent (id) VALUES (1);", &[])
+        .unwrap();
+    conn.execute("COMMIT;", &[]).unwrap();
+    assert_eq!(
+        query(&mut conn, "SELECT parent_id FROM child;"),
+        vec![vec![Value::Integer(1)]]
+    );
+
+    // Outside of a transaction, the statement itself is the transaction
+    assert!(conn
+        .execute("INSERT INTO child (parent_id) VALUES (2);", &[])
+        .is_err());
+    assert_eq!(query(&mut conn, "SELECT parent_id FROM child;").len(), 1);
+}
@@ -0,0 +1,101 @@
+mod common;
+
+use common::query;
+use rustdb::{Connection, Value};
+
+fn open() -> Connection {
+    let mut conn = Connection::open(":memory:").unwrap();
+    conn.execute(
+        "CREATE TABLE t (id INTEGER PRIMARY KEY, a INTEGER, b INTEGER, \
+         s INTEGER GENERATED ALWAYS AS (a + b) STORED, v TEXT AS (a || '-' || b) VIRTUAL);",
+        &[],
+    )
+    .unwrap();
+    conn.execute("INSERT INTO t (a, b) VALUES (1, 2), (3, 4);", &[])
+        .unwrap();
+    conn
+}
+
+/// Returns the error of a statement that must fail
+fn error(conn: &mut Connection, sql: &str) -> String {
+    conn.execute(sql, &[]).unwrap_err().to_string()
+}
+
+#[test]
+fn generated_columns_follow_their_inputs() {
+    let mut conn = open();
+    assert_eq!(
+        query(&mut conn, "SELECT s, v FROM t;"),
+        vec![
+            vec![Value::Integer(3), Value::Text("1-2".into())],
+            vec![Value::Integer(7), Value::Text("3-4".into())],
+        ]
+    );
+    conn.execute("UPDATE t SET b = 10 WHERE id = 1;", &[])
+        .unwrap();
+    assert_eq!(
+        query(&mut conn, "SELECT s, v FROM t WHERE id = 1;"),
+        vec![vec![Value::Integer(11), Value::Text("1-10".into())]]
+    );
+}
+
+#[test]
+fn generated_columns_cannot_be_written() {
+    let mut conn = open();
+    for (sql, message) in [
+        (
+            "INSERT INTO t (a, b, s) VALUES (1, 1, 5);",
+            "cannot INSERT into generated column \"s\"",
+        ),
+        (
+            "UPDATE t SET v = 'x';",
+            "cannot UPDATE generated column \"v\"",
+        ),
+        (
+            "INSERT INTO t VALUES (9, 1, 1, 2, 'x');",
+            "table t has 3 columns but 5 values were supplied",
+        ),
+        (
+            "CREATE TABLE w (a INTEGER AS (c), c INTEGER AS (a));",
+            "generated column loop on \"a\"",
+        ),
+    ] {
+        let error = error(&mut conn, sql);
+        assert!(error.ends_with(message), "{}: {}", sql, error);
+    }
+    // Rows without the generated columns are accepted
+    conn.execute("INSERT INTO t VALUES (9, 1, 1);", &[])
+        .unwrap();
+    assert_eq!(
+        query(&mut conn, "SELECT s FROM t WHERE id = 9;"),
+        vec![vec![Value::Integer(2)]]
+    );
+}
+
+#[test]
+fn generated_columns_can_be_indexed() {
+    let mut conn = open();
+    conn.execute("CREATE INDEX ix ON t (s);", &[]).unwrap();
+    conn.execute("CREATE INDEX iv ON t (v);", &[]).unwrap();
+    assert_eq!(
+        query(
+            &mut conn,
+            "EXPLAIN QUERY PLAN SELECT id FROM t WHERE s = 7;"
+        )
+        .last()
+        .unwrap(),
+        &vec![Value::Text("`--SEARCH t USING INDEX ix (s=?)".into())]
+    );
+    assert_eq!(
+        query(&mut conn, "SELECT id FROM t WHERE s = 7;"),
+        vec![vec![Value::Integer(2)]]
+    );
+    // The indexes follow updates of the inputs
+    conn.execute("UPDATE t SET b = 10 WHERE id = 1;", &[])
+        .unwrap();
+    assert_eq!(
+        query(&mut conn, "SELECT id FROM t WHERE v = '1-10';"),
+        vec![vec![Value::Integer(1)]]
+    );
+    assert!(query(&mut conn, "SELECT id FROM t WHERE s = 3;").is_empty());
+}
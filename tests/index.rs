@@ -0,0 +1,114 @@
+mod common;
+
+use common::query;
+use rustdb::{Connection, Value};
+
+fn open() -> Connection {
+    let mut conn = Connection::open(":memory:").unwrap();
+    conn.execute(
+        "CREATE TABLE t (id INTEGER PRIMARY KEY, city TEXT, age INTEGER);",
+        &[],
+    )
+    .unwrap();
+    conn.execute(
+        "INSERT INTO t (id, city, age) VALUES (1, 'Paris', 30), (2, 'Rome', 40), (3, 'Paris', 50);",
+        &[],
+    )
+    .unwrap();
+    conn
+}
+
+fn ids(conn: &mut Connection, sql: &str) -> Vec<i64> {
+    query(conn, sql)
+        .into_iter()
+        .map(|row| match row[0] {
+            Value::Integer(id) => id,
+            ref value => panic!("not an id: {:?}", value),
+        })
+        .collect()
+}
+
+#[test]
+fn non_unique_index_keeps_every_rowid_of_a_value() {
+    let mut conn = open();
+    conn.execute("CREATE INDEX ix_city ON t (city);", &[])
+        .unwrap();
+    conn.execute(
+        "INSERT INTO t (id, city, age) VALUES (4, 'Paris', 60);",
+        &[],
+    )
+    .unwrap();
+    assert_eq!(
+        query(
+            &mut conn,
+            "EXPLAIN QUERY PLAN SELECT id FROM t WHERE city = 'Paris';"
+        )
+        .last(),
+        Some(&vec![Value::Text(
+            "`--SEARCH t USING INDEX ix_city (city=?)".into()
+        )])
+    );
+    assert_eq!(
+        ids(&mut conn, "SELECT id FROM t WHERE city = 'Paris';"),
+        [1, 3, 4]
+    );
+
+    conn.execute("DELETE FROM t WHERE id = 3;", &[]).unwrap();
+    conn.execute("UPDATE t SET city = 'Rome' WHERE id = 4;", &[])
+        .unwrap();
+    assert_eq!(
+        ids(&mut conn, "SELECT id FROM t WHERE city = 'Paris';"),
+        [1]
+    );
+    assert_eq!(
+        ids(&mut conn, "SELECT id FROM t WHERE city = 'Rome';"),
+        [2, 4]
+    );
+}
+
+#[test]
+fn unique_index_rejects_duplicates() {
+    let mut conn = open();
+    // The index is built from the rows already in the table
+    assert!(conn
+        .execute("CREATE UNIQUE INDEX ux_city ON t (city);", &[])
+        .is_err());
+    conn.execute("CREATE UNIQUE INDEX ux_age ON t (age);", &[])
+        .unwrap();
+    assert!(conn
+        .execute("INSERT INTO t (id, city, age) VALUES (4, 'Oslo', 30);", &[])
+        .is_err());
+    // NULLs are never equal to each other
+    conn.execute(
+        "INSERT INTO t (id, city) VALUES (5, 'Oslo'), (6, 'Oslo');",
+        &[],
+    )
+    .unwrap();
+    // Nor can an index take the name of another one
+    assert!(conn
+        .execute("CREATE INDEX ux_age ON t (city);", &[])
+        .is_err());
+}
+
+#[test]
+fn multi_column_index_matches_its_prefix() {
+    let mut conn = open();
+    conn.execute("CREATE INDEX ix_city_age ON t (city, age);", &[])
+        .unwrap();
+    assert_eq!(
+        ids(
+            &mut conn,
+            "SELECT id FROM t WHERE city = 'Paris' AND age > 35;"
+        ),
+        [3]
+    );
+    assert_eq!(
+        ids(&mut conn, "SELECT id FROM t WHERE city = 'Paris';"),
+        [1, 3]
+    );
+    conn.execute("DROP INDEX ix_city_age;", &[]).unwrap();
+    assert_eq!(
+        ids(&mut conn, "SELECT id FROM t WHERE city = 'Paris';"),
+        [1, 3]
+    );
+}
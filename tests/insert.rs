@@ -0,0 +1,134 @@
+mod common;
+
+use common::query;
+use rustdb::{Connection, Value};
+
+fn open() -> Connection {
+    let mut conn = Connection::open(":memory:").unwrap();
+    conn.execute(
+        "CREATE TABLE t (id INTEGER PRIMARY KEY, name TEXT, age INTEGER);",
+        &[],
+    )
+    .unwrap();
+    conn
+}
+
+#[test]
+fn columns_are_matched_by_name_in_any_order() {
+    let mut conn = open();
+    conn.execute("INSERT INTO t (age, id, name) VALUES (30, 1, 'a');", &[])
+        .unwrap();
+    conn.execute("INSERT INTO t (name) VALUES ('b');", &[])
+        .unwrap();
+    assert_eq!(
+        query(&mut conn, "SELECT id, name, age FROM t;"),
+        vec![
+            vec![
+                Value::Integer(1),
+                Value::Text("a".into()),
+                Value::Integer(30)
+            ],
+            vec![Value::Integer(2), Value::Text("b".into()), Value::Null],
+        ]
+    );
+}
+
+#[test]
+fn missing_column_list_means_every_column() {
+    let mut conn = open();
+    conn.execute("INSERT INTO t VALUES (7, 'a', 30), (8, 'b', NULL);", &[])
+        .unwrap();
+    assert_eq!(
+        query(&mut conn, "SELECT id, name, age FROM t;"),
+        vec![
+            vec![
+                Value::Integer(7),
+                Value::Text("a".into()),
+                Value::Integer(30)
+            ],
+            vec![Value::Integer(8), Value::Text("b".into()), Value::Null],
+        ]
+    );
+}
+
+#[test]
+fn values_must_match_the_columns() {
+    let mut conn = open();
+    for sql in [
+        "INSERT INTO t VALUES (1, 'a');",
+        "INSERT INTO t (id, name) VALUES (1, 'a', 30);",
+        "INSERT INTO t (id, missing) VALUES (1, 'a');",
+    ] {
+        assert!(conn.execute(sql, &[]).is_err(), "{} succeeded", sql);
+    }
+    assert!(query(&mut conn, "SELECT id FROM t;").is_empty());
+}
+
+#[test]
+fn insert_select_copies_the_rows_of_a_query() {
+    let mut conn = open();
+    conn.execute("INSERT INTO t VALUES (1, 'a', 30), (2, 'b', 40);", &[])
+        .unwrap();
+    conn.execute("CREATE TABLE s (age INTEGER, label TEXT);", &[])
+        .unwrap();
+    assert_eq!(
+        conn.execute(
+            "INSERT INTO s (label, age) SELECT name, age + 1 FROM t WHERE age > 35;",
+            &[]
+        )
+        .unwrap(),
+        1
+    );
+    // The query reads the table as it was before the statement
+    conn.execute("INSERT INTO t (name, age) SELECT name, age FROM t;", &[])
+        .unwrap();
+    assert_eq!(
+        query(&mut conn, "SELECT age, label FROM s;"),
+        vec![vec![Value::Integer(41), Value::Text("b".into())]]
+    );
+    assert_eq!(query(&mut conn, "SELECT id FROM t;").len(), 4);
+    assert!(conn
+        .execute("INSERT INTO s (age) SELECT id, name FROM t;", &[])
+        .is_err());
+}
+
+#[test]
+fn create_table_as_select_infers_column_types() {
+    let mut conn = open();
+    conn.execute("INSERT INTO t VALUES (1, 'a', 30), (2, NULL, 40);", &[])
+        .unwrap();
+    conn.execute(
+        "CREATE TABLE c AS SELECT id, name, age * 1.5 AS score, NULL AS nothing FROM t;",
+        &[],
+    )
+    .unwrap();
+    let rows = conn.query("SELECT * FROM c;", &[]).unwrap();
+    assert_eq!(rows.columns(), ["id", "name", "score", "nothing"]);
+    assert_eq!(
+        rows.collect::<Vec<_>>(),
+        vec![
+            vec![
+                Value::Integer(1),
+                Value::Text("a".into()),
+                Value::Real(45.0),
+                Value::Null
+            ],
+            vec![
+                Value::Integer(2),
+                Value::Null,
+                Value::Real(60.0),
+                Value::Null
+            ],
+        ]
+    );
+    // The new table takes values of the types it inferred
+    conn.execute("INSERT INTO c (id, score) VALUES (3, 7);", &[])
+        .unwrap();
+    assert_eq!(
+        query(&mut conn, "SELECT score FROM c WHERE id = 3;"),
+        vec![vec![Value::Real(7.0)]]
+    );
+    assert!(conn
+        .execute("CREATE TABLE c AS SELECT id FROM t;", &[])
+        .is_err());
+}
@@ -0,0 +1,74 @@
+mod common;
+
+use common::query;
+use rustdb::{Connection, Value};
+
+#[test]
+fn composite_primary_key_rejects_duplicate_pairs() {
+    let mut conn = Connection::open(":memory:").unwrap();
+    conn.execute(
+        "CREATE TABLE t (a INTEGER, b TEXT, c INTEGER, PRIMARY KEY (a, b));",
+        &[],
+    )
+    .unwrap();
+    conn.execute(
+        "INSERT INTO t (a, b, c) VALUES (1, 'x', 1), (1, 'y', 2), (2, 'x', 3);",
+        &[],
+    )
+    .unwrap();
+    assert!(conn
+        .execute("INSERT INTO t (a, b, c) VALUES (1, 'x', 4);", &[])
+        .is_err());
+    assert!(conn
+        .execute("UPDATE t SET b = 'x' WHERE c = 2;", &[])
+        .is_err());
+    assert_eq!(
+        query(&mut conn, "SELECT c FROM t WHERE a = 1 AND b = 'x';"),
+        vec![vec![Value::Integer(1)]]
+    );
+    // The key does not alias the ROWID
+    assert_eq!(
+        query(&mut conn, "SELECT rowid FROM t WHERE c = 3;"),
+        vec![vec![Value::Integer(3)]]
+    );
+}
+
+#[test]
+fn table_unique_constraint_spans_its_columns() {
+    let mut conn = Connection::open(":memory:").unwrap();
+    conn.execute(
+        "CREATE TABLE t (id INTEGER PRIMARY KEY, a INTEGER, b INTEGER, UNIQUE (a, b));",
+        &[],
+    )
+    .unwrap();
+    conn.execute("INSERT INTO t (a, b) VALUES (1, 1), (1, 2);", &[])
+        .unwrap();
+    assert!(conn
+        .execute("INSERT INTO t (a, b) VALUES (1, 2);", &[])
+        .is_err());
+    // NULLs are never equal to each other
+    conn.execute("INSERT INTO t (a, b) VALUES (1, NULL), (1, NULL);", &[])
+        .unwrap();
+    assert_eq!(query(&mut conn, "SELECT id FROM t;").len(), 4);
+}
+
+#[test]
+fn tables_need_no_primary_key() {
+    let mut conn = Connection::open(":memory:").unwrap();
+    conn.execute("CREATE TABLE t (a INTEGER);", &[]).unwrap();
+    conn.execute("INSERT INTO t (a) VALUES (1), (1);", &[])
+        .unwrap();
+    assert_eq!(
+        query(&mut conn, "SELECT rowid, a FROM t;"),
+        vec![
+            vec![Value::Integer(1), Value::Integer(1)],
+            vec![Value::Integer(2), Value::Integer(1)],
+        ]
+    );
+    assert!(conn
+        .execute(
+            "CREATE TABLE u (a INTEGER PRIMARY KEY, b INTEGER, PRIMARY KEY (a, b));",
+            &[]
+        )
+        .is_err());
+}
@@ -0,0 +1,57 @@
+mod common;
+
+use common::query;
+use rustdb::{Connection, Value};
+
+#[test]
+fn quoted_returning_is_a_column() {
+    let mut conn = Connection::open(":memory:").unwrap();
+    conn.execute(
+        "CREATE TABLE t (id INTEGER PRIMARY KEY, \"returning\" INTEGER);",
+        &[],
+    )
+    .unwrap();
+    conn.execute("INSERT INTO t (id, \"returning\") VALUES (1, 4);", &[])
+        .unwrap();
+
+    assert_eq!(
+        query(
+            &mut conn,
+            "UPDATE t SET \"returning\" = 5 WHERE id = 1 RETURNING \"returning\";"
+        ),
+        vec![vec![Value::Integer(5)]]
+    );
+}
+
+#[test]
+fn generated_can_name_a_column() {
+    let mut conn = Connection::open(":memory:").unwrap();
+    conn.execute(
+        "CREATE TABLE g (generated INTEGER, x INTEGER GENERATED ALWAYS AS (generated + 1));",
+        &[],
+    )
+    .unwrap();
+    conn.execute("INSERT INTO g (generated) VALUES (2);", &[])
+        .unwrap();
+    assert_eq!(
+        query(&mut conn, "SELECT generated, x FROM g;"),
+        vec![vec![Value::Integer(2), Value::Integer(3)]]
+    );
+}
+
+#[test]
+fn quoted_limit_is_a_column() {
+    let mut conn = Connection::open(":memory:").unwrap();
+    conn.execute(
+        "CREATE TABLE t (id INTEGER PRIMARY KEY, \"limit\" INTEGER);",
+        &[],
+    )
+    .unwrap();
+    conn.execute("INSERT INTO t (id, \"limit\") VALUES (1, 3), (2, 1);", &[])
+        .unwrap();
+
+    assert_eq!(
+        query(&mut conn, "SELECT \"limit\" FROM t WHERE \"limit\" > 2;"),
+        vec![vec![Value::Integer(3)]]
+    );
+}
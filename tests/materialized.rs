@@ -0,0 +1,123 @@
+mod common;
+
+use common::query;
+use rustdb::{Connection, Value};
+
+fn open() -> Connection {
+    let mut conn = Connection::open(":memory:").unwrap();
+    for sql in [
+        "CREATE TABLE t (id INTEGER PRIMARY KEY, g TEXT, n INTEGER);",
+        "CREATE TABLE u (id INTEGER PRIMARY KEY, label TEXT);",
+        "INSERT INTO t VALUES (1, 'a', 1), (2, 'a', 2), (3, 'b', 5);",
+        "INSERT INTO u VALUES (1, 'one');",
+    ] {
+        conn.execute(sql, &[]).unwrap();
+    }
+    conn
+}
+
+fn group(g: &str, count: i64, sum: i64) -> Vec<Value> {
+    vec![
+        Value::Text(g.into()),
+        Value::Integer(count),
+        Value::Integer(sum),
+    ]
+}
+
+#[test]
+fn aggregates_follow_inserts_and_deletes() {
+    let mut conn = open();
+    conn.execute(
+        "CREATE MATERIALIZED VIEW m AS SELECT g, count(*), sum(n) FROM t GROUP BY g;",
+        &[],
+    )
+    .unwrap();
+    assert_eq!(
+        query(&mut conn, "SELECT * FROM m;"),
+        vec![group("a", 2, 3), group("b", 1, 5)]
+    );
+
+    conn.execute("INSERT INTO t VALUES (4, 'b', 1), (5, 'c', 9);", &[])
+        .unwrap();
+    assert_eq!(
+        query(&mut conn, "SELECT * FROM m;"),
+        vec![group("a", 2, 3), group("b", 2, 6), group("c", 1, 9)]
+    );
+
+    // Groups left without rows are removed
+    conn.execute("DELETE FROM t WHERE g = 'a';", &[]).unwrap();
+    conn.execute("UPDATE t SET n = 100 WHERE id = 5;", &[])
+        .unwrap();
+    assert_eq!(
+        query(&mut conn, "SELECT * FROM m;"),
+        vec![group("b", 2, 6), group("c", 1, 100)]
+    );
+}
+
+#[test]
+fn min_and_max_follow_deletes() {
+    let mut conn = open();
+    conn.execute(
+        "CREATE MATERIALIZED VIEW m (g, lo, hi) AS SELECT g, min(n), max(n) FROM t GROUP BY g;",
+        &[],
+    )
+    .unwrap();
+    conn.execute("DELETE FROM t WHERE id = 2;", &[]).unwrap();
+    assert_eq!(
+        query(&mut conn, "SELECT * FROM m WHERE g = 'a';"),
+        vec![vec![
+            Value::Text("a".into()),
+            Value::Integer(1),
+            Value::Integer(1)
+        ]]
+    );
+
+    // Rolling back undoes the changes made to the view
+    conn.execute("BEGIN;", &[]).unwrap();
+    conn.execute("INSERT INTO t VALUES (9, 'a', 50);", &[])
+        .unwrap();
+    conn.execute("ROLLBACK;", &[]).unwrap();
+    assert_eq!(
+        query(&mut conn, "SELECT hi FROM m WHERE g = 'a';"),
+        vec![vec![Value::Integer(1)]]
+    );
+}
+
+#[test]
+fn other_views_wait_for_refresh() {
+    let mut conn = open();
+    conn.execute(
+        "CREATE MATERIALIZED VIEW j AS SELECT t.g, u.label FROM t JOIN u ON t.id = u.id;",
+        &[],
+    )
+    .unwrap();
+    conn.execute("INSERT INTO u VALUES (2, 'two');", &[])
+        .unwrap();
+    assert_eq!(query(&mut conn, "SELECT label FROM j;").len(), 1);
+
+    conn.execute("REFRESH MATERIALIZED VIEW j;", &[]).unwrap();
+    assert_eq!(
+        query(&mut conn, "SELECT label FROM j;"),
+        vec![
+            vec![Value::Text("one".into())],
+            vec![Value::Text("two".into())]
+        ]
+    );
+}
+
+#[test]
+fn only_materialized_views_are_refreshed() {
+    let mut conn = open();
+    conn.execute("CREATE VIEW v AS SELECT g FROM t;", &[])
+        .unwrap();
+    for (sql, message) in [
+        (
+            "REFRESH MATERIALIZED VIEW v;",
+            "v is not a materialized view",
+        ),
+        ("REFRESH MATERIALIZED VIEW t;", "no such view: t"),
+    ] {
+        let error = conn.execute(sql, &[]).unwrap_err().to_string();
+        assert!(error.ends_with(message), "{}: {}", sql, error);
+    }
+}
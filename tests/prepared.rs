@@ -0,0 +1,98 @@
+mod common;
+
+use common::query;
+use rustdb::{Connection, Value};
+
+fn open() -> Connection {
+    let mut conn = Connection::open(":memory:").unwrap();
+    conn.execute("CREATE TABLE t (id INTEGER PRIMARY KEY, name TEXT);", &[])
+        .unwrap();
+    conn.execute(
+        "INSERT INTO t (id, name) VALUES (1, 'a'), (2, 'b'), (3, 'c');",
+        &[],
+    )
+    .unwrap();
+    conn
+}
+
+#[test]
+fn parameters_name_their_columns() {
+    let mut conn = open();
+    let rows = conn
+        .query("SELECT :x, ? + 1, @y AS z;", &["hello".into(), 2.into()])
+        .unwrap();
+    assert_eq!(rows.columns(), [":x", "? + 1", "z"]);
+    assert_eq!(
+        rows.collect::<Vec<_>>(),
+        vec![vec![
+            Value::Text("hello".into()),
+            Value::Integer(3),
+            Value::Null
+        ]]
+    );
+}
+
+#[test]
+fn statement_runs_again_with_other_values() {
+    let mut conn = open();
+    let mut statement = conn.prepare("SELECT name FROM t WHERE id = ?;").unwrap();
+    for (id, name) in [(1, "a"), (3, "c")] {
+        assert_eq!(
+            statement.query(&[id.into()]).unwrap().collect::<Vec<_>>(),
+            vec![vec![Value::Text(name.into())]]
+        );
+    }
+    assert_eq!(statement.query(&[4.into()]).unwrap().count(), 0);
+}
+
+#[test]
+fn named_parameters_share_their_index() {
+    let mut conn = open();
+    let mut statement = conn
+        .prepare("UPDATE t SET name = :name WHERE id = :id OR name = :name;")
+        .unwrap();
+    assert_eq!(statement.parameter_count(), 2);
+    statement.bind_named(":id", 2).unwrap();
+    statement.bind_named(":name", "a").unwrap();
+    assert_eq!(statement.execute(&[]).unwrap(), 2);
+    assert_eq!(
+        query(&mut conn, "SELECT id FROM t WHERE name = 'a';"),
+        vec![vec![Value::Integer(1)], vec![Value::Integer(2)]]
+    );
+
+    assert_eq!(
+        conn.execute("DELETE FROM t WHERE id > $min;", &[1.into()])
+            .unwrap(),
+        2
+    );
+    assert_eq!(query(&mut conn, "SELECT id FROM t;").len(), 1);
+}
+
+#[test]
+fn values_are_never_read_as_sql() {
+    let mut conn = open();
+    let name = "a' OR '1' = '1";
+    assert_eq!(
+        conn.query("SELECT id FROM t WHERE name = ?;", &[name.into()])
+            .unwrap()
+            .count(),
+        0
+    );
+}
+
+#[test]
+fn plan_is_made_again_once_its_index_is_dropped() {
+    let mut conn = open();
+    conn.execute("CREATE INDEX ix ON t (name);", &[]).unwrap();
+    let sql = "SELECT id FROM t WHERE name = ?;";
+    assert_eq!(
+        conn.query(sql, &["b".into()]).unwrap().collect::<Vec<_>>(),
+        vec![vec![Value::Integer(2)]]
+    );
+
+    conn.execute("DROP INDEX ix;", &[]).unwrap();
+    assert_eq!(
+        conn.query(sql, &["c".into()]).unwrap().collect::<Vec<_>>(),
+        vec![vec![Value::Integer(3)]]
+    );
+}
@@ -0,0 +1,130 @@
+mod common;
+
+use common::query;
+use rustdb::{Connection, Value};
+
+#[test]
+fn escaped_quotes_in_string_literals() {
+    let mut conn = Connection::open(":memory:").unwrap();
+    conn.execute("CREATE TABLE t (id INTEGER PRIMARY KEY, s TEXT);", &[])
+        .unwrap();
+    conn.execute("INSERT INTO t (id, s) VALUES (1, 'it''s');", &[])
+        .unwrap();
+    assert_eq!(
+        query(&mut conn, "SELECT id, s FROM t WHERE s = 'it''s';"),
+        vec![vec![Value::Integer(1), Value::Text("it's".into())]]
+    );
+}
+
+#[test]
+fn escaped_quotes_next_to_deferrable_clauses() {
+    let mut conn = Connection::open(":memory:").unwrap();
+    conn.execute("CREATE TABLE p (id INTEGER PRIMARY KEY);", &[])
+        .unwrap();
+    conn.execute(
+        "CREATE TABLE c (s TEXT DEFAULT 'it''s', \
+         p_id INTEGER REFERENCES p (id) DEFERRABLE INITIALLY DEFERRED);",
+        &[],
+    )
+    .unwrap();
+    conn.execute("INSERT INTO c (p_id) VALUES (NULL);", &[])
+        .unwrap();
+    assert_eq!(
+        query(&mut conn, "SELECT s FROM c;"),
+        vec![vec![Value::Text("it's".into())]]
+    );
+}
+
+#[test]
+fn escaped_quotes_in_identifiers_survive_trigger_bodies() {
+    let mut conn = Connection::open(":memory:").unwrap();
+    conn.execute("CREATE TABLE t (id INTEGER PRIMARY KEY);", &[])
+        .unwrap();
+    conn.execute("CREATE TABLE log (\"a\"\"b\" TEXT);", &[])
+        .unwrap();
+    conn.execute(
+        "CREATE TRIGGER tr AFTER INSERT ON t BEGIN \
+         INSERT INTO log (\"a\"\"b\") VALUES ('it''s'); \
+         END;",
+        &[],
+    )
+    .unwrap();
+
+    conn.execute("INSERT INTO t (id) VALUES (1);", &[]).unwrap();
+    assert_eq!(
+        query(&mut conn, "SELECT \"a\"\"b\" FROM log;"),
+        vec![vec![Value::Text("it's".into())]]
+    );
+}
+
+#[test]
+fn escaped_quotes_before_returning() {
+    let mut conn = Connection::open(":memory:").unwrap();
+    conn.execute("CREATE TABLE t (id INTEGER PRIMARY KEY, s TEXT);", &[])
+        .unwrap();
+    assert_eq!(
+        query(
+            &mut conn,
+            "INSERT INTO t (id, s) VALUES (1, 'it''s') RETURNING id, s;"
+        ),
+        vec![vec![Value::Integer(1), Value::Text("it's".into())]]
+    );
+    assert_eq!(
+        query(
+            &mut conn,
+            "UPDATE t SET s = 'that''s' WHERE s = 'it''s' RETURNING s;"
+        ),
+        vec![vec![Value::Text("that's".into())]]
+    );
+}
+
+#[test]
+fn escaped_quotes_in_explain_query_plan() {
+    let mut conn = Connection::open(":memory:").unwrap();
+    conn.execute("CREATE TABLE t (id INTEGER PRIMARY KEY, s TEXT);", &[])
+        .unwrap();
+    let rows = query(
+        &mut conn,
+        "EXPLAIN QUERY PLAN SELECT id FROM t WHERE s = 'it''s';",
+    );
+    assert_eq!(rows.last(), Some(&vec![Value::Text("`--SCAN t".into())]));
+}
+
+#[test]
+fn escaped_quotes_before_on_conflict() {
+    let mut conn = Connection::open(":memory:").unwrap();
+    conn.execute("CREATE TABLE t (id INTEGER PRIMARY KEY, s TEXT);", &[])
+        .unwrap();
+    conn.execute("INSERT INTO t (id, s) VALUES (1, 'a');", &[])
+        .unwrap();
+    conn.execute(
+        "INSERT INTO t (id, s) VALUES (1, 'it''s') \
+         ON CONFLICT (id) DO UPDATE SET s = excluded.s;",
+        &[],
+    )
+    .unwrap();
+    assert_eq!(
+        query(&mut conn, "SELECT s FROM t;"),
+        vec![vec![Value::Text("it's".into())]]
+    );
+}
+
+#[test]
+fn escaped_quotes_before_the_where_of_a_partial_index() {
+    let mut conn = Connection::open(":memory:").unwrap();
+    conn.execute(
+        "CREATE TABLE t (id INTEGER PRIMARY KEY, \"a\"\"b\" TEXT);",
+        &[],
+    )
+    .unwrap();
+    conn.execute(
+        "CREATE UNIQUE INDEX ix ON t (\"a\"\"b\", \"a\"\"b\" = 'it''s') WHERE id > 0;",
+        &[],
+    )
+    .unwrap();
+    conn.execute("INSERT INTO t (id, \"a\"\"b\") VALUES (1, 'it''s');", &[])
+        .unwrap();
+    assert!(conn
+        .execute("INSERT INTO t (id, \"a\"\"b\") VALUES (2, 'it''s');", &[])
+        .is_err());
+}
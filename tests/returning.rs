@@ -0,0 +1,90 @@
+mod common;
+
+use common::query;
+use rustdb::{Connection, Value};
+
+fn open() -> Connection {
+    let mut conn = Connection::open(":memory:").unwrap();
+    conn.execute(
+        "CREATE TABLE t (id INTEGER PRIMARY KEY, name TEXT, n INTEGER);",
+        &[],
+    )
+    .unwrap();
+    conn
+}
+
+#[test]
+fn insert_returns_the_new_rows() {
+    let mut conn = open();
+    let rows = conn
+        .query(
+            "INSERT INTO t (name, n) VALUES ('a', 1), ('b', 2) RETURNING *;",
+            &[],
+        )
+        .unwrap();
+    assert_eq!(rows.columns(), ["id", "name", "n"]);
+    assert_eq!(
+        rows.collect::<Vec<_>>(),
+        vec![
+            vec![
+                Value::Integer(1),
+                Value::Text("a".into()),
+                Value::Integer(1)
+            ],
+            vec![
+                Value::Integer(2),
+                Value::Text("b".into()),
+                Value::Integer(2)
+            ],
+        ]
+    );
+    let rows = conn
+        .query(
+            "INSERT INTO t (name, n) VALUES ('c', 3) RETURNING id, n * 2 AS twice;",
+            &[],
+        )
+        .unwrap();
+    assert_eq!(rows.columns(), ["id", "twice"]);
+    assert_eq!(
+        rows.collect::<Vec<_>>(),
+        vec![vec![Value::Integer(3), Value::Integer(6)]]
+    );
+}
+
+#[test]
+fn update_returns_the_changed_rows() {
+    let mut conn = open();
+    conn.execute("INSERT INTO t (name, n) VALUES ('a', 1), ('b', 2);", &[])
+        .unwrap();
+    assert_eq!(
+        query(
+            &mut conn,
+            "UPDATE t SET n = n + 10 WHERE id > 1 RETURNING id, n;"
+        ),
+        vec![vec![Value::Integer(2), Value::Integer(12)]]
+    );
+}
+
+#[test]
+fn delete_returns_the_removed_rows() {
+    let mut conn = open();
+    conn.execute("INSERT INTO t (name, n) VALUES ('a', 1), ('b', 2);", &[])
+        .unwrap();
+    assert_eq!(
+        query(&mut conn, "DELETE FROM t WHERE id = 1 RETURNING name;"),
+        vec![vec![Value::Text("a".into())]]
+    );
+    assert!(query(&mut conn, "DELETE FROM t WHERE id = 1 RETURNING name;").is_empty());
+    assert_eq!(
+        query(&mut conn, "SELECT name FROM t;"),
+        vec![vec![Value::Text("b".into())]]
+    );
+}
+
+#[test]
+fn returning_unknown_columns_fails() {
+    let mut conn = open();
+    assert!(conn
+        .execute("INSERT INTO t (name) VALUES ('a') RETURNING missing;", &[])
+        .is_err());
+}
@@ -0,0 +1,124 @@
+mod common;
+
+use common::query;
+use rustdb::{Connection, Value};
+
+#[test]
+fn integer_values_are_stored_as_64_bits() {
+    let mut conn = Connection::open(":memory:").unwrap();
+    conn.execute("CREATE TABLE t (id INTEGER PRIMARY KEY, n INTEGER);", &[])
+        .unwrap();
+    conn.execute(
+        "INSERT INTO t (id, n) VALUES (5000000000, 6000000000);",
+        &[],
+    )
+    .unwrap();
+    conn.execute("INSERT INTO t (n) VALUES (1);", &[]).unwrap();
+
+    assert_eq!(
+        query(&mut conn, "SELECT id, n FROM t;"),
+        vec![
+            vec![Value::Integer(5_000_000_000), Value::Integer(6_000_000_000)],
+            vec![Value::Integer(5_000_000_001), Value::Integer(1)],
+        ]
+    );
+}
+
+#[test]
+fn only_integer_primary_keys_alias_the_rowid() {
+    let mut conn = Connection::open(":memory:").unwrap();
+    conn.execute("CREATE TABLE a (id INTEGER PRIMARY KEY);", &[])
+        .unwrap();
+    conn.execute("CREATE TABLE b (id BIGINT PRIMARY KEY);", &[])
+        .unwrap();
+    conn.execute("CREATE TABLE c (id INT PRIMARY KEY);", &[])
+        .unwrap();
+    for table in ["a", "b", "c"] {
+        conn.execute(&format!("INSERT INTO {} (id) VALUES (7);", table), &[])
+            .unwrap();
+    }
+
+    assert_eq!(
+        query(&mut conn, "SELECT rowid FROM a;"),
+        vec![vec![Value::Integer(7)]]
+    );
+    assert_eq!(
+        query(&mut conn, "SELECT rowid FROM b;"),
+        vec![vec![Value::Integer(1)]]
+    );
+    assert_eq!(
+        query(&mut conn, "SELECT rowid FROM c;"),
+        vec![vec![Value::Integer(1)]]
+    );
+}
+
+#[test]
+fn updating_the_integer_primary_key_moves_the_row() {
+    let mut conn = Connection::open(":memory:").unwrap();
+    conn.execute("CREATE TABLE t (id INTEGER PRIMARY KEY, name TEXT);", &[])
+        .unwrap();
+    conn.execute("INSERT INTO t (id, name) VALUES (1, 'a');", &[])
+        .unwrap();
+    conn.execute("INSERT INTO t (id, name) VALUES (2, 'b');", &[])
+        .unwrap();
+
+    assert_eq!(
+        conn.execute("UPDATE t SET id = 10 WHERE id = 1;", &[])
+            .unwrap(),
+        1
+    );
+    assert_eq!(
+        query(&mut conn, "SELECT rowid, id, name FROM t;"),
+        vec![
+            vec![
+                Value::Integer(2),
+                Value::Integer(2),
+                Value::Text("b".into())
+            ],
+            vec![
+                Value::Integer(10),
+                Value::Integer(10),
+                Value::Text("a".into())
+            ],
+        ]
+    );
+
+    assert!(conn
+        .execute("UPDATE t SET id = 2 WHERE id = 10;", &[])
+        .is_err());
+    assert_eq!(
+        query(&mut conn, "SELECT id, name FROM t WHERE id = 10;"),
+        vec![vec![Value::Integer(10), Value::Text("a".into())]]
+    );
+
+    conn.execute("INSERT INTO t (name) VALUES ('c');", &[])
+        .unwrap();
+    assert_eq!(
+        query(&mut conn, "SELECT id FROM t WHERE name = 'c';"),
+        vec![vec![Value::Integer(11)]]
+    );
+}
+
+#[test]
+fn updating_an_integer_primary_key_cascades() {
+    let mut conn = Connection::open(":memory:").unwrap();
+    conn.execute("PRAGMA foreign_keys = ON;", &[]).unwrap();
+    conn.execute("CREATE TABLE parent (id INTEGER PRIMARY KEY);", &[])
+        .unwrap();
+    conn.execute(
+        "CREATE TABLE child (id INTEGER PRIMARY KEY, \
+         parent_id INTEGER REFERENCES parent (id) ON UPDATE CASCADE);",
+        &[],
+    )
+    .unwrap();
+    conn.execute("INSERT INTO parent (id) VALUES (1);", &[])
+        .unwrap();
+    conn.execute("INSERT INTO child (id, parent_id) VALUES (1, 1);", &[])
+        .unwrap();
+
+    conn.execute("UPDATE parent SET id = 3;", &[]).unwrap();
+    assert_eq!(
+        query(&mut conn, "SELECT parent_id FROM child;"),
+        vec![vec![Value::Integer(3)]]
+    );
+}
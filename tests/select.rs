@@ -0,0 +1,157 @@
+mod common;
+
+use common::query;
+use rustdb::{Connection, Value};
+
+fn open() -> Connection {
+    let mut conn = Connection::open(":memory:").unwrap();
+    conn.execute("CREATE TABLE r (id INTEGER PRIMARY KEY, a INTEGER);", &[])
+        .unwrap();
+    conn.execute("CREATE INDEX ix ON r (a);", &[]).unwrap();
+    for id in 1..=5 {
+        conn.execute(
+            "INSERT INTO r (id, a) VALUES (?, ?);",
+            &[id.into(), (id * 10).into()],
+        )
+        .unwrap();
+    }
+    conn
+}
+
+fn ids(rows: Vec<Vec<Value>>) -> Vec<i64> {
+    rows.into_iter()
+        .map(|row| match row[0] {
+            Value::Integer(id) => id,
+            _ => panic!("not an INTEGER"),
+        })
+        .collect()
+}
+
+#[test]
+fn rowid_lookup_matches_whole_real_values() {
+    let mut conn = open();
+    assert_eq!(
+        ids(query(&mut conn, "SELECT id FROM r WHERE id = 1.0;")),
+        [1]
+    );
+    assert_eq!(
+        ids(query(&mut conn, "SELECT id FROM r WHERE a = 20.0;")),
+        [2]
+    );
+    assert!(query(&mut conn, "SELECT id FROM r WHERE id = 1.5;").is_empty());
+    // Like a full scan, which compares the values as they are
+    assert!(query(&mut conn, "SELECT id FROM r WHERE id = '1';").is_empty());
+    assert!(query(&mut conn, "SELECT id FROM r WHERE a = '20';").is_empty());
+}
+
+#[test]
+fn rowid_range_reads_the_rows_between_its_bounds() {
+    let mut conn = open();
+    assert_eq!(
+        ids(query(
+            &mut conn,
+            "SELECT id FROM r WHERE id > 2 AND id <= 4;"
+        )),
+        [3, 4]
+    );
+    assert_eq!(
+        ids(query(
+            &mut conn,
+            "SELECT id FROM r WHERE id >= 1.5 AND id < 3.5;"
+        )),
+        [2, 3]
+    );
+    assert_eq!(
+        ids(query(&mut conn, "SELECT id FROM r WHERE id > 2.0;")),
+        [3, 4, 5]
+    );
+    assert_eq!(
+        ids(query(&mut conn, "SELECT id FROM r WHERE id < 'a';")),
+        [1, 2, 3, 4, 5]
+    );
+    assert!(query(&mut conn, "SELECT id FROM r WHERE id > 'a';").is_empty());
+    assert!(query(&mut conn, "SELECT id FROM r WHERE id > 4 AND id < 5;").is_empty());
+    assert!(query(&mut conn, "SELECT id FROM r WHERE id > NULL;").is_empty());
+    assert_eq!(
+        ids(query(
+            &mut conn,
+            "SELECT id FROM r WHERE id > 9223372036854775807 OR id < -9223372036854775808;"
+        )),
+        Vec::<i64>::new()
+    );
+}
+
+#[test]
+fn rowid_range_is_a_range_scan() {
+    let mut conn = open();
+    let plan = query(
+        &mut conn,
+        "EXPLAIN QUERY PLAN SELECT id FROM r WHERE id > 2;",
+    );
+    assert_eq!(
+        plan.last(),
+        Some(&vec![Value::Text(
+            "`--SEARCH r USING INTEGER PRIMARY KEY (rowid>?)".into()
+        )])
+    );
+}
+
+/// Returns the lines of the plan of a query, header excluded
+fn plan(conn: &mut Connection, sql: &str) -> Vec<Value> {
+    let mut lines: Vec<Value> = query(conn, &format!("EXPLAIN QUERY PLAN {}", sql))
+        .into_iter()
+        .flatten()
+        .collect();
+    lines.remove(0);
+    lines
+}
+
+#[test]
+fn index_is_searched_for_equalities_and_ranges() {
+    let mut conn = open();
+    let sql = "SELECT id FROM r WHERE a = 30;";
+    assert_eq!(
+        plan(&mut conn, sql),
+        [Value::Text("`--SEARCH r USING INDEX ix (a=?)".into())]
+    );
+    assert_eq!(ids(query(&mut conn, sql)), [3]);
+
+    let sql = "SELECT id FROM r WHERE a > 15 AND a <= 40;";
+    assert_eq!(
+        plan(&mut conn, sql),
+        [Value::Text(
+            "`--SEARCH r USING INDEX ix (a>? AND a<=?)".into()
+        )]
+    );
+    assert_eq!(ids(query(&mut conn, sql)), [2, 3, 4]);
+}
+
+#[test]
+fn index_order_satisfies_order_by() {
+    let mut conn = open();
+    conn.execute("UPDATE r SET a = 60 - a;", &[]).unwrap();
+    for (sql, expected) in [
+        ("SELECT id FROM r ORDER BY a;", [5, 4, 3, 2, 1]),
+        ("SELECT id FROM r ORDER BY a DESC;", [1, 2, 3, 4, 5]),
+    ] {
+        assert_eq!(
+            plan(&mut conn, sql),
+            [Value::Text("`--SCAN r USING INDEX ix".into())]
+        );
+        assert_eq!(ids(query(&mut conn, sql)), expected);
+    }
+}
+
+#[test]
+fn other_conditions_scan_the_table() {
+    let mut conn = open();
+    let sql = "SELECT id FROM r WHERE a + 0 = 20 ORDER BY a + 0;";
+    assert_eq!(
+        plan(&mut conn, sql),
+        [
+            Value::Text("|--SCAN r".into()),
+            Value::Text("`--USE TEMP B-TREE FOR ORDER BY".into())
+        ]
+    );
+    assert_eq!(ids(query(&mut conn, sql)), [2]);
+}
@@ -0,0 +1,76 @@
+mod common;
+
+use common::query;
+use rustdb::{Connection, Value};
+
+#[test]
+fn rollback_undoes_the_transaction() {
+    let mut conn = Connection::open(":memory:").unwrap();
+    conn.execute("CREATE TABLE t (id INTEGER PRIMARY KEY, name TEXT);", &[])
+        .unwrap();
+    conn.execute("INSERT INTO t (id, name) VALUES (1, 'a');", &[])
+        .unwrap();
+
+    conn.execute("BEGIN;", &[]).unwrap();
+    conn.execute("INSERT INTO t (id, name) VALUES (2, 'b');", &[])
+        .unwrap();
+    conn.execute("UPDATE t SET name = 'z' WHERE id = 1;", &[])
+        .unwrap();
+    conn.execute("CREATE TABLE u (id INTEGER PRIMARY KEY);", &[])
+        .unwrap();
+    conn.execute("ROLLBACK;", &[]).unwrap();
+
+    assert_eq!(
+        query(&mut conn, "SELECT id, name FROM t;"),
+        vec![vec![Value::Integer(1), Value::Text("a".into())]]
+    );
+    assert!(conn.query("SELECT id FROM u;", &[]).is_err());
+    assert!(conn.execute("ROLLBACK;", &[]).is_err());
+}
+
+#[test]
+fn failed_statement_in_a_transaction_is_undone_alone() {
+    let mut conn = Connection::open(":memory:").unwrap();
+    conn.execute("CREATE TABLE t (id INTEGER PRIMARY KEY);", &[])
+        .unwrap();
+
+    conn.execute("BEGIN;", &[]).unwrap();
+    conn.execute("INSERT INTO t (id) VALUES (1);", &[]).unwrap();
+    assert!(conn
+        .execute("INSERT INTO t (id) VALUES (2), (1);", &[])
+        .is_err());
+    conn.execute("COMMIT;", &[]).unwrap();
+
+    assert_eq!(
+        query(&mut conn, "SELECT id FROM t;"),
+        vec![vec![Value::Integer(1)]]
+    );
+}
+
+#[test]
+fn failed_trigger_undoes_its_statement() {
+    let mut conn = Connection::open(":memory:").unwrap();
+    conn.execute("CREATE TABLE t (id INTEGER PRIMARY KEY);", &[])
+        .unwrap();
+    conn.execute("CREATE TABLE log (id INTEGER);", &[]).unwrap();
+    conn.execute(
+        "CREATE TRIGGER tr AFTER INSERT ON t BEGIN \
+         INSERT INTO log (id) VALUES (NEW.id); \
+         SELECT RAISE(ABORT, 'no') WHERE NEW.id > 1; \
+         END;",
+        &[],
+    )
+    .unwrap();
+
+    conn.execute("INSERT INTO t (id) VALUES (1);", &[]).unwrap();
+    assert!(conn.execute("INSERT INTO t (id) VALUES (2);", &[]).is_err());
+
+    assert_eq!(
+        query(&mut conn, "SELECT id FROM t;"),
+        vec![vec![Value::Integer(1)]]
+    );
+    assert_eq!(
+        query(&mut conn, "SELECT id FROM log;"),
+        vec![vec![Value::Integer(1)]]
+    );
+}
@@ -0,0 +1,126 @@
+mod common;
+
+use common::query;
+use rustdb::{Connection, Value};
+
+fn open() -> Connection {
+    let mut conn = Connection::open(":memory:").unwrap();
+    for sql in [
+        "CREATE TABLE t (id INTEGER PRIMARY KEY, n INTEGER);",
+        "CREATE TABLE log (id INTEGER PRIMARY KEY, msg TEXT, v INTEGER);",
+    ] {
+        conn.execute(sql, &[]).unwrap();
+    }
+    conn
+}
+
+fn numbers(conn: &mut Connection) -> Vec<Vec<Value>> {
+    query(conn, "SELECT n FROM t;")
+}
+
+fn log(msg: &str, v: i64) -> Vec<Value> {
+    vec![Value::Text(msg.into()), Value::Integer(v)]
+}
+
+#[test]
+fn after_triggers_see_new_and_old_rows() {
+    let mut conn = open();
+    for sql in [
+        "CREATE TRIGGER ai AFTER INSERT ON t \
+         BEGIN INSERT INTO log (msg, v) VALUES ('ins', NEW.n); END;",
+        "CREATE TRIGGER au AFTER UPDATE OF n ON t WHEN OLD.n <> NEW.n \
+         BEGIN INSERT INTO log (msg, v) VALUES ('upd', NEW.n - OLD.n); END;",
+        "CREATE TRIGGER ad AFTER DELETE ON t \
+         BEGIN INSERT INTO log (msg, v) VALUES ('del', OLD.n); END;",
+        "INSERT INTO t (n) VALUES (1), (2);",
+        "UPDATE t SET n = 5 WHERE id = 1;",
+        // The WHEN clause skips updates leaving n as it is
+        "UPDATE t SET n = 5 WHERE id = 1;",
+        "DELETE FROM t WHERE id = 2;",
+    ] {
+        conn.execute(sql, &[]).unwrap();
+    }
+    assert_eq!(
+        query(&mut conn, "SELECT msg, v FROM log;"),
+        vec![log("ins", 1), log("ins", 2), log("upd", 4), log("del", 2)]
+    );
+}
+
+#[test]
+fn before_triggers_can_reject_or_skip_rows() {
+    let mut conn = open();
+    for sql in [
+        "CREATE TRIGGER neg BEFORE INSERT ON t WHEN NEW.n < 0 \
+         BEGIN SELECT RAISE(ABORT, 'negative'); END;",
+        "CREATE TRIGGER zero BEFORE INSERT ON t WHEN NEW.n = 0 \
+         BEGIN SELECT RAISE(IGNORE); END;",
+    ] {
+        conn.execute(sql, &[]).unwrap();
+    }
+    let error = conn
+        .execute("INSERT INTO t (n) VALUES (1), (-1);", &[])
+        .unwrap_err()
+        .to_string();
+    assert!(error.ends_with("negative"), "{}", error);
+    assert!(numbers(&mut conn).is_empty());
+
+    conn.execute("INSERT INTO t (n) VALUES (0), (7);", &[])
+        .unwrap();
+    assert_eq!(numbers(&mut conn), vec![vec![Value::Integer(7)]]);
+}
+
+#[test]
+fn raise_fail_keeps_the_earlier_rows() {
+    let mut conn = open();
+    conn.execute(
+        "CREATE TRIGGER neg BEFORE INSERT ON t WHEN NEW.n < 0 \
+         BEGIN SELECT RAISE(FAIL, 'negative'); END;",
+        &[],
+    )
+    .unwrap();
+    assert!(conn
+        .execute("INSERT INTO t (n) VALUES (1), (-1), (2);", &[])
+        .is_err());
+    assert_eq!(numbers(&mut conn), vec![vec![Value::Integer(1)]]);
+}
+
+#[test]
+fn raise_rollback_ends_the_transaction() {
+    let mut conn = open();
+    conn.execute(
+        "CREATE TRIGGER neg BEFORE INSERT ON t WHEN NEW.n < 0 \
+         BEGIN SELECT RAISE(ROLLBACK, 'negative'); END;",
+        &[],
+    )
+    .unwrap();
+    conn.execute("BEGIN;", &[]).unwrap();
+    conn.execute("INSERT INTO t (n) VALUES (1);", &[]).unwrap();
+    assert!(conn.execute("INSERT INTO t (n) VALUES (-1);", &[]).is_err());
+    assert!(conn.execute("COMMIT;", &[]).is_err());
+    assert!(numbers(&mut conn).is_empty());
+}
+
+#[test]
+fn instead_of_triggers_make_views_writable() {
+    let mut conn = open();
+    for sql in [
+        "CREATE VIEW v AS SELECT id, n FROM t;",
+        "CREATE TRIGGER io INSTEAD OF INSERT ON v \
+         BEGIN INSERT INTO t (n) VALUES (NEW.n * 10); END;",
+        "INSERT INTO v (id, n) VALUES (NULL, 3);",
+    ] {
+        conn.execute(sql, &[]).unwrap();
+    }
+    assert_eq!(numbers(&mut conn), vec![vec![Value::Integer(30)]]);
+
+    conn.execute("DROP TRIGGER io;", &[]).unwrap();
+    assert!(conn
+        .execute("INSERT INTO v (id, n) VALUES (NULL, 3);", &[])
+        .is_err());
+    assert!(conn
+        .execute("DROP TRIGGER io;", &[])
+        .unwrap_err()
+        .to_string()
+        .ends_with("no such trigger: io"));
+    conn.execute("DROP TRIGGER IF EXISTS io;", &[]).unwrap();
+}
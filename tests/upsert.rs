@@ -0,0 +1,148 @@
+mod common;
+
+use common::query;
+use rustdb::{Connection, Value};
+
+fn open() -> Connection {
+    let mut conn = Connection::open(":memory:").unwrap();
+    conn.execute(
+        "CREATE TABLE t (id INTEGER PRIMARY KEY, code TEXT UNIQUE, n INTEGER);",
+        &[],
+    )
+    .unwrap();
+    conn.execute("INSERT INTO t VALUES (1, 'a', 10), (2, 'b', 20);", &[])
+        .unwrap();
+    conn
+}
+
+fn rows(conn: &mut Connection) -> Vec<(i64, String, i64)> {
+    query(conn, "SELECT id, code, n FROM t;")
+        .into_iter()
+        .map(|row| match &row[..] {
+            [Value::Integer(id), Value::Text(code), Value::Integer(n)] => {
+                (*id, code.to_string(), *n)
+            }
+            row => panic!("unexpected row: {:?}", row),
+        })
+        .collect()
+}
+
+fn row(id: i64, code: &str, n: i64) -> (i64, String, i64) {
+    (id, code.to_string(), n)
+}
+
+#[test]
+fn or_ignore_skips_conflicting_rows() {
+    let mut conn = open();
+    let changes = conn
+        .execute(
+            "INSERT OR IGNORE INTO t VALUES (1, 'z', 0), (3, 'c', 30), (4, 'a', 0);",
+            &[],
+        )
+        .unwrap();
+    assert_eq!(changes, 1);
+    assert_eq!(
+        rows(&mut conn),
+        [row(1, "a", 10), row(2, "b", 20), row(3, "c", 30)]
+    );
+}
+
+#[test]
+fn or_replace_deletes_every_conflicting_row() {
+    let mut conn = open();
+    conn.execute("INSERT OR REPLACE INTO t VALUES (1, 'b', 99);", &[])
+        .unwrap();
+    assert_eq!(rows(&mut conn), [row(1, "b", 99)]);
+}
+
+#[test]
+fn or_abort_undoes_the_statement() {
+    let mut conn = open();
+    conn.execute("BEGIN;", &[]).unwrap();
+    conn.execute("INSERT INTO t VALUES (3, 'c', 30);", &[])
+        .unwrap();
+    assert!(conn
+        .execute(
+            "INSERT OR ABORT INTO t VALUES (4, 'd', 40), (5, 'a', 50);",
+            &[]
+        )
+        .is_err());
+    conn.execute("COMMIT;", &[]).unwrap();
+    assert_eq!(
+        rows(&mut conn),
+        [row(1, "a", 10), row(2, "b", 20), row(3, "c", 30)]
+    );
+}
+
+#[test]
+fn or_fail_keeps_the_rows_before_the_conflict() {
+    let mut conn = open();
+    assert!(conn
+        .execute(
+            "INSERT OR FAIL INTO t VALUES (3, 'c', 30), (4, 'a', 40), (5, 'e', 50);",
+            &[]
+        )
+        .is_err());
+    assert_eq!(
+        rows(&mut conn),
+        [row(1, "a", 10), row(2, "b", 20), row(3, "c", 30)]
+    );
+}
+
+#[test]
+fn or_rollback_ends_the_transaction() {
+    let mut conn = open();
+    conn.execute("BEGIN;", &[]).unwrap();
+    conn.execute("INSERT INTO t VALUES (3, 'c', 30);", &[])
+        .unwrap();
+    assert!(conn
+        .execute("INSERT OR ROLLBACK INTO t VALUES (4, 'a', 40);", &[])
+        .is_err());
+    assert!(conn.execute("COMMIT;", &[]).is_err());
+    assert_eq!(rows(&mut conn), [row(1, "a", 10), row(2, "b", 20)]);
+}
+
+#[test]
+fn on_conflict_do_nothing_skips_conflicting_rows() {
+    let mut conn = open();
+    conn.execute(
+        "INSERT INTO t VALUES (3, 'a', 30), (4, 'd', 40) ON CONFLICT (code) DO NOTHING;",
+        &[],
+    )
+    .unwrap();
+    assert_eq!(
+        rows(&mut conn),
+        [row(1, "a", 10), row(2, "b", 20), row(4, "d", 40)]
+    );
+    // Conflicts on other constraints still fail
+    assert!(conn
+        .execute(
+            "INSERT INTO t VALUES (1, 'z', 0) ON CONFLICT (code) DO NOTHING;",
+            &[]
+        )
+        .is_err());
+}
+
+#[test]
+fn on_conflict_do_update_reads_excluded_values() {
+    let mut conn = open();
+    conn.execute(
+        "INSERT INTO t (id, code, n) VALUES (3, 'a', 5), (4, 'c', 7) \
+         ON CONFLICT (code) DO UPDATE SET n = n + excluded.n WHERE excluded.n > 1;",
+        &[],
+    )
+    .unwrap();
+    assert_eq!(
+        rows(&mut conn),
+        [row(1, "a", 15), row(2, "b", 20), row(4, "c", 7)]
+    );
+
+    // The WHERE clause of DO UPDATE leaves the row as it is
+    conn.execute(
+        "INSERT INTO t (id, code, n) VALUES (5, 'b', 0) \
+         ON CONFLICT (code) DO UPDATE SET n = excluded.n WHERE excluded.n > 1;",
+        &[],
+    )
+    .unwrap();
+    assert_eq!(rows(&mut conn)[1], row(2, "b", 20));
+}
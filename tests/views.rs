@@ -0,0 +1,94 @@
+mod common;
+
+use common::query;
+use rustdb::{Connection, Value};
+
+fn open() -> Connection {
+    let mut conn = Connection::open(":memory:").unwrap();
+    for sql in [
+        "CREATE TABLE a (id INTEGER PRIMARY KEY, name TEXT);",
+        "CREATE TABLE b (id INTEGER PRIMARY KEY, a_id INTEGER, n INTEGER);",
+        "INSERT INTO a VALUES (1, 'x'), (2, 'y');",
+        "INSERT INTO b VALUES (1, 1, 10), (2, 1, 20), (3, 2, 5);",
+    ] {
+        conn.execute(sql, &[]).unwrap();
+    }
+    conn
+}
+
+/// Returns the error of a statement that must fail
+fn error(conn: &mut Connection, sql: &str) -> String {
+    conn.execute(sql, &[]).unwrap_err().to_string()
+}
+
+#[test]
+fn views_over_joins_can_be_filtered() {
+    let mut conn = open();
+    conn.execute(
+        "CREATE VIEW j AS SELECT a.name, b.n FROM a JOIN b ON a.id = b.a_id;",
+        &[],
+    )
+    .unwrap();
+    let rows = conn.query("SELECT * FROM j WHERE n > 6;", &[]).unwrap();
+    assert_eq!(rows.columns(), ["name", "n"]);
+    assert_eq!(
+        rows.collect::<Vec<_>>(),
+        vec![
+            vec![Value::Text("x".into()), Value::Integer(10)],
+            vec![Value::Text("x".into()), Value::Integer(20)],
+        ]
+    );
+    // Views read the tables as they are when queried
+    conn.execute("INSERT INTO b VALUES (4, 2, 7);", &[])
+        .unwrap();
+    assert_eq!(query(&mut conn, "SELECT n FROM j WHERE n > 6;").len(), 3);
+}
+
+#[test]
+fn views_over_aggregates_take_column_names() {
+    let mut conn = open();
+    conn.execute(
+        "CREATE VIEW s (name, total) AS \
+         SELECT a.name, sum(b.n) FROM a JOIN b ON a.id = b.a_id GROUP BY a.name;",
+        &[],
+    )
+    .unwrap();
+    assert_eq!(
+        query(&mut conn, "SELECT name, total FROM s ORDER BY total;"),
+        vec![
+            vec![Value::Text("y".into()), Value::Integer(5)],
+            vec![Value::Text("x".into()), Value::Integer(30)],
+        ]
+    );
+}
+
+#[test]
+fn views_cannot_be_modified() {
+    let mut conn = open();
+    conn.execute("CREATE VIEW v AS SELECT name FROM a;", &[])
+        .unwrap();
+    assert!(error(&mut conn, "INSERT INTO v VALUES ('z');")
+        .ends_with("cannot modify v because it is a view"));
+    assert!(
+        error(&mut conn, "CREATE VIEW v AS SELECT id FROM b;").ends_with("view v already exists")
+    );
+}
+
+#[test]
+fn dropping_views_and_their_tables() {
+    let mut conn = open();
+    conn.execute("CREATE VIEW v AS SELECT n FROM b;", &[])
+        .unwrap();
+    conn.execute("DROP TABLE b;", &[]).unwrap();
+    assert!(conn
+        .query("SELECT * FROM v;", &[])
+        .unwrap_err()
+        .to_string()
+        .ends_with("no such table: b"));
+
+    assert!(error(&mut conn, "DROP VIEW a;").ends_with("use DROP TABLE to delete table a"));
+    conn.execute("DROP VIEW v;", &[]).unwrap();
+    assert!(conn.query("SELECT * FROM v;", &[]).is_err());
+    assert!(error(&mut conn, "DROP VIEW v;").ends_with("no such view: v"));
+    conn.execute("DROP VIEW IF EXISTS v;", &[]).unwrap();
+}